@@ -0,0 +1,72 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Benchmarks for reading records from tabix-indexed files.
+//!
+//! Run with `cargo +nightly bench --features nightly`.
+
+#![feature(test)]
+
+extern crate rust_htslib;
+extern crate tempdir;
+extern crate test;
+
+use rust_htslib::tbx::{self, Read, TabixConfig};
+use test::Bencher;
+
+/// Number of records in the benchmark file.
+const N_RECORDS: u32 = 100_000;
+
+/// Write a BED file with `N_RECORDS` records and return the temporary directory and the path.
+fn bed_file() -> (tempdir::TempDir, std::path::PathBuf) {
+    let tmp = tempdir::TempDir::new("rust-htslib").unwrap();
+    let path = tmp.path().join("bench.bed.gz");
+    let mut writer = tbx::Writer::from_path(&path, &TabixConfig::bed()).unwrap();
+    for i in 0..N_RECORDS {
+        let line = format!("chr1\t{}\t{}\tfeature{}\t{}\t+", i * 10, i * 10 + 5, i, i % 1000);
+        writer.write(line.as_bytes()).unwrap();
+    }
+    writer.close().unwrap();
+    (tmp, path)
+}
+
+#[bench]
+fn bench_records(b: &mut Bencher) {
+    let (_tmp, path) = bed_file();
+    let mut reader = tbx::Reader::from_path(&path).unwrap();
+    b.iter(|| {
+        reader.fetch_all().unwrap();
+        reader.records().map(|r| r.unwrap().len()).sum::<usize>()
+    });
+}
+
+#[bench]
+fn bench_read_reuse(b: &mut Bencher) {
+    let (_tmp, path) = bed_file();
+    let mut reader = tbx::Reader::from_path(&path).unwrap();
+    let mut record = Vec::new();
+    b.iter(|| {
+        reader.fetch_all().unwrap();
+        let mut total = 0;
+        while reader.read(&mut record).is_ok() {
+            total += record.len();
+        }
+        total
+    });
+}
+
+#[bench]
+fn bench_read_ref(b: &mut Bencher) {
+    let (_tmp, path) = bed_file();
+    let mut reader = tbx::Reader::from_path(&path).unwrap();
+    b.iter(|| {
+        reader.fetch_all().unwrap();
+        let mut total = 0;
+        while let Ok(record) = reader.read_ref() {
+            total += record.len();
+        }
+        total
+    });
+}
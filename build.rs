@@ -4,6 +4,7 @@
 // except according to those terms.
 
 extern crate bindgen;
+extern crate cc;
 extern crate fs_utils;
 
 use fs_utils::copy::copy_directory;
@@ -30,6 +31,8 @@ fn main() {
         panic!("failed to build htslib");
     }
 
+    cc::Build::new().file("wrapper.c").compile("wrapper");
+
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
         .generate_comments(false)
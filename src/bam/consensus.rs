@@ -0,0 +1,333 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of consensus sequences from pileups.
+//!
+//! This is a simple re-implementation of the "simple" mode of `samtools consensus`: for each
+//! column of the pileup, the bases of the overlapping reads are counted and the most frequent
+//! one is called if enough reads support it.  Positions without sufficient support are written
+//! as `N`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rust_htslib::bam::{self, consensus};
+//! use rust_htslib::prelude::*;
+//!
+//! let mut bam = bam::IndexedReader::from_path(&"test/test.bam").unwrap();
+//! let tid = bam.header().tid(b"CHROMOSOME_I").unwrap();
+//! bam.fetch((tid, 0, 100)).unwrap();
+//!
+//! let params = consensus::ConsensusParams::default();
+//! let cons = consensus::consensus(&mut bam, tid, 0, 100, &params).unwrap();
+//! print!("{}", String::from_utf8(cons.to_fasta(b"CHROMOSOME_I:1-100")).unwrap());
+//! ```
+
+use std::cmp;
+use std::collections::HashMap;
+
+use bam;
+use bam::pileup::{Indel, PileupError};
+
+/// Maximal PHRED quality written to FASTQ output.
+const MAX_QUAL: u32 = 93;
+
+/// Parameters for the consensus calling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsensusParams {
+    /// Minimal number of bases (after quality filtering) required for calling a base.
+    pub min_depth: u32,
+    /// Minimal base quality for a base to be counted.
+    pub min_base_qual: u8,
+    /// Minimal fraction of the (filtered) depth the called allele must reach.
+    pub min_allele_fraction: f64,
+    /// Whether to apply majority insertions and deletions to the consensus.  Otherwise,
+    /// positions deleted in the majority of reads are reported as `missing_char`.
+    pub call_indels: bool,
+    /// Character to write for positions that cannot be called.
+    pub missing_char: u8,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        ConsensusParams {
+            min_depth: 1,
+            min_base_qual: 0,
+            min_allele_fraction: 0.5,
+            call_indels: true,
+            missing_char: b'N',
+        }
+    }
+}
+
+/// A consensus sequence together with per-base qualities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Consensus {
+    /// The consensus bases.
+    pub seq: Vec<u8>,
+    /// PHRED-scaled qualities of the consensus bases, without offset.
+    pub qual: Vec<u8>,
+}
+
+impl Consensus {
+    /// Return FASTA representation with the given sequence name, wrapped at 60 characters.
+    pub fn to_fasta(&self, name: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(b'>');
+        out.extend_from_slice(name);
+        out.push(b'\n');
+        for line in self.seq.chunks(60) {
+            out.extend_from_slice(line);
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// Return FASTQ representation with the given sequence name.
+    pub fn to_fastq(&self, name: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(b'@');
+        out.extend_from_slice(name);
+        out.push(b'\n');
+        out.extend_from_slice(&self.seq);
+        out.extend_from_slice(b"\n+\n");
+        out.extend(self.qual.iter().map(|q| q + 33));
+        out.push(b'\n');
+        out
+    }
+}
+
+/// Compute the consensus of the 0-based, half-open interval `[start, end)` on `tid`.
+///
+/// The pileup is generated from `reader`, so for an `IndexedReader` you should `fetch()` the
+/// region first.  Columns outside of the interval are skipped.
+///
+/// # Arguments
+///
+/// * `reader` - the reader to generate the pileup from
+/// * `tid` - the target ID of the region
+/// * `start` - 0-based start position of the region
+/// * `end` - 0-based, exclusive end position of the region
+/// * `params` - thresholds to use for calling
+pub fn consensus<R: bam::Read>(
+    reader: &mut R,
+    tid: u32,
+    start: u32,
+    end: u32,
+    params: &ConsensusParams,
+) -> Result<Consensus, ConsensusError> {
+    if start > end {
+        return Err(ConsensusError::InvalidRegion);
+    }
+
+    let mut result = Consensus {
+        seq: Vec::new(),
+        qual: Vec::new(),
+    };
+    // next position to write, used for filling uncovered positions
+    let mut next_pos = start;
+    for pileup in reader.pileup() {
+        let pileup = try!(pileup);
+        if pileup.tid() < tid || (pileup.tid() == tid && pileup.pos() < start) {
+            continue;
+        } else if pileup.tid() > tid || pileup.pos() >= end {
+            break;
+        }
+
+        while next_pos < pileup.pos() {
+            result.seq.push(params.missing_char);
+            result.qual.push(0);
+            next_pos += 1;
+        }
+        next_pos = pileup.pos() + 1;
+
+        let mut column = Column::new();
+        for alignment in pileup.alignments() {
+            if alignment.is_refskip() {
+                continue;
+            }
+            let record = alignment.record();
+            match alignment.qpos() {
+                Some(qpos) => {
+                    let qual = record.qual()[qpos];
+                    if qual < params.min_base_qual {
+                        continue;
+                    }
+                    column.add(record.seq()[qpos], qual);
+                }
+                None => column.add(b'*', 0),
+            }
+            if let (Indel::Ins(len), Some(qpos)) = (alignment.indel(), alignment.qpos()) {
+                let seq = record.seq();
+                let inserted = (qpos + 1..qpos + 1 + len as usize)
+                    .map(|i| seq[i])
+                    .collect();
+                *column.insertions.entry(inserted).or_insert(0) += 1;
+            }
+        }
+
+        match column.call(params) {
+            Some((b'*', _)) if params.call_indels => (),
+            Some((base, qual)) if base != b'*' => {
+                result.seq.push(base);
+                result.qual.push(qual);
+            }
+            // majority deletions are reported as missing if indels are not called
+            _ => {
+                result.seq.push(params.missing_char);
+                result.qual.push(0);
+            }
+        }
+
+        if params.call_indels {
+            if let Some(inserted) = column.call_insertion(params) {
+                result.qual.extend(inserted.iter().map(|_| 0));
+                result.seq.extend(inserted);
+            }
+        }
+    }
+
+    while next_pos < end {
+        result.seq.push(params.missing_char);
+        result.qual.push(0);
+        next_pos += 1;
+    }
+
+    Ok(result)
+}
+
+/// Base counts of a pileup column.
+struct Column {
+    /// Number of bases (or `*` for deletions) and sum of their qualities.
+    counts: HashMap<u8, (u32, u32)>,
+    /// Total number of counted bases.
+    depth: u32,
+    /// Inserted sequences starting after this column.
+    insertions: HashMap<Vec<u8>, u32>,
+}
+
+impl Column {
+    fn new() -> Self {
+        Column {
+            counts: HashMap::new(),
+            depth: 0,
+            insertions: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, base: u8, qual: u8) {
+        let entry = self.counts.entry(base.to_ascii_uppercase()).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += qual as u32;
+        self.depth += 1;
+    }
+
+    /// Call the base of the column, returning base and quality.
+    fn call(&self, params: &ConsensusParams) -> Option<(u8, u8)> {
+        if self.depth == 0 || self.depth < params.min_depth {
+            return None;
+        }
+        // break ties by base value to yield deterministic results
+        let (&base, &(count, qual_sum)) = self.counts
+            .iter()
+            .max_by_key(|&(&base, &(count, _))| (count, cmp::Reverse(base)))
+            .unwrap();
+        if (count as f64) < params.min_allele_fraction * self.depth as f64 {
+            return None;
+        }
+        let total_qual: u32 = self.counts.values().map(|&(_, q)| q).sum();
+        let qual = qual_sum.saturating_sub(total_qual - qual_sum);
+        Some((base, cmp::min(qual, MAX_QUAL) as u8))
+    }
+
+    /// Call the insertion after the column, if any.
+    fn call_insertion(&self, params: &ConsensusParams) -> Option<&Vec<u8>> {
+        if self.depth == 0 || self.depth < params.min_depth {
+            return None;
+        }
+        self.insertions
+            .iter()
+            .max_by_key(|&(seq, &count)| (count, cmp::Reverse(seq)))
+            .and_then(|(seq, &count)| {
+                if (count as f64) < params.min_allele_fraction * self.depth as f64 {
+                    None
+                } else {
+                    Some(seq)
+                }
+            })
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ConsensusError {
+        InvalidRegion {
+            description("invalid region, start must not be greater than end")
+        }
+        PileupError(err: PileupError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consensus() {
+        let mut bam = bam::Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let params = ConsensusParams::default();
+
+        let cons = consensus(&mut bam, 0, 0, 27, &params).unwrap();
+        // position 0 is not covered by any read
+        assert_eq!(cons.seq, b"NCCTAGCCCTAACCCTAACCCTAACCC".to_vec());
+        assert_eq!(cons.qual.len(), cons.seq.len());
+        assert_eq!(cons.qual[0], 0);
+    }
+
+    #[test]
+    fn test_consensus_min_depth() {
+        let mut bam = bam::Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let mut params = ConsensusParams::default();
+        params.min_depth = 7;
+
+        let cons = consensus(&mut bam, 0, 1, 11, &params).unwrap();
+        assert_eq!(cons.seq, b"NNNNNNNNNN".to_vec());
+    }
+
+    #[test]
+    fn test_consensus_deletion() {
+        let mut params = ConsensusParams::default();
+        // all reads have a deletion at position 28
+        let mut bam = bam::Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let cons = consensus(&mut bam, 0, 27, 31, &params).unwrap();
+        assert_eq!(cons.seq, b"TAG".to_vec());
+
+        params.call_indels = false;
+        let mut bam = bam::Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let cons = consensus(&mut bam, 0, 27, 31, &params).unwrap();
+        assert_eq!(cons.seq, b"TNAG".to_vec());
+        assert_eq!(cons.qual[1], 0);
+    }
+
+    #[test]
+    fn test_to_fastx() {
+        let cons = Consensus {
+            seq: b"ACGT".to_vec(),
+            qual: vec![0, 1, 2, 3],
+        };
+        assert_eq!(cons.to_fasta(b"x"), b">x\nACGT\n".to_vec());
+        assert_eq!(cons.to_fastq(b"x"), b"@x\nACGT\n+\n!\"#$\n".to_vec());
+    }
+}
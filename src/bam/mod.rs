@@ -6,9 +6,11 @@
 //! Module for working with BAM files.
 
 pub mod buffer;
+pub mod consensus;
 pub mod header;
 pub mod pileup;
 pub mod record;
+pub mod stats;
 
 #[cfg(feature = "serde")]
 pub mod record_serde;
@@ -92,6 +94,18 @@ pub trait Read: Sized {
     fn set_threads(&mut self, n_threads: usize) -> Result<(), ThreadingError> {
         set_threads(self.bgzf(), n_threads)
     }
+
+    /// Set the size of the cache for decompressed BGZF blocks.
+    ///
+    /// This is mainly useful for random access to remote files (e.g., opened with `from_url`),
+    /// where a larger cache avoids fetching the same blocks again for nearby regions.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_size` - the cache size in bytes
+    fn set_cache_size(&mut self, cache_size: usize) {
+        unsafe { htslib::bgzf_set_cache_size(self.bgzf(), cache_size as i32) }
+    }
 }
 
 /// A BAM reader.
@@ -215,6 +229,62 @@ impl Drop for Reader {
     }
 }
 
+/// Region to fetch from an `IndexedReader`.
+///
+/// All coordinates are 0-based, end coordinates are exclusive.  Usually, you will not construct
+/// this directly but pass one of the types with a `From` implementation to `fetch()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchDefinition<'a> {
+    /// Interval on the contig with the given target ID.
+    Region(u32, u32, u32),
+    /// Interval on the contig with the given name.
+    RegionString(&'a [u8], u32, u32),
+    /// The complete contig with the given target ID.
+    CompleteTid(u32),
+    /// A contig name or samtools-style region string, e.g. `chr1:1,000-2,000`.
+    String(&'a [u8]),
+    /// All reads, including unmapped ones.
+    All,
+    /// Only unmapped reads without coordinates, stored at the end of the file.
+    Unmapped,
+}
+
+impl<'a> From<(u32, u32, u32)> for FetchDefinition<'a> {
+    fn from(region: (u32, u32, u32)) -> Self {
+        FetchDefinition::Region(region.0, region.1, region.2)
+    }
+}
+
+impl<'a> From<(&'a [u8], u32, u32)> for FetchDefinition<'a> {
+    fn from(region: (&'a [u8], u32, u32)) -> Self {
+        FetchDefinition::RegionString(region.0, region.1, region.2)
+    }
+}
+
+impl<'a> From<(&'a str, u32, u32)> for FetchDefinition<'a> {
+    fn from(region: (&'a str, u32, u32)) -> Self {
+        FetchDefinition::RegionString(region.0.as_bytes(), region.1, region.2)
+    }
+}
+
+impl<'a> From<u32> for FetchDefinition<'a> {
+    fn from(tid: u32) -> Self {
+        FetchDefinition::CompleteTid(tid)
+    }
+}
+
+impl<'a> From<&'a [u8]> for FetchDefinition<'a> {
+    fn from(region: &'a [u8]) -> Self {
+        FetchDefinition::String(region)
+    }
+}
+
+impl<'a> From<&'a str> for FetchDefinition<'a> {
+    fn from(region: &'a str) -> Self {
+        FetchDefinition::String(region.as_bytes())
+    }
+}
+
 #[derive(Debug)]
 pub struct IndexedReader {
     bgzf: *mut htslib::BGZF,
@@ -265,13 +335,49 @@ impl IndexedReader {
         }
     }
 
-    pub fn fetch(&mut self, tid: u32, beg: u32, end: u32) -> Result<(), FetchError> {
-        if let Some(itr) = self.itr {
+    /// Fetch reads from the given region, afterwards `read()`, `records()` and `pileup()` are
+    /// constrained to it.
+    ///
+    /// The region can be given in several ways, see `FetchDefinition`:
+    ///
+    /// * `(tid, start, end)` or `(name, start, end)` - 0-based, half-open interval on a contig
+    /// * `tid` - complete contig
+    /// * `name` - complete contig or a samtools-style region string like `chr1:1,000-2,000`
+    /// * `FetchDefinition::All` - all reads, including unmapped ones
+    /// * `FetchDefinition::Unmapped` - only unmapped reads without coordinates
+    pub fn fetch<'a, T: Into<FetchDefinition<'a>>>(&mut self, def: T) -> Result<(), FetchError> {
+        if let Some(itr) = self.itr.take() {
             unsafe { htslib::hts_itr_destroy(itr) }
         }
-        let itr = unsafe { htslib::sam_itr_queryi(self.idx, tid as i32, beg as i32, end as i32) };
+        let itr = match def.into() {
+            FetchDefinition::Region(tid, beg, end) => unsafe {
+                htslib::sam_itr_queryi(self.idx, tid as i32, beg as i32, end as i32)
+            },
+            FetchDefinition::RegionString(name, beg, end) => {
+                let tid = try!(self.header.tid(name).ok_or(FetchError::UnknownContig));
+                unsafe { htslib::sam_itr_queryi(self.idx, tid as i32, beg as i32, end as i32) }
+            }
+            FetchDefinition::CompleteTid(tid) => unsafe {
+                htslib::sam_itr_queryi(self.idx, tid as i32, 0, i32::max_value())
+            },
+            FetchDefinition::String(region) => {
+                let region = try!(ffi::CString::new(region).map_err(|_| FetchError::Some));
+                unsafe { htslib::sam_itr_querys(self.idx, self.header.inner, region.as_ptr()) }
+            }
+            FetchDefinition::All => unsafe {
+                htslib::sam_itr_queryi(self.idx, htslib::HTS_IDX_START, 0, 0)
+            },
+            FetchDefinition::Unmapped => unsafe {
+                // htslib fails to seek past the last mapped read if no unmapped reads follow
+                let tid = if htslib::hts_idx_get_n_no_coor(self.idx) == 0 {
+                    htslib::HTS_IDX_NONE
+                } else {
+                    htslib::HTS_IDX_NOCOOR
+                };
+                htslib::sam_itr_queryi(self.idx, tid, 0, 0)
+            },
+        };
         if itr.is_null() {
-            self.itr = None;
             Err(FetchError::Some)
         } else {
             self.itr = Some(itr);
@@ -610,6 +716,9 @@ quick_error! {
         Some {
             description("error fetching a locus")
         }
+        UnknownContig {
+            description("unknown contig name")
+        }
     }
 }
 
@@ -786,11 +895,13 @@ impl Drop for HeaderView {
 mod tests {
     extern crate tempdir;
     use super::header::HeaderRecord;
-    use super::record::{Aux, Cigar, CigarString};
+    use super::record::{Aux, AuxArray, Cigar, CigarString};
     use super::*;
     use std::collections::HashMap;
     use std::path::Path;
     use std::str;
+    use std::sync::Arc;
+    use std::thread;
 
     fn gold() -> (
         [&'static [u8]; 6],
@@ -948,13 +1059,13 @@ CCCCCCCCCCCCCCCCCCC"[..],
         assert!(bam.header.target_len(tid).expect("Expected target len.") == 15072423);
 
         // fetch to position containing reads
-        bam.fetch(tid, 0, 2)
+        bam.fetch((tid, 0, 2))
             .ok()
             .expect("Expected successful fetch.");
         assert!(bam.records().count() == 6);
 
         // compare reads
-        bam.fetch(tid, 0, 2)
+        bam.fetch((tid, 0, 2))
             .ok()
             .expect("Expected successful fetch.");
         for (i, record) in bam.records().enumerate() {
@@ -971,10 +1082,44 @@ CCCCCCCCCCCCCCCCCCC"[..],
         }
 
         // fetch to empty position
-        bam.fetch(2, 1, 1).ok().expect("Expected successful fetch.");
+        bam.fetch((2, 1, 1)).ok().expect("Expected successful fetch.");
         assert!(bam.records().count() == 0);
     }
 
+    #[test]
+    fn test_fetch_definitions() {
+        let mut bam = IndexedReader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Expected valid index.");
+
+        bam.fetch(0).unwrap();
+        assert_eq!(bam.records().count(), 6);
+        bam.fetch("CHROMOSOME_I").unwrap();
+        assert_eq!(bam.records().count(), 6);
+        bam.fetch(&b"CHROMOSOME_I:1-2"[..]).unwrap();
+        assert_eq!(bam.records().count(), 6);
+        bam.fetch(("CHROMOSOME_I", 0, 2)).unwrap();
+        assert_eq!(bam.records().count(), 6);
+        bam.fetch(("CHROMOSOME_II", 0, 2)).unwrap();
+        assert_eq!(bam.records().count(), 0);
+        bam.fetch(FetchDefinition::All).unwrap();
+        assert_eq!(bam.records().count(), 6);
+        bam.fetch(FetchDefinition::Unmapped).unwrap();
+        assert_eq!(bam.records().count(), 0);
+
+        assert!(bam.fetch(("CHROMOSOME_X", 0, 2)).is_err());
+    }
+
+    #[test]
+    fn test_set_cache_size() {
+        let mut bam = IndexedReader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Expected valid index.");
+        bam.set_cache_size(10 * 1024 * 1024);
+        bam.fetch((0, 0, 2)).unwrap();
+        assert_eq!(bam.records().count(), 6);
+    }
+
     #[test]
     fn test_set_record() {
         let (names, _, seqs, quals, cigars) = gold();
@@ -994,6 +1139,41 @@ CCCCCCCCCCCCCCCCCCC"[..],
         assert_eq!(rec.aux(b"NM").unwrap(), Aux::Integer(15));
     }
 
+    #[test]
+    fn test_aux_arrays() {
+        let (names, _, seqs, quals, cigars) = gold();
+
+        let mut rec = record::Record::new();
+        rec.set(names[0], &cigars[0], seqs[0], quals[0]);
+        let values_i16 = [-1i16, 300, -300];
+        let values_u32 = [1u32, 4_000_000_000];
+        let values_f32 = [0.5f32, -1.25];
+        rec.push_aux(b"XA", &Aux::ArrayI16(AuxArray::from(&values_i16[..])))
+            .unwrap();
+        rec.push_aux(b"XB", &Aux::ArrayU32(AuxArray::from(&values_u32[..])))
+            .unwrap();
+        rec.push_aux(b"XC", &Aux::ArrayFloat(AuxArray::from(&values_f32[..])))
+            .unwrap();
+        rec.push_aux(b"XH", &Aux::HexByteArray(b"1AE301")).unwrap();
+        rec.push_aux(b"XI", &Aux::Integer(4_000_000_000)).unwrap();
+
+        match rec.aux(b"XA").unwrap() {
+            Aux::ArrayI16(array) => assert_eq!(array.to_vec(), values_i16.to_vec()),
+            _ => panic!("expected i16 array"),
+        }
+        assert_eq!(
+            rec.aux(b"XB").unwrap(),
+            Aux::ArrayU32(AuxArray::from(&values_u32[..]))
+        );
+        assert_eq!(
+            rec.aux(b"XC").unwrap().to_sam_field(b"XC"),
+            "XC:B:f,0.5,-1.25"
+        );
+        assert_eq!(rec.aux(b"XH").unwrap().hex(), b"1AE301");
+        assert_eq!(rec.aux(b"XI").unwrap().to_u32().unwrap(), 4_000_000_000);
+        assert!(rec.aux(b"XI").unwrap().to_i32().is_err());
+    }
+
     #[test]
     fn test_set_qname() {
         let (names, _, seqs, quals, cigars) = gold();
@@ -1236,6 +1416,41 @@ CCCCCCCCCCCCCCCCCCC"[..],
         }
     }
 
+    #[test]
+    fn test_owned_pileup() {
+        let (_, _, seqs, _, _) = gold();
+
+        let mut bam = Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let pileups: Vec<pileup::OwnedPileup> = bam.pileup()
+            .owned()
+            .take(26)
+            .map(|p| p.ok().expect("Expected successful pileup."))
+            .collect();
+
+        // records are shared between columns
+        let first: Vec<_> = pileups[0].alignments().collect();
+        let last: Vec<_> = pileups[25].alignments().collect();
+        for (a, b) in first.iter().zip(last.iter()) {
+            assert!(Arc::ptr_eq(a.record(), b.record()));
+        }
+
+        // pileups can be moved to other threads
+        let handle = thread::spawn(move || {
+            for p in &pileups {
+                assert_eq!(p.depth(), 6);
+                let pos = p.pos() as usize;
+                for (i, a) in p.alignments().enumerate() {
+                    let qpos = a.qpos().unwrap();
+                    assert_eq!(qpos, pos - 1);
+                    assert_eq!(a.record().seq()[qpos], seqs[i][qpos]);
+                }
+            }
+        });
+        handle.join().unwrap();
+    }
+
     #[test]
     fn test_idx_pileup() {
         let mut bam = IndexedReader::from_path(&"test/test.bam")
@@ -1247,7 +1462,7 @@ CCCCCCCCCCCCCCCCCCC"[..],
         }
         // go back again
         let tid = bam.header().tid(b"CHROMOSOME_I").unwrap();
-        bam.fetch(tid, 0, 5).unwrap();
+        bam.fetch((tid, 0, 5)).unwrap();
         for p in bam.pileup() {
             println!("{}", p.unwrap().pos())
         }
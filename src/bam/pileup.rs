@@ -3,9 +3,11 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::collections::HashMap;
 use std::fmt;
 use std::iter;
 use std::slice;
+use std::sync::Arc;
 
 use htslib;
 
@@ -44,6 +46,18 @@ impl Pileup {
         self.inner().iter().map(Alignment::new)
     }
 
+    /// Copy the pileup, including its records, so that it can outlive the iterator.
+    ///
+    /// Use `Pileups::owned()` instead when converting many pileups, this shares the records
+    /// between the columns.
+    pub fn to_owned_pileup(&self) -> OwnedPileup {
+        OwnedPileup {
+            tid: self.tid,
+            pos: self.pos,
+            alignments: self.alignments().map(|a| a.to_owned_alignment()).collect(),
+        }
+    }
+
     fn inner(&self) -> &[htslib::bam_pileup1_t] {
         unsafe {
             slice::from_raw_parts(
@@ -113,6 +127,101 @@ impl<'a> Alignment<'a> {
     pub fn record(&self) -> record::Record {
         record::Record::from_inner(self.inner.b)
     }
+
+    /// Copy the alignment, including its record, so that it can outlive the pileup.
+    pub fn to_owned_alignment(&self) -> OwnedAlignment {
+        self.to_owned_with_record(Arc::new(self.record().clone()))
+    }
+
+    fn to_owned_with_record(&self, record: Arc<record::Record>) -> OwnedAlignment {
+        OwnedAlignment {
+            record: record,
+            qpos: self.qpos(),
+            indel: self.indel(),
+            is_del: self.is_del(),
+            is_head: self.is_head(),
+            is_tail: self.is_tail(),
+            is_refskip: self.is_refskip(),
+        }
+    }
+}
+
+/// A pileup over one genomic position that owns its alignments.
+///
+/// In contrast to `Pileup`, this can be retained while advancing the iterator or be sent to
+/// other threads.
+#[derive(Debug, Clone)]
+pub struct OwnedPileup {
+    tid: u32,
+    pos: u32,
+    alignments: Vec<OwnedAlignment>,
+}
+
+impl OwnedPileup {
+    pub fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    pub fn pos(&self) -> u32 {
+        self.pos
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.alignments.len() as u32
+    }
+
+    pub fn alignments(&self) -> slice::Iter<OwnedAlignment> {
+        self.alignments.iter()
+    }
+}
+
+/// An aligned read in a pileup, holding a reference-counted copy of its record.
+#[derive(Debug, Clone)]
+pub struct OwnedAlignment {
+    record: Arc<record::Record>,
+    qpos: Option<usize>,
+    indel: Indel,
+    is_del: bool,
+    is_head: bool,
+    is_tail: bool,
+    is_refskip: bool,
+}
+
+impl OwnedAlignment {
+    /// Position within the read. None if either `is_del` or `is_refskip`.
+    pub fn qpos(&self) -> Option<usize> {
+        self.qpos
+    }
+
+    /// Insertion, deletion (with length) if indel starts at next base or None otherwise.
+    pub fn indel(&self) -> Indel {
+        self.indel
+    }
+
+    /// Whether there is a deletion in the alignment at this position.
+    pub fn is_del(&self) -> bool {
+        self.is_del
+    }
+
+    /// Whether the alignment starts at this position.
+    pub fn is_head(&self) -> bool {
+        self.is_head
+    }
+
+    /// Whether the alignment ends at this position.
+    pub fn is_tail(&self) -> bool {
+        self.is_tail
+    }
+
+    /// Whether this position is marked as refskip in the CIGAR string.
+    pub fn is_refskip(&self) -> bool {
+        self.is_refskip
+    }
+
+    /// The corresponding record, shared with the other columns covered by the read.
+    pub fn record(&self) -> &Arc<record::Record> {
+        &self.record
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
@@ -143,6 +252,16 @@ impl<'a, R: bam::Read> Pileups<'a, R> {
             htslib::bam_plp_set_maxcnt(self.itr, depth as i32);
         }
     }
+
+    /// Turn into an iterator over `OwnedPileup`s.
+    ///
+    /// Each record is copied once and shared between all columns that it covers.
+    pub fn owned(self) -> OwnedPileups<'a, R> {
+        OwnedPileups {
+            pileups: self,
+            records: HashMap::new(),
+        }
+    }
 }
 
 impl<'a, R: bam::Read> Iterator for Pileups<'a, R> {
@@ -174,6 +293,76 @@ impl<'a, R: bam::Read> Drop for Pileups<'a, R> {
     }
 }
 
+/// Iterator over owned pileups, see `Pileups::owned()`.
+#[derive(Debug)]
+pub struct OwnedPileups<'a, R: 'a + bam::Read> {
+    pileups: Pileups<'a, R>,
+    /// Copies of the records in the current pileup column, by their address in the pileup
+    /// buffer.
+    records: HashMap<*const htslib::bam1_t, Arc<record::Record>>,
+}
+
+impl<'a, R: bam::Read> OwnedPileups<'a, R> {
+    /// Return shared copy of the record of the given alignment.
+    fn shared_record(
+        &self,
+        records: &mut HashMap<*const htslib::bam1_t, Arc<record::Record>>,
+        alignment: &Alignment,
+    ) -> Arc<record::Record> {
+        let key = alignment.inner.b as *const htslib::bam1_t;
+        let rec = alignment.record();
+        match self.records.get(&key) {
+            // the pileup buffer reuses memory, so check that it is still the same record
+            Some(shared) if shared.pos() == rec.pos() && shared.flags() == rec.flags()
+                && shared.qname() == rec.qname() =>
+            {
+                records.insert(key, shared.clone());
+                shared.clone()
+            }
+            _ => {
+                let shared = Arc::new(rec.clone());
+                records.insert(key, shared.clone());
+                shared
+            }
+        }
+    }
+}
+
+impl<'a, R: bam::Read> Iterator for OwnedPileups<'a, R> {
+    type Item = Result<OwnedPileup, PileupError>;
+
+    fn next(&mut self) -> Option<Result<OwnedPileup, PileupError>> {
+        let pileup = match self.pileups.next() {
+            Some(Ok(pileup)) => pileup,
+            Some(Err(e)) => return Some(Err(e)),
+            None => {
+                self.records.clear();
+                return None;
+            }
+        };
+
+        // only keep records of reads that are still in the pileup
+        let mut records = HashMap::new();
+        let alignments = pileup
+            .alignments()
+            .map(|a| {
+                let record = self.shared_record(&mut records, &a);
+                if a.is_tail() {
+                    records.remove(&(a.inner.b as *const htslib::bam1_t));
+                }
+                a.to_owned_with_record(record)
+            })
+            .collect();
+        self.records = records;
+
+        Some(Ok(OwnedPileup {
+            tid: pileup.tid(),
+            pos: pileup.pos(),
+            alignments: alignments,
+        }))
+    }
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum PileupError {
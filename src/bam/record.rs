@@ -3,6 +3,7 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cmp;
 use std::error::Error;
 use std::ffi;
 use std::fmt;
@@ -10,6 +11,7 @@ use std::ops;
 use std::slice;
 use std::str;
 use std::str::FromStr;
+use std::f32;
 use std::u32;
 
 use itertools::Itertools;
@@ -47,6 +49,10 @@ quick_error! {
             description("CIGAR operation not allowed at this point")
             display(x) -> ("{}: {}", x.description(), msg)
         }
+        UnexpectedTagType(tag: String) {
+            description("Aux tag holding a CIGAR string has unexpected type")
+            display(x) -> ("{}: {}", x.description(), tag)
+        }
     }
 }
 
@@ -447,11 +453,37 @@ impl Record {
                 }
                 b'f' | b'd' => Some(Aux::Float(htslib::bam_aux2f(aux))),
                 b'A' => Some(Aux::Char(htslib::bam_aux2A(aux) as u8)),
-                b'Z' | b'H' => {
+                b'Z' => {
                     let f = aux.offset(1) as *const i8;
                     let x = ffi::CStr::from_ptr(f).to_bytes();
                     Some(Aux::String(x))
                 }
+                b'H' => {
+                    let f = aux.offset(1) as *const i8;
+                    let x = ffi::CStr::from_ptr(f).to_bytes();
+                    Some(Aux::HexByteArray(x))
+                }
+                b'B' => {
+                    let len = htslib::bam_auxB_len(aux) as usize;
+                    // skip type, subtype and the 32 bit length
+                    let data = aux.offset(6);
+                    macro_rules! array {
+                        ($variant:ident, $t:ty) => {{
+                            let raw = slice::from_raw_parts(data, len * <$t>::size());
+                            Some(Aux::$variant(AuxArray::Raw(raw)))
+                        }};
+                    }
+                    match *aux.offset(1) {
+                        b'c' => array!(ArrayI8, i8),
+                        b'C' => array!(ArrayU8, u8),
+                        b's' => array!(ArrayI16, i16),
+                        b'S' => array!(ArrayU16, u16),
+                        b'i' => array!(ArrayI32, i32),
+                        b'I' => array!(ArrayU32, u32),
+                        b'f' => array!(ArrayFloat, f32),
+                        _ => None,
+                    }
+                }
                 _ => None,
             }
         }
@@ -459,23 +491,40 @@ impl Record {
 
     /// Add auxiliary data.
     /// push_aux() should never be called before set().
+    ///
+    /// Integers are written with type `i` if they fit into 32 bits and with type `I` if they only
+    /// fit into an unsigned 32 bit value.  Floats are written with type `f`.
     pub fn push_aux(&mut self, tag: &[u8], value: &Aux) -> Result<(), AuxWriteError> {
         let ctag = tag.as_ptr() as *mut i8;
         let ret = unsafe {
             match *value {
-                Aux::Integer(v) => htslib::bam_aux_append(
-                    self.inner,
-                    ctag,
-                    b'i' as i8,
-                    4,
-                    [v].as_mut_ptr() as *mut u8,
-                ),
+                Aux::Integer(v) => {
+                    if v >= i32::min_value() as i64 && v <= i32::max_value() as i64 {
+                        htslib::bam_aux_append(
+                            self.inner,
+                            ctag,
+                            b'i' as i8,
+                            4,
+                            [v as i32].as_mut_ptr() as *mut u8,
+                        )
+                    } else if v >= 0 && v <= u32::max_value() as i64 {
+                        htslib::bam_aux_append(
+                            self.inner,
+                            ctag,
+                            b'I' as i8,
+                            4,
+                            [v as u32].as_mut_ptr() as *mut u8,
+                        )
+                    } else {
+                        return Err(AuxWriteError::Some);
+                    }
+                }
                 Aux::Float(v) => htslib::bam_aux_append(
                     self.inner,
                     ctag,
                     b'f' as i8,
                     4,
-                    [v].as_mut_ptr() as *mut u8,
+                    [v as f32].as_mut_ptr() as *mut u8,
                 ),
                 Aux::Char(v) => htslib::bam_aux_append(
                     self.inner,
@@ -491,6 +540,20 @@ impl Record {
                     (v.len() + 1) as i32,
                     ffi::CString::new(v).unwrap().as_ptr() as *mut u8,
                 ),
+                Aux::HexByteArray(v) => htslib::bam_aux_append(
+                    self.inner,
+                    ctag,
+                    b'H' as i8,
+                    (v.len() + 1) as i32,
+                    ffi::CString::new(v).unwrap().as_ptr() as *mut u8,
+                ),
+                Aux::ArrayI8(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayU8(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayI16(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayU16(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayI32(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayU32(ref v) => push_aux_array(self.inner, ctag, v),
+                Aux::ArrayFloat(ref v) => push_aux_array(self.inner, ctag, v),
             }
         };
 
@@ -519,6 +582,82 @@ impl Record {
         }
     }
 
+    /// Get the CIGAR string of the mate from the `MC` tag, positioned at the mate position.
+    ///
+    /// Returns `Ok(None)` if the tag is not present and `CigarError::UnexpectedTagType` if it
+    /// does not hold a string.
+    pub fn mate_cigar(&self) -> Result<Option<CigarStringView>, CigarError> {
+        match self.aux(b"MC") {
+            Some(Aux::String(mc)) => {
+                Ok(Some(try!(CigarString::from_bytes(mc)).into_view(self.mpos())))
+            }
+            Some(_) => Err(CigarError::UnexpectedTagType("MC".to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Get (exclusive) end position of the mate alignment, computed from the `MC` tag.
+    ///
+    /// Returns `Ok(None)` if the tag is not present.
+    pub fn mate_end_pos(&self) -> Result<Option<i32>, CigarError> {
+        match try!(self.mate_cigar()) {
+            Some(cigar) => Ok(Some(try!(cigar.end_pos()))),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether both the read and its mate are mapped to the same contig.
+    fn is_pair_on_same_contig(&self) -> bool {
+        self.is_paired() && !self.is_unmapped() && !self.is_mate_unmapped()
+            && self.tid() == self.mtid()
+    }
+
+    /// Get the 0-based, half-open interval spanned by the read and its mate, using the `MC`
+    /// tag for the end of the mate.
+    ///
+    /// Returns `Ok(None)` if the read is not paired, one of the reads is unmapped, the reads are
+    /// on different contigs or the `MC` tag is missing.
+    pub fn fragment_span(&self) -> Result<Option<(i32, i32)>, CigarError> {
+        if !self.is_pair_on_same_contig() {
+            return Ok(None);
+        }
+        let mate_end = match try!(self.mate_end_pos()) {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+        let end = try!(self.cigar().end_pos());
+        Ok(Some((cmp::min(self.pos(), self.mpos()), cmp::max(end, mate_end))))
+    }
+
+    /// Classify the relative orientation of the read and its mate, like Picard's
+    /// `SamPairUtil.getPairOrientation()`.
+    ///
+    /// The end of the mate is taken from the `MC` tag if present, otherwise it is derived from
+    /// the insert size.  Returns `None` if the read is not paired, one of the reads is unmapped
+    /// or the reads are on different contigs.
+    pub fn pair_orientation(&self) -> Option<PairOrientation> {
+        if !self.is_pair_on_same_contig() {
+            return None;
+        }
+        if self.is_reverse() == self.is_mate_reverse() {
+            return Some(PairOrientation::Tandem);
+        }
+        let (forward_start, reverse_end) = if self.is_reverse() {
+            (self.mpos(), self.cigar().end_pos().unwrap_or(self.pos() + 1))
+        } else {
+            let mate_end = match self.mate_end_pos() {
+                Ok(Some(end)) => end,
+                _ => self.pos() + self.insert_size(),
+            };
+            (self.pos(), mate_end)
+        };
+        if forward_start < reverse_end - 1 {
+            Some(PairOrientation::FR)
+        } else {
+            Some(PairOrientation::RF)
+        }
+    }
+
     flag!(is_paired, set_paired, unset_paired, 1u16);
     flag!(is_proper_pair, set_proper_pair, unset_proper_pair, 2u16);
     flag!(is_unmapped, set_unmapped, unset_unmapped, 4u16);
@@ -558,6 +697,17 @@ impl Record {
     );
 }
 
+/// Relative orientation of the two reads of a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PairOrientation {
+    /// The forward read starts before the end of the reverse read (e.g., Illumina paired-end).
+    FR,
+    /// The reverse read ends before the start of the forward read (e.g., Illumina mate-pair).
+    RF,
+    /// Both reads are on the same strand.
+    Tandem,
+}
+
 impl Drop for Record {
     fn drop(&mut self) {
         if self.own {
@@ -566,13 +716,60 @@ impl Drop for Record {
     }
 }
 
+/// Append a `B` array aux field to the given record.
+unsafe fn push_aux_array<T: AuxArrayElement>(
+    inner: *mut htslib::bam1_t,
+    ctag: *mut i8,
+    array: &AuxArray<T>,
+) -> i32 {
+    let mut data = Vec::with_capacity(5 + array.len() * T::size());
+    data.push(T::type_code());
+    (array.len() as u32).write_le(&mut data);
+    for v in array.iter() {
+        v.write_le(&mut data);
+    }
+    htslib::bam_aux_append(inner, ctag, b'B' as i8, data.len() as i32, data.as_ptr())
+}
+
 /// Auxiliary record data.
+///
+/// The `Display` implementation yields the `TYPE:VALUE` part of a SAM aux field, use
+/// `Aux::to_sam_field()` for obtaining the complete field including the tag.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Aux<'a> {
     Integer(i64),
     String(&'a [u8]),
     Float(f64),
     Char(u8),
+    /// Byte array in hex format (`H`).
+    HexByteArray(&'a [u8]),
+    ArrayI8(AuxArray<'a, i8>),
+    ArrayU8(AuxArray<'a, u8>),
+    ArrayI16(AuxArray<'a, i16>),
+    ArrayU16(AuxArray<'a, u16>),
+    ArrayI32(AuxArray<'a, i32>),
+    ArrayU32(AuxArray<'a, u32>),
+    /// Array of floats. Note that BAM only supports single precision in arrays.
+    ArrayFloat(AuxArray<'a, f32>),
+}
+
+/// Generate checked conversion methods for integer aux values.
+macro_rules! aux_to_integer {
+    ($name:ident, $t:ident) => (
+        /// Convert integer aux data, checking for type and overflow.
+        pub fn $name(&self) -> Result<$t, AuxConversionError> {
+            match *self {
+                Aux::Integer(x) => {
+                    if x < $t::min_value() as i64 || (x > 0 && x as u64 > $t::max_value() as u64) {
+                        Err(AuxConversionError::Overflow)
+                    } else {
+                        Ok(x as $t)
+                    }
+                }
+                _ => Err(AuxConversionError::WrongType),
+            }
+        }
+    )
 }
 
 impl<'a> Aux<'a> {
@@ -604,11 +801,252 @@ impl<'a> Aux<'a> {
             _ => panic!("not a character"),
         }
     }
+
+    /// Get hex byte array from aux data (panics if not a hex byte array).
+    pub fn hex(&self) -> &'a [u8] {
+        match *self {
+            Aux::HexByteArray(x) => x,
+            _ => panic!("not a hex byte array"),
+        }
+    }
+
+    aux_to_integer!(to_i8, i8);
+    aux_to_integer!(to_u8, u8);
+    aux_to_integer!(to_i16, i16);
+    aux_to_integer!(to_u16, u16);
+    aux_to_integer!(to_i32, i32);
+    aux_to_integer!(to_u32, u32);
+    aux_to_integer!(to_i64, i64);
+
+    /// Convert integer aux data to `u64`, checking for type and sign.
+    pub fn to_u64(&self) -> Result<u64, AuxConversionError> {
+        match *self {
+            Aux::Integer(x) if x < 0 => Err(AuxConversionError::Overflow),
+            Aux::Integer(x) => Ok(x as u64),
+            _ => Err(AuxConversionError::WrongType),
+        }
+    }
+
+    /// Convert float aux data to `f32`, checking for type and overflow.
+    pub fn to_f32(&self) -> Result<f32, AuxConversionError> {
+        match *self {
+            Aux::Float(x) => {
+                if x.is_finite() && x.abs() > f32::MAX as f64 {
+                    Err(AuxConversionError::Overflow)
+                } else {
+                    Ok(x as f32)
+                }
+            }
+            _ => Err(AuxConversionError::WrongType),
+        }
+    }
+
+    /// Convert float aux data to `f64`, checking for type.
+    pub fn to_f64(&self) -> Result<f64, AuxConversionError> {
+        match *self {
+            Aux::Float(x) => Ok(x),
+            _ => Err(AuxConversionError::WrongType),
+        }
+    }
+
+    /// Return the SAM text representation of the aux field with the given tag, e.g.,
+    /// `NM:i:15`.
+    pub fn to_sam_field(&self, tag: &[u8]) -> String {
+        format!("{}:{}", String::from_utf8_lossy(tag), self)
+    }
+}
+
+impl<'a> fmt::Display for Aux<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Aux::Integer(x) => write!(fmt, "i:{}", x),
+            Aux::String(x) => write!(fmt, "Z:{}", String::from_utf8_lossy(x)),
+            Aux::Float(x) => write!(fmt, "f:{}", x),
+            Aux::Char(x) => write!(fmt, "A:{}", x as char),
+            Aux::HexByteArray(x) => write!(fmt, "H:{}", String::from_utf8_lossy(x)),
+            Aux::ArrayI8(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayU8(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayI16(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayU16(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayI32(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayU32(ref x) => write!(fmt, "B:{}", x),
+            Aux::ArrayFloat(ref x) => write!(fmt, "B:{}", x),
+        }
+    }
 }
 
 unsafe impl<'a> Send for Aux<'a> {}
 unsafe impl<'a> Sync for Aux<'a> {}
 
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum AuxConversionError {
+        WrongType {
+            description("aux data has a different type than requested")
+        }
+        Overflow {
+            description("aux data value does not fit into requested type")
+        }
+    }
+}
+
+/// Element types of `B` (array) aux fields.
+pub trait AuxArrayElement: Copy + PartialEq + fmt::Display {
+    /// Sub-type code of the array in BAM.
+    fn type_code() -> u8;
+
+    /// Size of one element in bytes.
+    fn size() -> usize;
+
+    /// Read value from the beginning of the given little endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Append value as little endian bytes to `buf`.
+    fn write_le(self, buf: &mut Vec<u8>);
+}
+
+macro_rules! aux_array_element {
+    ($t:ty, $ut:ty, $code:expr) => (
+        impl AuxArrayElement for $t {
+            fn type_code() -> u8 {
+                $code
+            }
+
+            fn size() -> usize {
+                ::std::mem::size_of::<$t>()
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                let mut v = 0u64;
+                for (i, b) in bytes[..Self::size()].iter().enumerate() {
+                    v |= (*b as u64) << (8 * i);
+                }
+                v as $ut as $t
+            }
+
+            fn write_le(self, buf: &mut Vec<u8>) {
+                let v = self as $ut as u64;
+                for i in 0..Self::size() {
+                    buf.push((v >> (8 * i)) as u8);
+                }
+            }
+        }
+    )
+}
+
+aux_array_element!(i8, u8, b'c');
+aux_array_element!(u8, u8, b'C');
+aux_array_element!(i16, u16, b's');
+aux_array_element!(u16, u16, b'S');
+aux_array_element!(i32, u32, b'i');
+aux_array_element!(u32, u32, b'I');
+
+impl AuxArrayElement for f32 {
+    fn type_code() -> u8 {
+        b'f'
+    }
+
+    fn size() -> usize {
+        4
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f32::from_bits(u32::read_le(bytes))
+    }
+
+    fn write_le(self, buf: &mut Vec<u8>) {
+        self.to_bits().write_le(buf)
+    }
+}
+
+/// Array of aux data, either borrowed from a BAM record or from a slice of values.
+#[derive(Debug, Clone, Copy)]
+pub enum AuxArray<'a, T: 'a + AuxArrayElement> {
+    /// Values given as a slice, e.g., for writing with `Record::push_aux()`.
+    Slice(&'a [T]),
+    /// Raw little endian data as stored in a BAM record.
+    Raw(&'a [u8]),
+}
+
+impl<'a, T: AuxArrayElement> AuxArray<'a, T> {
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        match *self {
+            AuxArray::Slice(s) => s.len(),
+            AuxArray::Raw(r) => r.len() / T::size(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the element at position `i`, if any.
+    pub fn get(&self, i: usize) -> Option<T> {
+        if i >= self.len() {
+            return None;
+        }
+        match *self {
+            AuxArray::Slice(s) => Some(s[i]),
+            AuxArray::Raw(r) => Some(T::read_le(&r[i * T::size()..])),
+        }
+    }
+
+    /// Iterate over the elements.
+    pub fn iter(&self) -> AuxArrayIter<'a, T> {
+        AuxArrayIter {
+            array: *self,
+            idx: 0,
+        }
+    }
+
+    /// Copy the elements into a vector.
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, T: AuxArrayElement> From<&'a [T]> for AuxArray<'a, T> {
+    fn from(s: &'a [T]) -> Self {
+        AuxArray::Slice(s)
+    }
+}
+
+impl<'a, 'b, T: AuxArrayElement> PartialEq<AuxArray<'b, T>> for AuxArray<'a, T> {
+    fn eq(&self, other: &AuxArray<'b, T>) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<'a, T: AuxArrayElement> fmt::Display for AuxArray<'a, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{}", T::type_code() as char));
+        for v in self.iter() {
+            try!(write!(fmt, ",{}", v));
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the elements of an `AuxArray`.
+#[derive(Debug)]
+pub struct AuxArrayIter<'a, T: 'a + AuxArrayElement> {
+    array: AuxArray<'a, T>,
+    idx: usize,
+}
+
+impl<'a, T: AuxArrayElement> Iterator for AuxArrayIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let v = self.array.get(self.idx);
+        if v.is_some() {
+            self.idx += 1;
+        }
+        v
+    }
+}
+
 static DECODE_BASE: &'static [u8] = b"=ACMGRSVTWYHKDBN";
 static ENCODE_BASE: [u8; 256] = [
     15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
@@ -1044,6 +1482,88 @@ impl fmt::Display for CigarStringView {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_aux_display_and_conversion() {
+        assert_eq!(Aux::Integer(15).to_sam_field(b"NM"), "NM:i:15");
+        assert_eq!(Aux::String(b"foo").to_sam_field(b"XA"), "XA:Z:foo");
+        assert_eq!(Aux::Char(b'x').to_string(), "A:x");
+        let values = [-1i8, 2];
+        assert_eq!(Aux::ArrayI8(AuxArray::from(&values[..])).to_string(), "B:c,-1,2");
+        let raw = [0x01u8, 0x00, 0xff, 0xff];
+        assert_eq!(AuxArray::<i16>::Raw(&raw[..]).to_vec(), vec![1i16, -1]);
+
+        assert_eq!(Aux::Integer(-128).to_i8().unwrap(), -128);
+        assert!(Aux::Integer(128).to_i8().is_err());
+        assert!(Aux::Integer(-1).to_u16().is_err());
+        assert!(Aux::Integer(-1).to_u64().is_err());
+        assert_eq!(Aux::Integer(-1).to_i64().unwrap(), -1);
+        assert!(Aux::Float(1.0).to_i32().is_err());
+        assert!(Aux::Float(1e300).to_f32().is_err());
+        assert_eq!(Aux::Float(0.5).to_f32().unwrap(), 0.5);
+    }
+
+    fn paired_record(pos: i32, reverse: bool, mpos: i32, mate_reverse: bool) -> Record {
+        let mut rec = Record::new();
+        rec.set(
+            b"pair",
+            &CigarString(vec![Cigar::Match(10), Cigar::SoftClip(2)]),
+            b"ACGTACGTACGT",
+            &[30; 12],
+        );
+        rec.set_paired();
+        rec.set_tid(0);
+        rec.set_pos(pos);
+        rec.set_mtid(0);
+        rec.set_mpos(mpos);
+        if reverse {
+            rec.set_reverse();
+        }
+        if mate_reverse {
+            rec.set_mate_reverse();
+        }
+        rec
+    }
+
+    #[test]
+    fn test_mate_cigar() {
+        let mut rec = paired_record(100, false, 150, true);
+        assert_eq!(rec.mate_cigar().unwrap(), None);
+        assert_eq!(rec.fragment_span().unwrap(), None);
+
+        rec.push_aux(b"MC", &Aux::String(b"5S20M")).unwrap();
+        assert_eq!(
+            rec.mate_cigar().unwrap().unwrap(),
+            CigarString(vec![Cigar::SoftClip(5), Cigar::Match(20)]).into_view(150)
+        );
+        assert_eq!(rec.mate_end_pos().unwrap(), Some(170));
+        assert_eq!(rec.fragment_span().unwrap(), Some((100, 170)));
+
+        let mut rec = paired_record(100, false, 150, true);
+        rec.push_aux(b"MC", &Aux::Integer(20)).unwrap();
+        match rec.mate_cigar() {
+            Err(CigarError::UnexpectedTagType(ref tag)) if tag == "MC" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_pair_orientation() {
+        let mut rec = paired_record(100, false, 150, true);
+        rec.push_aux(b"MC", &Aux::String(b"20M")).unwrap();
+        assert_eq!(rec.pair_orientation(), Some(PairOrientation::FR));
+
+        // reverse read ends before the forward mate starts
+        let rec = paired_record(100, true, 150, false);
+        assert_eq!(rec.pair_orientation(), Some(PairOrientation::RF));
+
+        let rec = paired_record(100, true, 150, true);
+        assert_eq!(rec.pair_orientation(), Some(PairOrientation::Tandem));
+
+        let mut rec = paired_record(100, false, 150, true);
+        rec.set_mate_unmapped();
+        assert_eq!(rec.pair_orientation(), None);
+    }
+
     #[test]
     fn test_cigar_string() {
         let cigar = CigarString(vec![Cigar::Match(100), Cigar::SoftClip(10)]);
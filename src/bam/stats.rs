@@ -0,0 +1,326 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Library QC statistics computed from BAM files.
+//!
+//! Currently, this module provides GC bias and coverage uniformity metrics in the spirit of
+//! Picard's `CollectGcBiasMetrics`: the genome is split into windows of fixed size, each window
+//! is assigned to a bin by its GC content in the reference and the read starts falling into the
+//! window are counted.  The normalized coverage per GC bin is the mean number of reads per
+//! window in the bin divided by the mean number of reads over all windows.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rust_htslib::{bam, faidx};
+//! use rust_htslib::bam::stats;
+//!
+//! let mut bam = bam::Reader::from_path(&"sample.bam").unwrap();
+//! let fasta = faidx::Reader::from_path(&"ref.fa").unwrap();
+//! let result = stats::gc_bias(&mut bam, &fasta, &stats::GcBiasParams::default()).unwrap();
+//! for bin in &result.bins {
+//!     println!("{}\t{}\t{}\t{}", bin.gc, bin.windows, bin.reads, bin.normalized_coverage);
+//! }
+//! ```
+
+use std::cmp;
+
+use bam;
+use bam::ReadError;
+use faidx;
+
+/// Number of windows to load from the reference at once.
+const CHUNK_WINDOWS: u32 = 1000;
+
+/// Parameters for computing GC bias statistics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcBiasParams {
+    /// Size of the windows to bin the genome into.
+    pub window_size: u32,
+    /// Minimal mapping quality for a read to be counted.
+    pub min_mapq: u8,
+    /// Windows with a larger fraction of `N` characters in the reference are ignored.
+    pub max_n_fraction: f64,
+    /// Whether to count reads flagged as duplicates.
+    pub count_duplicates: bool,
+}
+
+impl Default for GcBiasParams {
+    fn default() -> Self {
+        GcBiasParams {
+            window_size: 100,
+            min_mapq: 0,
+            max_n_fraction: 0.1,
+            count_duplicates: false,
+        }
+    }
+}
+
+/// Statistics for the windows with a given GC content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcBin {
+    /// GC content of the windows in percent.
+    pub gc: u32,
+    /// Number of windows with this GC content.
+    pub windows: u64,
+    /// Number of reads starting in these windows.
+    pub reads: u64,
+    /// Mean reads per window, normalized by the mean reads per window over all windows.
+    pub normalized_coverage: f64,
+}
+
+/// Result of `gc_bias()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcBiasStats {
+    /// One bin for each GC percentage from 0 to 100.
+    pub bins: Vec<GcBin>,
+    /// Number of windows that were considered.
+    pub total_windows: u64,
+    /// Number of reads starting in the considered windows.
+    pub total_reads: u64,
+    /// Mean number of reads per window.
+    pub mean_coverage: f64,
+    /// Standard deviation of the number of reads per window.
+    pub coverage_sd: f64,
+    /// Fraction of windows with more than 0.2 times the mean number of reads.
+    pub uniformity: f64,
+}
+
+impl GcBiasStats {
+    /// Coefficient of variation of the number of reads per window.
+    pub fn coefficient_of_variation(&self) -> f64 {
+        if self.mean_coverage == 0.0 {
+            0.0
+        } else {
+            self.coverage_sd / self.mean_coverage
+        }
+    }
+
+    /// Picard's AT dropout, the sum of the percentage of windows minus the percentage of reads
+    /// over all bins with a GC content of at most 50%, where positive.
+    pub fn at_dropout(&self) -> f64 {
+        self.dropout(|gc| gc <= 50)
+    }
+
+    /// Picard's GC dropout, like `at_dropout()` but for bins with a GC content of at least 50%.
+    pub fn gc_dropout(&self) -> f64 {
+        self.dropout(|gc| gc >= 50)
+    }
+
+    fn dropout<F: Fn(u32) -> bool>(&self, select: F) -> f64 {
+        if self.total_windows == 0 || self.total_reads == 0 {
+            return 0.0;
+        }
+        self.bins
+            .iter()
+            .filter(|bin| select(bin.gc))
+            .map(|bin| {
+                let pct_windows = 100.0 * bin.windows as f64 / self.total_windows as f64;
+                let pct_reads = 100.0 * bin.reads as f64 / self.total_reads as f64;
+                (pct_windows - pct_reads).max(0.0)
+            })
+            .sum()
+    }
+}
+
+/// Compute GC bias and coverage uniformity statistics.
+///
+/// All records are read from `reader`, counting mapped primary reads (and, depending on
+/// `params`, duplicates) by their start position.  Contigs that are missing from `fasta` are
+/// ignored, as are windows at the contig ends that are shorter than the window size.
+///
+/// # Arguments
+///
+/// * `reader` - the reader to read the records from
+/// * `fasta` - the reference sequence
+/// * `params` - window size and read filters
+pub fn gc_bias<R: bam::Read>(
+    reader: &mut R,
+    fasta: &faidx::Reader,
+    params: &GcBiasParams,
+) -> Result<GcBiasStats, StatsError> {
+    if params.window_size == 0 {
+        return Err(StatsError::InvalidWindowSize);
+    }
+    let window_size = params.window_size;
+
+    // count read starts per window
+    let targets: Vec<(Vec<u8>, u32)> = {
+        let header = reader.header();
+        header
+            .target_names()
+            .iter()
+            .enumerate()
+            .map(|(tid, name)| (name.to_vec(), header.target_len(tid as u32).unwrap()))
+            .collect()
+    };
+    let mut counts: Vec<Vec<u32>> = targets
+        .iter()
+        .map(|&(_, len)| vec![0; (len / window_size) as usize])
+        .collect();
+    let mut record = bam::Record::new();
+    loop {
+        if let Err(e) = reader.read(&mut record) {
+            if e.is_eof() {
+                break;
+            }
+            return Err(StatsError::ReadError(e));
+        }
+        if record.is_unmapped() || record.is_secondary() || record.is_supplementary()
+            || record.is_quality_check_failed()
+            || (record.is_duplicate() && !params.count_duplicates)
+            || record.mapq() < params.min_mapq || record.tid() < 0 || record.pos() < 0
+        {
+            continue;
+        }
+        let window = (record.pos() as u32 / window_size) as usize;
+        if let Some(count) = counts
+            .get_mut(record.tid() as usize)
+            .and_then(|c| c.get_mut(window))
+        {
+            *count += 1;
+        }
+    }
+
+    // assign windows to GC bins
+    let mut bins: Vec<GcBin> = (0..101)
+        .map(|gc| GcBin {
+            gc: gc,
+            windows: 0,
+            reads: 0,
+            normalized_coverage: 0.0,
+        })
+        .collect();
+    let mut window_reads = Vec::new();
+    for (&(ref name, _), counts) in targets.iter().zip(counts.iter()) {
+        let seq_len = match fasta.seq_len(name) {
+            Some(len) => len,
+            None => continue,
+        };
+        let n_windows = cmp::min(counts.len() as u32, seq_len / window_size);
+        let mut chunk_start = 0;
+        while chunk_start < n_windows {
+            let chunk_end = cmp::min(chunk_start + CHUNK_WINDOWS, n_windows);
+            let seq = try!(fasta.fetch_seq(
+                name,
+                chunk_start * window_size,
+                chunk_end * window_size
+            ));
+            for (i, window_seq) in seq.chunks(window_size as usize).enumerate() {
+                if window_seq.len() < window_size as usize {
+                    break;
+                }
+                let (gc, n) = gc_count(window_seq);
+                if n as f64 > params.max_n_fraction * window_size as f64 || n == window_size {
+                    continue;
+                }
+                let gc_pct = (100.0 * gc as f64 / (window_size - n) as f64).round() as usize;
+                let reads = counts[chunk_start as usize + i];
+                bins[gc_pct].windows += 1;
+                bins[gc_pct].reads += reads as u64;
+                window_reads.push(reads);
+            }
+            chunk_start = chunk_end;
+        }
+    }
+
+    // normalize and compute uniformity metrics
+    let total_windows = window_reads.len() as u64;
+    let total_reads: u64 = window_reads.iter().map(|&r| r as u64).sum();
+    let mean_coverage = if total_windows == 0 {
+        0.0
+    } else {
+        total_reads as f64 / total_windows as f64
+    };
+    for bin in &mut bins {
+        if bin.windows > 0 && mean_coverage > 0.0 {
+            bin.normalized_coverage = bin.reads as f64 / bin.windows as f64 / mean_coverage;
+        }
+    }
+    let (coverage_sd, uniformity) = if total_windows == 0 {
+        (0.0, 0.0)
+    } else {
+        let var = window_reads
+            .iter()
+            .map(|&r| (r as f64 - mean_coverage).powi(2))
+            .sum::<f64>() / total_windows as f64;
+        let uniform = window_reads
+            .iter()
+            .filter(|&&r| r as f64 > 0.2 * mean_coverage)
+            .count();
+        (var.sqrt(), uniform as f64 / total_windows as f64)
+    };
+
+    Ok(GcBiasStats {
+        bins: bins,
+        total_windows: total_windows,
+        total_reads: total_reads,
+        mean_coverage: mean_coverage,
+        coverage_sd: coverage_sd,
+        uniformity: uniformity,
+    })
+}
+
+/// Return number of G/C and number of non-ACGT characters in `seq`.
+fn gc_count(seq: &[u8]) -> (u32, u32) {
+    let mut gc = 0;
+    let mut n = 0;
+    for &c in seq {
+        match c {
+            b'G' | b'C' | b'g' | b'c' => gc += 1,
+            b'A' | b'T' | b'a' | b't' => (),
+            _ => n += 1,
+        }
+    }
+    (gc, n)
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum StatsError {
+        InvalidWindowSize {
+            description("window size must be greater than zero")
+        }
+        ReadError(err: ReadError) {
+            from()
+        }
+        FetchError(err: faidx::FetchError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_bias() {
+        let mut bam = bam::Reader::from_path(&"test/test.bam")
+            .ok()
+            .expect("Error opening file.");
+        let fasta = faidx::Reader::from_path(&"test/test_gc.fa")
+            .ok()
+            .expect("Error opening file.");
+
+        let result = gc_bias(&mut bam, &fasta, &GcBiasParams::default()).unwrap();
+
+        // the supplementary alignment is not counted
+        assert_eq!(result.total_windows, 2);
+        assert_eq!(result.total_reads, 5);
+        assert_eq!(result.bins.len(), 101);
+        assert_eq!(result.bins[100].windows, 1);
+        assert_eq!(result.bins[100].reads, 5);
+        assert_eq!(result.bins[100].normalized_coverage, 2.0);
+        assert_eq!(result.bins[0].windows, 1);
+        assert_eq!(result.bins[0].normalized_coverage, 0.0);
+        assert_eq!(result.mean_coverage, 2.5);
+        assert_eq!(result.coverage_sd, 2.5);
+        assert_eq!(result.coefficient_of_variation(), 1.0);
+        assert_eq!(result.uniformity, 0.5);
+        assert_eq!(result.at_dropout(), 50.0);
+        assert_eq!(result.gc_dropout(), 0.0);
+    }
+}
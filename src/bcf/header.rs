@@ -3,10 +3,14 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cmp;
 use std::ffi;
+use std::ptr;
 use std::slice;
 use std::str;
 
+use bcf::record::FillTag;
+use faidx;
 use htslib;
 
 use linear_map::LinearMap;
@@ -45,6 +49,33 @@ impl Header {
         }
     }
 
+    /// Create a new `Header` by parsing the given VCF header text, i.e., the meta-information
+    /// lines starting with `##` followed by the `#CHROM` line.
+    ///
+    /// # Arguments
+    ///
+    /// - `text` - The header text, e.g., as returned by `HeaderView::to_vcf_bytes()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_htslib::bcf::Header;
+    ///
+    /// let header = Header::from_text(
+    ///     b"##fileformat=VCFv4.2\n##contig=<ID=chr1,length=1000>\n\
+    ///       #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tsample1\n",
+    /// ).unwrap();
+    /// ```
+    pub fn from_text(text: &[u8]) -> Result<Self, ParseError> {
+        match parse(text) {
+            Some(inner) => Ok(Header {
+                inner: inner,
+                subset: None,
+            }),
+            None => Err(ParseError::Some),
+        }
+    }
+
     /// Create a new `Header` using the given `HeaderView as the template.
     ///
     /// After construction, you can modify the header independently from the template `header`.
@@ -94,6 +125,25 @@ impl Header {
         }
     }
 
+    /// Set the VCF version of the `##fileformat` line, e.g., `VCFv4.3`.
+    ///
+    /// Note that htslib does not convert the records, so the output has to be valid for the
+    /// given version.
+    ///
+    /// # Arguments
+    ///
+    /// - `version` - The version, starting with `VCFv`.
+    pub fn set_version(&mut self, version: &str) -> Result<&mut Self, HeaderRecordError> {
+        if !version.starts_with("VCFv")
+            || version.contains(|c: char| c.is_whitespace() || c == '\0')
+        {
+            return Err(HeaderRecordError::InvalidValue(version.to_owned()));
+        }
+        let version = ffi::CString::new(version).unwrap();
+        unsafe { htslib::bcf_hdr_set_version(self.inner, version.as_ptr()) };
+        Ok(self)
+    }
+
     /// Add a `sample` to the header.
     ///
     /// # Arguments
@@ -106,6 +156,139 @@ impl Header {
         self
     }
 
+    /// Rename the sample `old` to `new`, similar to `bcftools reheader -s`.
+    ///
+    /// The order of the samples is kept, hence records remain valid for the renamed header.
+    ///
+    /// # Arguments
+    ///
+    /// - `old` - The current name of the sample.
+    /// - `new` - The new name of the sample.
+    pub fn rename_sample(&mut self, old: &[u8], new: &[u8]) -> Result<&mut Self, RenameError> {
+        let mut samples: Vec<Vec<u8>> = unsafe {
+            let n = (*self.inner).n[htslib::BCF_DT_SAMPLE as usize] as usize;
+            slice::from_raw_parts((*self.inner).samples, n)
+                .iter()
+                .map(|name| ffi::CStr::from_ptr(*name).to_bytes().to_vec())
+                .collect()
+        };
+        match samples.iter().position(|s| s.as_slice() == old) {
+            Some(i) => samples[i] = new.to_vec(),
+            None => {
+                return Err(RenameError::UnknownSample(
+                    String::from_utf8_lossy(old).into_owned(),
+                ))
+            }
+        }
+        self.set_samples(&samples)
+    }
+
+    /// Replace the names of all samples, keeping their order, similar to
+    /// `bcftools reheader -s`.
+    ///
+    /// # Arguments
+    ///
+    /// - `samples` - The new sample names, one for each sample of the header.
+    pub fn set_samples<S: AsRef<[u8]>>(&mut self, samples: &[S]) -> Result<&mut Self, RenameError> {
+        let n = unsafe { (*self.inner).n[htslib::BCF_DT_SAMPLE as usize] } as usize;
+        if samples.len() != n {
+            return Err(RenameError::InvalidSampleCount);
+        }
+        for (i, sample) in samples.iter().enumerate() {
+            let sample = sample.as_ref();
+            if sample.is_empty()
+                || sample
+                    .iter()
+                    .any(|&c| c == b'\0' || c == b'\t' || c == b'\n')
+            {
+                return Err(RenameError::InvalidSample(
+                    String::from_utf8_lossy(sample).into_owned(),
+                ));
+            }
+            if samples[..i].iter().any(|s| s.as_ref() == sample) {
+                return Err(RenameError::DuplicateSample(
+                    String::from_utf8_lossy(sample).into_owned(),
+                ));
+            }
+        }
+
+        if self.replace_samples(samples) {
+            Ok(self)
+        } else {
+            Err(RenameError::Some)
+        }
+    }
+
+    /// Subset and reorder the samples by their index, e.g., `&[2, 0]` keeps the third and
+    /// the first sample, in this order.
+    ///
+    /// Returns the mapping from the new samples to the samples of the records, which is also
+    /// used by `Writer::subset()` for writers created with this header.  If the header already
+    /// was a subset (see `from_template_subset()`), the mapping refers to the samples of the
+    /// original records.
+    ///
+    /// # Arguments
+    ///
+    /// - `indices` - The indices of the samples to keep, in the new order.
+    pub fn select_samples(&mut self, indices: &[usize]) -> Result<SampleSubset, SubsetError> {
+        let samples: Vec<Vec<u8>> = unsafe {
+            let n = (*self.inner).n[htslib::BCF_DT_SAMPLE as usize] as usize;
+            slice::from_raw_parts((*self.inner).samples, n)
+                .iter()
+                .map(|name| ffi::CStr::from_ptr(*name).to_bytes().to_vec())
+                .collect()
+        };
+        for (i, &idx) in indices.iter().enumerate() {
+            if idx >= samples.len() {
+                return Err(SubsetError::InvalidSampleIndex(idx));
+            }
+            if indices[..i].contains(&idx) {
+                return Err(SubsetError::DuplicateSampleName);
+            }
+        }
+
+        let selected: Vec<&[u8]> = indices.iter().map(|&idx| samples[idx].as_slice()).collect();
+        if !self.replace_samples(&selected) {
+            return Err(SubsetError::Some);
+        }
+        let imap: SampleSubset = match self.subset {
+            Some(ref subset) => indices.iter().map(|&idx| subset[idx]).collect(),
+            None => indices.iter().map(|&idx| idx as i32).collect(),
+        };
+        self.subset = Some(imap.clone());
+        Ok(imap)
+    }
+
+    /// Replace the samples by rebuilding the header with the given sample names, returning
+    /// whether this succeeded.
+    fn replace_samples<S: AsRef<[u8]>>(&mut self, samples: &[S]) -> bool {
+        // replace the `#CHROM` line of the header text and parse it again
+        let mut text = format_vcf(self.inner);
+        let start = match text.windows(7).rposition(|w| w == b"\n#CHROM") {
+            Some(i) => i + 1,
+            None => return false,
+        };
+        text.truncate(start);
+        text.extend_from_slice(b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO");
+        if !samples.is_empty() {
+            text.extend_from_slice(b"\tFORMAT");
+        }
+        for sample in samples {
+            text.push(b'\t');
+            text.extend_from_slice(sample.as_ref());
+        }
+        text.push(b'\n');
+
+        match parse(&text) {
+            Some(inner) => {
+                unsafe { htslib::bcf_hdr_destroy(self.inner) };
+                self.inner = inner;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Add a record to the header.
     ///
     /// # Arguments
@@ -122,6 +305,245 @@ impl Header {
         self
     }
 
+    /// Add an `INFO` definition to the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - the ID of the field
+    /// - `length` - the number of values, `TagLength::Fixed(0)` for flags only
+    /// - `tag_type` - the type of the values
+    /// - `description` - the description
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_htslib::bcf::header::{Header, TagLength, TagType};
+    ///
+    /// let mut header = Header::new();
+    /// header
+    ///     .add_info(b"DP", TagLength::Fixed(1), TagType::Integer, "Total depth")
+    ///     .unwrap();
+    /// ```
+    pub fn add_info(
+        &mut self,
+        id: &[u8],
+        length: TagLength,
+        tag_type: TagType,
+        description: &str,
+    ) -> Result<&mut Self, HeaderRecordError> {
+        let line = try!(tag_line("INFO", id, length, tag_type, description));
+        self.append(&line)
+    }
+
+    /// Add the definitions of the given `INFO` fields computed by `Record::fill_tags()`,
+    /// replacing existing definitions of the same fields.
+    pub fn add_fill_tags(&mut self, tags: &[FillTag]) -> Result<&mut Self, HeaderRecordError> {
+        for tag in tags {
+            let (length, tag_type, description) = tag.definition();
+            self.remove_info(tag.id());
+            try!(self.add_info(tag.id(), length, tag_type, description));
+        }
+        Ok(self)
+    }
+
+    /// Add a `FORMAT` definition to the header, flags are not allowed.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - the ID of the field
+    /// - `length` - the number of values
+    /// - `tag_type` - the type of the values
+    /// - `description` - the description
+    pub fn add_format(
+        &mut self,
+        id: &[u8],
+        length: TagLength,
+        tag_type: TagType,
+        description: &str,
+    ) -> Result<&mut Self, HeaderRecordError> {
+        if tag_type == TagType::Flag {
+            return Err(HeaderRecordError::InvalidType);
+        }
+        let line = try!(tag_line("FORMAT", id, length, tag_type, description));
+        self.append(&line)
+    }
+
+    /// Add a `FILTER` definition to the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - the ID of the filter
+    /// - `description` - the description
+    pub fn add_filter(
+        &mut self,
+        id: &[u8],
+        description: &str,
+    ) -> Result<&mut Self, HeaderRecordError> {
+        if !is_valid_id(id) {
+            return Err(HeaderRecordError::InvalidId(String::from_utf8_lossy(id).into_owned()));
+        }
+        let line = format!(
+            "##FILTER=<ID={},Description={}>",
+            str::from_utf8(id).unwrap(),
+            try!(quote(description))
+        );
+        self.append(line.as_bytes())
+    }
+
+    /// Add a contig definition to the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `id` - the name of the contig
+    /// - `length` - the length of the contig, if known
+    pub fn add_contig(
+        &mut self,
+        id: &[u8],
+        length: Option<u64>,
+    ) -> Result<&mut Self, HeaderRecordError> {
+        let valid = !id.is_empty() && id[0] != b'*' && id[0] != b'='
+            && id.iter().all(|&c| c > b' ' && c < 0x7f && !b",<>\"'".contains(&c));
+        if !valid {
+            return Err(HeaderRecordError::InvalidId(String::from_utf8_lossy(id).into_owned()));
+        }
+        let id = str::from_utf8(id).unwrap();
+        let line = match length {
+            Some(length) => format!("##contig=<ID={},length={}>", id, length),
+            None => format!("##contig=<ID={}>", id),
+        };
+        self.append(line.as_bytes())
+    }
+
+    /// Add a contig definition with the name and length of each sequence of the indexed
+    /// FASTA file, in the order of the index.
+    ///
+    /// # Arguments
+    ///
+    /// - `reference` - The reader of the reference sequences.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use rust_htslib::bcf::Header;
+    /// use rust_htslib::faidx;
+    ///
+    /// let reference = faidx::Reader::from_path(&"ref.fa").unwrap();
+    /// let mut header = Header::new();
+    /// header.add_contigs_from_fai(&reference).unwrap();
+    /// ```
+    pub fn add_contigs_from_fai(
+        &mut self,
+        reference: &faidx::Reader,
+    ) -> Result<&mut Self, HeaderRecordError> {
+        for name in reference.seq_names() {
+            let length = reference.seq_len(&name).map(|length| length as u64);
+            try!(self.add_contig(&name, length));
+        }
+        Ok(self)
+    }
+
+    /// Add a structured header line `##key=<k1=v1,k2=v2,...>`, quoting and escaping the
+    /// values where needed.
+    ///
+    /// In contrast to `push_record()`, invalid keys and values are rejected.  Values are put
+    /// in quotes if they contain special characters and for the `Description` key.
+    ///
+    /// # Arguments
+    ///
+    /// - `key` - the key of the line, e.g., `ALT` or `SAMPLE`
+    /// - `values` - the keys and values, usually starting with `ID`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rust_htslib::bcf::Header;
+    ///
+    /// let mut header = Header::new();
+    /// header
+    ///     .push_structured("ALT", &[("ID", "DEL"), ("Description", "Deletion, \"large\"")])
+    ///     .unwrap();
+    /// ```
+    pub fn push_structured(
+        &mut self,
+        key: &str,
+        values: &[(&str, &str)],
+    ) -> Result<&mut Self, HeaderRecordError> {
+        if !is_valid_key(key) {
+            return Err(HeaderRecordError::InvalidKey(key.to_owned()));
+        }
+        if values.is_empty() {
+            return Err(HeaderRecordError::Some);
+        }
+        let mut fields = Vec::with_capacity(values.len());
+        for &(k, v) in values {
+            if !is_valid_key(k) {
+                return Err(HeaderRecordError::InvalidKey(k.to_owned()));
+            }
+            if v.contains(|c: char| c == '\0' || c == '\n') {
+                return Err(HeaderRecordError::InvalidValue(v.to_owned()));
+            }
+            let needs_quotes = k == "Description"
+                || v.is_empty()
+                || v.contains(|c: char| c.is_whitespace() || ",<>\"=\\".contains(c));
+            if needs_quotes {
+                fields.push(format!("{}={}", k, try!(quote(v))));
+            } else {
+                fields.push(format!("{}={}", k, v));
+            }
+        }
+        let line = format!("##{}=<{}>", key, fields.join(","));
+        self.append(line.as_bytes())
+    }
+
+    /// Add a generic header line `##key=value`.
+    ///
+    /// # Arguments
+    ///
+    /// - `key` - the key of the line, e.g., `source`
+    /// - `value` - the value, must not be empty or contain newlines or start with `<`
+    pub fn push_generic(&mut self, key: &str, value: &str) -> Result<&mut Self, HeaderRecordError> {
+        if !is_valid_key(key) {
+            return Err(HeaderRecordError::InvalidKey(key.to_owned()));
+        }
+        if value.is_empty()
+            || value.starts_with('<')
+            || value.contains(|c: char| c == '\0' || c == '\n')
+        {
+            return Err(HeaderRecordError::InvalidValue(value.to_owned()));
+        }
+        let line = format!("##{}={}", key, value);
+        self.append(line.as_bytes())
+    }
+
+    /// Merge the header lines of `header` into this header, e.g., for writing the records of
+    /// several files into one.
+    ///
+    /// `FILTER`, `INFO`, `FORMAT` and contig definitions as well as other header lines that
+    /// are missing in this header are added, the ones present in both are kept unchanged.
+    /// Samples are not merged.  Use `Writer::translate()` for writing records read with one
+    /// of the original headers.
+    ///
+    /// # Arguments
+    ///
+    /// - `header` - The `HeaderView` to merge into this header.
+    pub fn merge(&mut self, header: &HeaderView) -> Result<&mut Self, MergeError> {
+        if unsafe { htslib::bcf_hdr_merge(self.inner, header.inner) }.is_null() {
+            Err(MergeError::Some)
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Append a header line, checking the result.
+    fn append(&mut self, line: &[u8]) -> Result<&mut Self, HeaderRecordError> {
+        let line = ffi::CString::new(line).unwrap();
+        if unsafe { htslib::bcf_hdr_append(self.inner, line.as_ptr()) } < 0 {
+            Err(HeaderRecordError::Some)
+        } else {
+            Ok(self)
+        }
+    }
+
     /// Remove an `FILTER` entry from header.
     ///
     /// # Arguments
@@ -176,6 +598,60 @@ impl Header {
         self.remove_impl(tag, htslib::BCF_HL_GEN)
     }
 
+    /// Remove a `FILTER` entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - Name of the `FLT` tag to remove.
+    pub fn remove_filter_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_FLT)
+    }
+
+    /// Remove an `INFO` entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - Name of the `INFO` tag to remove.
+    pub fn remove_info_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_INFO)
+    }
+
+    /// Remove a `FORMAT` entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - Name of the `FORMAT` tag to remove.
+    pub fn remove_format_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_FMT)
+    }
+
+    /// Remove a contig entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - Name of the contig to remove.
+    pub fn remove_contig_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_CTG)
+    }
+
+    /// Remove a structured entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - `ID` of the structured entry to remove.
+    pub fn remove_structured_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_STR)
+    }
+
+    /// Remove a generic entry from header, failing if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - Name of the generic tag to remove.
+    pub fn remove_generic_checked(&mut self, tag: &[u8]) -> Result<&mut Self, RemoveError> {
+        self.remove_checked_impl(tag, htslib::BCF_HL_GEN)
+    }
+
     /// Implementation of removing header tags.
     fn remove_impl(&mut self, tag: &[u8], type_: u32) -> &mut Self {
         unsafe {
@@ -185,6 +661,17 @@ impl Header {
         }
         self
     }
+
+    /// Implementation of removing header tags, failing if no header line has been removed.
+    fn remove_checked_impl(&mut self, tag: &[u8], type_: u32) -> Result<&mut Self, RemoveError> {
+        let nhrec = unsafe { (*self.inner).nhrec };
+        self.remove_impl(tag, type_);
+        if unsafe { (*self.inner).nhrec } < nhrec {
+            Ok(self)
+        } else {
+            Err(RemoveError::UnknownTag(String::from_utf8_lossy(tag).into_owned()))
+        }
+    }
 }
 
 impl Drop for Header {
@@ -225,11 +712,20 @@ pub enum HeaderRecord {
     Generic { key: String, value: String },
 }
 
+/// A read-only view of a BCF header.
+///
+/// The header cannot be modified after construction, hence it can be shared between threads.
+/// Records keep an `Arc<HeaderView>` to the header they belong to.  Within this crate, the
+/// raw header must only be passed to htslib functions that do not modify it, with the
+/// exception of `bcf_translate()`, which is serialized by `Writer::translate()`.
 #[derive(Debug)]
 pub struct HeaderView {
-    pub inner: *mut htslib::bcf_hdr_t,
+    pub(crate) inner: *mut htslib::bcf_hdr_t,
 }
 
+unsafe impl Send for HeaderView {}
+unsafe impl Sync for HeaderView {}
+
 impl HeaderView {
     pub fn new(inner: *mut htslib::bcf_hdr_t) -> Self {
         HeaderView { inner: inner }
@@ -261,6 +757,40 @@ impl HeaderView {
         }
     }
 
+    /// Return the number of contigs in the header.
+    pub fn contig_count(&self) -> u32 {
+        self.inner().n[htslib::BCF_DT_CTG as usize] as u32
+    }
+
+    /// Return the length of the contig with the given `rid`, as given by the `length` field
+    /// of its header record, or `None` if the length is not given.
+    pub fn rid2len(&self, rid: u32) -> Option<u64> {
+        assert!(rid < self.contig_count(), "rid out of range");
+        unsafe {
+            let dict = self.inner().id[htslib::BCF_DT_CTG as usize];
+            let hrec = (*(*dict.offset(rid as isize)).val).hrec[0];
+            if hrec.is_null() {
+                return None;
+            }
+            let key = ffi::CString::new("length").unwrap();
+            match htslib::bcf_hrec_find_key(hrec, key.as_ptr()) {
+                i if i < 0 => None,
+                i => ffi::CStr::from_ptr(*(*hrec).vals.offset(i as isize))
+                    .to_str()
+                    .ok()
+                    .and_then(|length| length.parse::<u64>().ok()),
+            }
+        }
+    }
+
+    /// Iterate over the names and lengths (see `rid2len()`) of the contigs, ordered by rid.
+    pub fn contigs(&self) -> Contigs {
+        Contigs {
+            header: self,
+            rid: 0,
+        }
+    }
+
     pub fn name2rid(&self, name: &[u8]) -> Result<u32, RidError> {
         unsafe {
             match htslib::bcf_hdr_id2int(
@@ -276,6 +806,119 @@ impl HeaderView {
         }
     }
 
+    /// Iterate over the IDs of the `FILTER`, `INFO` and `FORMAT` definitions, ordered by
+    /// their numeric ID.
+    ///
+    /// An ID that is used by more than one kind of definition (e.g., `DP` as `INFO` and
+    /// `FORMAT`) is yielded once.
+    pub fn ids(&self) -> Ids {
+        Ids {
+            header: self,
+            id: 0,
+        }
+    }
+
+    /// Return the definitions of the `INFO` fields, in the order of the header.
+    pub fn info_defs(&self) -> Vec<TagDefinition> {
+        self.tag_defs(htslib::BCF_HL_INFO)
+    }
+
+    /// Return the definitions of the `FORMAT` fields, in the order of the header.
+    pub fn format_defs(&self) -> Vec<TagDefinition> {
+        self.tag_defs(htslib::BCF_HL_FMT)
+    }
+
+    fn tag_defs(&self, hdr_type: ::libc::c_uint) -> Vec<TagDefinition> {
+        let mut result = Vec::new();
+        for i in 0_i32..unsafe { (*self.inner).nhrec } {
+            let rec = unsafe { &(**(*self.inner).hrec.offset(i as isize)) };
+            if rec.type_ != hdr_type as i32 {
+                continue;
+            }
+            let values = parse_kv(rec);
+            let id = match values.get("ID") {
+                Some(id) => id.clone(),
+                None => continue,
+            };
+            if let Ok((tag_type, length)) = self.tag_type(id.as_bytes(), hdr_type) {
+                let description = values
+                    .get("Description")
+                    .map(|d| {
+                        if d.len() >= 2 && d.starts_with('"') && d.ends_with('"') {
+                            unescape(&d[1..d.len() - 1])
+                        } else {
+                            unescape(d)
+                        }
+                    })
+                    .unwrap_or_default();
+                result.push(TagDefinition {
+                    id: id,
+                    length: length,
+                    tag_type: tag_type,
+                    description: description,
+                });
+            }
+        }
+        result
+    }
+
+    /// Return the `##SAMPLE` header lines, keyed by their `ID`.
+    pub fn sample_defs(&self) -> LinearMap<String, SampleDefinition> {
+        let mut result = LinearMap::new();
+        for mut values in self.structured_values("SAMPLE") {
+            let id = match values.remove("ID") {
+                Some(id) => id,
+                None => continue,
+            };
+            let description = values.remove("Description").map(|d| unescape(&d));
+            result.insert(
+                id.clone(),
+                SampleDefinition {
+                    id: id,
+                    description: description,
+                    values: values,
+                },
+            );
+        }
+        result
+    }
+
+    /// Return the `##PEDIGREE` header lines, in the order of the header.
+    pub fn pedigree_defs(&self) -> Vec<PedigreeDefinition> {
+        self.structured_values("PEDIGREE")
+            .into_iter()
+            .map(|values| PedigreeDefinition { values: values })
+            .collect()
+    }
+
+    /// Return the values of the structured header lines with the given key, without the
+    /// `IDX` added by htslib and with quotes removed.
+    fn structured_values(&self, key: &str) -> Vec<LinearMap<String, String>> {
+        let mut result = Vec::new();
+        for i in 0_i32..unsafe { (*self.inner).nhrec } {
+            let rec = unsafe { &(**(*self.inner).hrec.offset(i as isize)) };
+            if rec.type_ != htslib::BCF_HL_STR as i32
+                || unsafe { ffi::CStr::from_ptr(rec.key) }.to_bytes() != key.as_bytes()
+            {
+                continue;
+            }
+            let values = parse_kv(rec)
+                .into_iter()
+                .filter(|&(ref k, _)| k != "IDX")
+                .map(|(k, v)| {
+                    let v = if v.len() >= 2 && v.starts_with('"') && v.ends_with('"') {
+                        v[1..v.len() - 1].to_owned()
+                    } else {
+                        v
+                    };
+                    (k, v)
+                })
+                .collect();
+            result.push(values);
+        }
+        result
+    }
+
     pub fn info_type(&self, tag: &[u8]) -> Result<(TagType, TagLength), TagTypeError> {
         self.tag_type(tag, htslib::BCF_HL_INFO)
     }
@@ -289,7 +932,7 @@ impl HeaderView {
         tag: &[u8],
         hdr_type: ::libc::c_uint,
     ) -> Result<(TagType, TagLength), TagTypeError> {
-        let (_type, length) = unsafe {
+        let (_type, length, number) = unsafe {
             let id = htslib::bcf_hdr_id2int(
                 self.inner,
                 htslib::BCF_DT_ID as i32,
@@ -303,7 +946,7 @@ impl HeaderView {
             let n = (*self.inner).n[htslib::BCF_DT_ID as usize] as usize;
             let entry = slice::from_raw_parts((*self.inner).id[htslib::BCF_DT_ID as usize], n);
             let d = (*entry[id as usize].val).info[hdr_type as usize];
-            (d >> 4 & 0xf, d >> 8 & 0xf)
+            (d >> 4 & 0xf, d >> 8 & 0xf, d >> 12)
         };
         let _type = match _type as ::libc::c_uint {
             htslib::BCF_HT_FLAG => TagType::Flag,
@@ -313,7 +956,7 @@ impl HeaderView {
             _ => return Err(TagTypeError::UnexpectedTagType),
         };
         let length = match length as ::libc::c_uint {
-            htslib::BCF_VL_FIXED => TagLength::Fixed,
+            htslib::BCF_VL_FIXED => TagLength::Fixed(number),
             htslib::BCF_VL_VAR => TagLength::Variable,
             htslib::BCF_VL_A => TagLength::AltAlleles,
             htslib::BCF_VL_R => TagLength::Alleles,
@@ -377,26 +1020,6 @@ impl HeaderView {
 
     /// Return structured `HeaderRecord`s.
     pub fn header_records(&self) -> Vec<HeaderRecord> {
-        fn parse_kv(rec: &htslib::bcf_hrec_t) -> LinearMap<String, String> {
-            let mut result: LinearMap<String, String> = LinearMap::new();
-            for i in 0_i32..(rec.nkeys) {
-                let key = unsafe {
-                    ffi::CStr::from_ptr(*rec.keys.offset(i as isize))
-                        .to_str()
-                        .unwrap()
-                        .to_string()
-                };
-                let value = unsafe {
-                    ffi::CStr::from_ptr(*rec.vals.offset(i as isize))
-                        .to_str()
-                        .unwrap()
-                        .to_string()
-                };
-                result.insert(key, value);
-            }
-            result
-        }
-
         let mut result: Vec<HeaderRecord> = Vec::new();
         for i in 1_i32..unsafe { (*self.inner).nhrec } {
             let rec = unsafe { &(**(*self.inner).hrec.offset(i as isize)) };
@@ -432,6 +1055,341 @@ impl HeaderView {
         }
         result
     }
+
+    /// Check that the contigs and the `FILTER`, `INFO` and `FORMAT` entries defined in both
+    /// headers have the same numeric IDs, such that records can be moved from one header to
+    /// the other without translation (see `Record::set_header()`).
+    ///
+    /// This is the case if one header is a copy of the other with lines added.  Entries defined
+    /// in one header only are not checked.
+    pub fn check_same_ids(&self, other: &HeaderView) -> Result<(), CompatibilityError> {
+        for &dict in &[htslib::BCF_DT_CTG, htslib::BCF_DT_ID] {
+            let d = dict as usize;
+            let n = unsafe { cmp::min((*self.inner).n[d], (*other.inner).n[d]) };
+            for i in 0..n as isize {
+                let key = unsafe { (*(*self.inner).id[d].offset(i)).key };
+                let other_key = unsafe { (*(*other.inner).id[d].offset(i)).key };
+                if key.is_null() || other_key.is_null() {
+                    continue;
+                }
+                let name = unsafe { ffi::CStr::from_ptr(key) };
+                if name != unsafe { ffi::CStr::from_ptr(other_key) } {
+                    let name = name.to_string_lossy().into_owned();
+                    return Err(if dict == htslib::BCF_DT_CTG {
+                        CompatibilityError::ContigMismatch(name)
+                    } else {
+                        CompatibilityError::IdMismatch(name)
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return whether records using this header can be combined with records using `other`,
+    /// see `check_compatible()`.
+    pub fn compatible_with(&self, other: &HeaderView) -> bool {
+        self.check_compatible(other).is_ok()
+    }
+
+    /// Check whether records using this header can be combined with records using `other`,
+    /// e.g., before concatenating files.
+    ///
+    /// The headers are compatible if they have the same samples and contigs (in the same
+    /// order, with the same lengths where given) and the `INFO` and `FORMAT` fields defined
+    /// in both have the same type and number of values.  Use `==` for checking whether the
+    /// headers are identical.
+    pub fn check_compatible(&self, other: &HeaderView) -> Result<(), CompatibilityError> {
+        if self.samples() != other.samples() {
+            return Err(CompatibilityError::SampleMismatch);
+        }
+        if self.contig_count() != other.contig_count() {
+            return Err(CompatibilityError::ContigMismatch(String::from("number of contigs")));
+        }
+        for ((name, len), (other_name, other_len)) in self.contigs().zip(other.contigs()) {
+            let len_differs = match (len, other_len) {
+                (Some(len), Some(other_len)) => len != other_len,
+                _ => false,
+            };
+            if name != other_name || len_differs {
+                return Err(CompatibilityError::ContigMismatch(
+                    String::from_utf8_lossy(name).into_owned(),
+                ));
+            }
+        }
+        for def in self.info_defs() {
+            if let Ok(other_type) = other.info_type(def.id.as_bytes()) {
+                if other_type != (def.tag_type, def.length) {
+                    return Err(CompatibilityError::InfoMismatch(def.id));
+                }
+            }
+        }
+        for def in self.format_defs() {
+            if let Ok(other_type) = other.format_type(def.id.as_bytes()) {
+                if other_type != (def.tag_type, def.length) {
+                    return Err(CompatibilityError::FormatMismatch(def.id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the VCF version declared by the `##fileformat` line, e.g., `VCFv4.2`.
+    ///
+    /// htslib assumes `VCFv4.2` if the header has no such line.
+    pub fn version(&self) -> &str {
+        unsafe { ffi::CStr::from_ptr(htslib::bcf_hdr_get_version(self.inner)) }
+            .to_str()
+            .unwrap()
+    }
+
+    /// Return the value of the first generic header line `##key=value` with the given key,
+    /// e.g., `reference` or `source`.
+    pub fn get_generic(&self, key: &str) -> Option<&str> {
+        self.get_all_generic(key).into_iter().next()
+    }
+
+    /// Return the values of all generic header lines `##key=value` with the given key, in the
+    /// order of the header.
+    pub fn get_all_generic(&self, key: &str) -> Vec<&str> {
+        let mut result = Vec::new();
+        for i in 0..self.inner().nhrec {
+            let rec = unsafe { &**self.inner().hrec.offset(i as isize) };
+            if rec.type_ != htslib::BCF_HL_GEN as i32 || rec.value.is_null() {
+                continue;
+            }
+            if unsafe { ffi::CStr::from_ptr(rec.key) }.to_bytes() == key.as_bytes() {
+                if let Ok(value) = unsafe { ffi::CStr::from_ptr(rec.value) }.to_str() {
+                    result.push(value);
+                }
+            }
+        }
+        result
+    }
+
+    /// Format the header as VCF text, i.e., the meta-information lines followed by the
+    /// `#CHROM` line, each terminated by a newline.
+    pub fn to_vcf_bytes(&self) -> Vec<u8> {
+        format_vcf(self.inner)
+    }
+
+    /// Format the header as VCF text, see `to_vcf_bytes()`.  Invalid UTF-8 sequences are
+    /// replaced.
+    pub fn to_vcf_string(&self) -> String {
+        String::from_utf8_lossy(&self.to_vcf_bytes()).into_owned()
+    }
+}
+
+/// Parse a header from VCF text, returning `None` if this fails.
+fn parse(text: &[u8]) -> Option<*mut htslib::bcf_hdr_t> {
+    let text = match ffi::CString::new(text) {
+        Ok(text) => text,
+        Err(_) => return None,
+    };
+    let inner = unsafe { htslib::bcf_hdr_init(ffi::CString::new("r").unwrap().as_ptr()) };
+    if unsafe { htslib::bcf_hdr_parse(inner, text.as_ptr() as *mut i8) } < 0 {
+        unsafe { htslib::bcf_hdr_destroy(inner) };
+        None
+    } else {
+        Some(inner)
+    }
+}
+
+/// Format the given header as VCF text.
+fn format_vcf(inner: *const htslib::bcf_hdr_t) -> Vec<u8> {
+    let mut buf = htslib::kstring_t {
+        l: 0,
+        m: 0,
+        s: ptr::null_mut(),
+    };
+    unsafe {
+        if htslib::bcf_hdr_format(inner, 0, &mut buf) < 0 {
+            panic!("failed to allocate memory for formatting the header");
+        }
+        let text = slice::from_raw_parts(buf.s as *const u8, buf.l as usize).to_vec();
+        ::libc::free(buf.s as *mut ::libc::c_void);
+        text
+    }
+}
+
+/// Return whether `id` is a valid ID of an `INFO`, `FORMAT` or `FILTER` definition.
+fn is_valid_id(id: &[u8]) -> bool {
+    if id == b"1000G" {
+        return true;
+    }
+    match id.split_first() {
+        Some((&first, rest)) => {
+            (first.is_ascii_alphabetic() || first == b'_')
+                && rest
+                    .iter()
+                    .all(|&c| c.is_ascii_alphanumeric() || c == b'_' || c == b'.')
+        }
+        None => false,
+    }
+}
+
+/// Return whether `key` is a valid key of a header line or of a value in a structured line.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+/// Quote a description for a header line, escaping quotes and backslashes.
+fn quote(description: &str) -> Result<String, HeaderRecordError> {
+    if description.contains(|c: char| c == '\0' || c == '\n') {
+        return Err(HeaderRecordError::InvalidDescription);
+    }
+    Ok(format!(
+        "\"{}\"",
+        description.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// Undo the escaping of quotes and backslashes of `quote()`.
+fn unescape(description: &str) -> String {
+    let mut result = String::with_capacity(description.len());
+    let mut chars = description.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(&next) = chars.peek() {
+                if next == '\\' || next == '"' {
+                    result.push(next);
+                    chars.next();
+                    continue;
+                }
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Format an `INFO` or `FORMAT` header line.
+fn tag_line(
+    kind: &str,
+    id: &[u8],
+    length: TagLength,
+    tag_type: TagType,
+    description: &str,
+) -> Result<Vec<u8>, HeaderRecordError> {
+    if !is_valid_id(id) {
+        return Err(HeaderRecordError::InvalidId(String::from_utf8_lossy(id).into_owned()));
+    }
+    // only flags have no values
+    if (tag_type == TagType::Flag) != (length == TagLength::Fixed(0)) {
+        return Err(HeaderRecordError::InvalidLength);
+    }
+    let number = match length {
+        TagLength::Fixed(n) => n.to_string(),
+        TagLength::AltAlleles => String::from("A"),
+        TagLength::Alleles => String::from("R"),
+        TagLength::Genotypes => String::from("G"),
+        TagLength::Variable => String::from("."),
+    };
+    let tag_type = match tag_type {
+        TagType::Flag => "Flag",
+        TagType::Integer => "Integer",
+        TagType::Float => "Float",
+        TagType::String => "String",
+    };
+    Ok(format!(
+        "##{}=<ID={},Number={},Type={},Description={}>",
+        kind,
+        str::from_utf8(id).unwrap(),
+        number,
+        tag_type,
+        try!(quote(description))
+    ).into_bytes())
+}
+
+/// Return the keys and values of a structured header record.
+fn parse_kv(rec: &htslib::bcf_hrec_t) -> LinearMap<String, String> {
+    let mut result: LinearMap<String, String> = LinearMap::new();
+    for i in 0_i32..(rec.nkeys) {
+        let key = unsafe {
+            ffi::CStr::from_ptr(*rec.keys.offset(i as isize))
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let value = unsafe {
+            ffi::CStr::from_ptr(*rec.vals.offset(i as isize))
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        result.insert(key, value);
+    }
+    result
+}
+
+/// Iterator over the contigs of a header, see `HeaderView::contigs()`.
+#[derive(Debug)]
+pub struct Contigs<'a> {
+    header: &'a HeaderView,
+    rid: u32,
+}
+
+impl<'a> Iterator for Contigs<'a> {
+    type Item = (&'a [u8], Option<u64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rid >= self.header.contig_count() {
+            return None;
+        }
+        let rid = self.rid;
+        self.rid += 1;
+        Some((self.header.rid2name(rid), self.header.rid2len(rid)))
+    }
+}
+
+/// The kinds of definitions of an ID, see `HeaderView::ids()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IdKind {
+    /// Whether the ID is defined as a `FILTER`.
+    pub filter: bool,
+    /// Whether the ID is defined as an `INFO` field.
+    pub info: bool,
+    /// Whether the ID is defined as a `FORMAT` field.
+    pub format: bool,
+}
+
+/// Iterator over the IDs of a header, see `HeaderView::ids()`.
+#[derive(Debug)]
+pub struct Ids<'a> {
+    header: &'a HeaderView,
+    id: i32,
+}
+
+impl<'a> Iterator for Ids<'a> {
+    type Item = (Id, &'a [u8], IdKind);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.header.inner;
+        while self.id < unsafe { (*inner).n[htslib::BCF_DT_ID as usize] } {
+            let id = self.id;
+            self.id += 1;
+            let pair = unsafe { *(*inner).id[htslib::BCF_DT_ID as usize].offset(id as isize) };
+            // gaps are left by removed definitions
+            if pair.key.is_null() || pair.val.is_null() {
+                continue;
+            }
+            let info = unsafe { (*pair.val).info };
+            let kind = IdKind {
+                filter: info[htslib::BCF_HL_FLT as usize] & 0xf != 0xf,
+                info: info[htslib::BCF_HL_INFO as usize] & 0xf != 0xf,
+                format: info[htslib::BCF_HL_FMT as usize] & 0xf != 0xf,
+            };
+            if !(kind.filter || kind.info || kind.format) {
+                continue;
+            }
+            let name = unsafe { ffi::CStr::from_ptr(pair.key).to_bytes() };
+            return Some((Id(id as u32), name, kind));
+        }
+        None
+    }
 }
 
 impl Clone for HeaderView {
@@ -442,6 +1400,15 @@ impl Clone for HeaderView {
     }
 }
 
+/// Headers are equal if their VCF text (see `to_vcf_bytes()`) is equal.
+impl PartialEq for HeaderView {
+    fn eq(&self, other: &HeaderView) -> bool {
+        self.to_vcf_bytes() == other.to_vcf_bytes()
+    }
+}
+
+impl Eq for HeaderView {}
+
 impl Drop for HeaderView {
     fn drop(&mut self) {
         unsafe {
@@ -458,15 +1425,195 @@ pub enum TagType {
     String,
 }
 
+/// Number of values of an `INFO` or `FORMAT` field (`Number` in the header).
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TagLength {
-    Fixed,
+    /// A fixed number of values, `0` for flags.
+    Fixed(u32),
     AltAlleles,
     Alleles,
     Genotypes,
     Variable,
 }
 
+/// Definition of an `INFO` or `FORMAT` field from the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagDefinition {
+    /// The ID of the field.
+    pub id: String,
+    /// The number of values (`Number`).
+    pub length: TagLength,
+    /// The type of the values (`Type`), `Character` is reported as `String`.
+    pub tag_type: TagType,
+    /// The description, without quotes.
+    pub description: String,
+}
+
+/// A `##SAMPLE` header line, see `HeaderView::sample_defs()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleDefinition {
+    /// The ID of the sample.
+    pub id: String,
+    /// The description, without quotes.
+    pub description: Option<String>,
+    /// The other keys and values, e.g., `Assay` or `Genomes`, without quotes.
+    pub values: LinearMap<String, String>,
+}
+
+/// A `##PEDIGREE` header line, see `HeaderView::pedigree_defs()`.
+///
+/// Depending on the VCF version, the line relates a child to its parents
+/// (`Child`, `Mother`, `Father`) or a derived genome to the original one (`Derived` or `ID`,
+/// `Original`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PedigreeDefinition {
+    /// All keys and values, without quotes.
+    pub values: LinearMap<String, String>,
+}
+
+impl PedigreeDefinition {
+    /// Return the `ID` of the line (VCF 4.3).
+    pub fn id(&self) -> Option<&str> {
+        self.get("ID")
+    }
+
+    /// Return the `Child` sample.
+    pub fn child(&self) -> Option<&str> {
+        self.get("Child")
+    }
+
+    /// Return the `Mother` sample.
+    pub fn mother(&self) -> Option<&str> {
+        self.get("Mother")
+    }
+
+    /// Return the `Father` sample.
+    pub fn father(&self) -> Option<&str> {
+        self.get("Father")
+    }
+
+    /// Return the `Derived` sample.
+    pub fn derived(&self) -> Option<&str> {
+        self.get("Derived")
+    }
+
+    /// Return the `Original` sample.
+    pub fn original(&self) -> Option<&str> {
+        self.get("Original")
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum HeaderRecordError {
+        InvalidId(id: String) {
+            description("invalid ID for header record")
+            display("invalid ID for header record: {}", id)
+        }
+        InvalidLength {
+            description("invalid number of values, only flags have none")
+        }
+        InvalidType {
+            description("invalid type for FORMAT field")
+        }
+        InvalidDescription {
+            description("description must not contain NUL or newline characters")
+        }
+        InvalidKey(key: String) {
+            description("invalid key for header record")
+            display("invalid key for header record: {}", key)
+        }
+        InvalidValue(value: String) {
+            description("invalid value for header record")
+            display("invalid value for header record: {:?}", value)
+        }
+        Some {
+            description("error adding header record")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum RenameError {
+        UnknownSample(name: String) {
+            description("unknown sample")
+            display("sample {} not found in header", name)
+        }
+        DuplicateSample(name: String) {
+            description("duplicate sample")
+            display("sample {} occurs more than once", name)
+        }
+        InvalidSample(name: String) {
+            description("invalid sample name")
+            display("invalid sample name: {:?}", name)
+        }
+        InvalidSampleCount {
+            description("number of samples does not match header")
+        }
+        Some {
+            description("error rebuilding header")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum CompatibilityError {
+        SampleMismatch {
+            description("headers have different samples")
+        }
+        ContigMismatch(name: String) {
+            description("headers have different contigs")
+            display("headers have different contigs: {}", name)
+        }
+        InfoMismatch(id: String) {
+            description("headers have different definitions of an INFO field")
+            display("headers have different definitions of INFO field {}", id)
+        }
+        FormatMismatch(id: String) {
+            description("headers have different definitions of a FORMAT field")
+            display("headers have different definitions of FORMAT field {}", id)
+        }
+        IdMismatch(id: String) {
+            description("headers assign an ID to different names")
+            display("headers assign the ID of {} to different names", id)
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum RemoveError {
+        UnknownTag(tag: String) {
+            description("tag not found in header")
+            display("tag {} not found in header", tag)
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ParseError {
+        Some {
+            description("error parsing header")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum MergeError {
+        Some {
+            description("error merging headers")
+        }
+    }
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum RidError {
@@ -503,6 +1650,13 @@ quick_error! {
         DuplicateSampleName {
             description("duplicate sample name when subsetting header")
         }
+        InvalidSampleIndex(index: usize) {
+            description("sample index out of range")
+            display("sample index out of range: {}", index)
+        }
+        Some {
+            description("error rebuilding header")
+        }
     }
 }
 
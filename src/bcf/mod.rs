@@ -13,19 +13,22 @@
 
 use std::ffi;
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 use url::Url;
 
 pub mod buffer;
 pub mod header;
+pub mod packed;
 pub mod record;
+pub mod record_builder;
 
 use bcf::header::{HeaderView, SampleSubset};
 use htslib;
 
 pub use bcf::header::{Header, HeaderRecord};
-pub use bcf::record::Record;
+pub use bcf::record::{Record, Unpack};
+pub use bcf::record_builder::RecordBuilder;
 
 /// Redefinition of corresponding `#define` in `vcf.h.`.
 pub const GT_MISSING: i32 = 0;
@@ -63,7 +66,13 @@ pub trait Read: Sized {
 #[derive(Debug)]
 pub struct Reader {
     inner: *mut htslib::htsFile,
-    header: Rc<HeaderView>,
+    header: Arc<HeaderView>,
+    /// The header records are parsed with, which is never shared (see `read()`).
+    parse_header: HeaderView,
+    /// Whether to skip records that did not pass all filters.
+    pass_only: bool,
+    /// The parts of records to decode when reading them.
+    unpack: Unpack,
 }
 
 unsafe impl Send for Reader {}
@@ -102,26 +111,65 @@ impl Reader {
     fn new(path: &[u8]) -> Result<Self, BCFError> {
         let htsfile = try!(bcf_open(path, b"r"));
         let header = unsafe { htslib::bcf_hdr_read(htsfile) };
+        if header.is_null() {
+            unsafe { htslib::hts_close(htsfile) };
+            return Err(BCFError::Some);
+        }
+        let header = HeaderView::new(header);
         Ok(Reader {
             inner: htsfile,
-            header: Rc::new(HeaderView::new(header)),
+            header: Arc::new(header.clone()),
+            parse_header: header,
+            pass_only: false,
+            unpack: Unpack::All,
         })
     }
+
+    /// Set the size of the BGZF block cache in bytes, see `bam::Read::set_cache_size()`.
+    pub fn set_cache_size(&mut self, cache_size: usize) {
+        unsafe { htslib::hts_set_cache_size(self.inner, cache_size as i32) }
+    }
+
+    /// Only yield records whose FILTER is `PASS` or missing (`.`) from `read()` and `records()`.
+    ///
+    /// For BCF files, the FILTER column is checked before unpacking INFO and FORMAT, so
+    /// skipping filtered records is much cheaper than checking `Record::is_pass()` yourself.
+    pub fn set_pass_only(&mut self, pass_only: bool) {
+        self.pass_only = pass_only;
+    }
+
+    /// Set the parts of records to decode when reading them (`Unpack::All` by default).
+    ///
+    /// The remaining parts are decoded on demand by the accessors of `Record`, so e.g.
+    /// `Unpack::Info` avoids decoding the FORMAT fields of records whose genotypes are never
+    /// accessed.
+    pub fn set_unpack(&mut self, unpack: Unpack) {
+        self.unpack = unpack;
+    }
 }
 
 impl Read for Reader {
     fn read(&mut self, record: &mut record::Record) -> Result<(), ReadError> {
-        match unsafe { htslib::bcf_read(self.inner, self.header.inner, record.inner) } {
-            0 => {
-                unsafe {
-                    // Always unpack record.
-                    htslib::bcf_unpack(record.inner_mut(), htslib::BCF_UN_ALL as i32);
+        loop {
+            let parse_header = self.parse_header.inner;
+            match unsafe { htslib::bcf_read(self.inner, parse_header, record.inner) } {
+                0 => {
+                    // Parsing VCF adds definitions of undefined contigs and tags to the header.
+                    // The header shared with the records must not change, it is replaced by a
+                    // copy of the updated one instead.
+                    if unsafe { (*parse_header).nhrec != (*self.header.inner).nhrec } {
+                        self.header = Arc::new(self.parse_header.clone());
+                    }
+                    if self.pass_only && !record.is_pass() {
+                        continue;
+                    }
+                    record.unpack(self.unpack);
+                    record.set_header_unchecked(self.header.clone());
+                    return Ok(());
                 }
-                record.set_header(self.header.clone());
-                Ok(())
+                -1 => return Err(ReadError::NoMoreRecord),
+                _ => return Err(ReadError::Invalid),
             }
-            -1 => Err(ReadError::NoMoreRecord),
-            _ => Err(ReadError::Invalid),
         }
     }
 
@@ -157,10 +205,12 @@ pub struct IndexedReader {
     /// The synced VCF/BCF reader to use internally.
     inner: *mut htslib::bcf_srs_t,
     /// The header.
-    header: Rc<HeaderView>,
+    header: Arc<HeaderView>,
 
     /// The position of the previous fetch, if any.
     current_region: Option<(u32, u32, u32)>,
+    /// Whether to skip records that did not pass all filters.
+    pass_only: bool,
 }
 
 unsafe impl Send for IndexedReader {}
@@ -198,13 +248,14 @@ impl IndexedReader {
         } // 0: BCF_SR_REQUIRE_IDX
           // Attach a file with the path from the arguments.
         if unsafe { htslib::bcf_sr_add_reader(ser_reader, path.as_ptr()) } >= 0 {
-            let header = Rc::new(HeaderView::new(unsafe {
+            let header = Arc::new(HeaderView::new(unsafe {
                 htslib::bcf_hdr_dup((*(*ser_reader).readers.offset(0)).header)
             }));
             Ok(IndexedReader {
                 inner: ser_reader,
                 header: header,
                 current_region: None,
+                pass_only: false,
             })
         } else {
             Err(IndexedReaderError::InvalidPath)
@@ -230,44 +281,60 @@ impl IndexedReader {
             Ok(())
         }
     }
+
+    /// Set the size of the BGZF block cache in bytes, see `bam::Read::set_cache_size()`.
+    pub fn set_cache_size(&mut self, cache_size: usize) {
+        unsafe { htslib::hts_set_cache_size(self.hts_file(), cache_size as i32) }
+    }
+
+    /// Return the file of the (only) reader of the synced reader.
+    fn hts_file(&self) -> *mut htslib::htsFile {
+        unsafe { (*(*self.inner).readers.offset(0)).file }
+    }
+
+    /// Only yield records whose FILTER is `PASS` or missing (`.`) from `read()` and `records()`.
+    pub fn set_pass_only(&mut self, pass_only: bool) {
+        self.pass_only = pass_only;
+    }
 }
 
 impl Read for IndexedReader {
     fn read(&mut self, record: &mut record::Record) -> Result<(), ReadError> {
-        match unsafe { htslib::bcf_sr_next_line(self.inner) } {
-            0 => {
-                if unsafe { (*self.inner).errnum } != 0 {
-                    Err(ReadError::SyncedBcfReaderError)
-                } else {
-                    Err(ReadError::NoMoreRecord)
-                }
-            }
-            i => {
-                assert!(i > 0, "Must not be negative");
-                // Note that the sync BCF reader has a different interface than the others
-                // as it keeps its own buffer already for each record.  An alternative here
-                // would be to replace the `inner` value by an enum that can be a pointer
-                // into a synced reader or an owning popinter to an allocated record.
-                unsafe {
-                    htslib::bcf_copy(
-                        record.inner,
-                        *(*(*self.inner).readers.offset(0)).buffer.offset(0),
-                    );
+        loop {
+            match unsafe { htslib::bcf_sr_next_line(self.inner) } {
+                0 => {
+                    if unsafe { (*self.inner).errnum } != 0 {
+                        return Err(ReadError::SyncedBcfReaderError);
+                    } else {
+                        return Err(ReadError::NoMoreRecord);
+                    }
                 }
+                i => {
+                    assert!(i > 0, "Must not be negative");
+                    // Note that the sync BCF reader has a different interface than the others
+                    // as it keeps its own buffer already for each record.  An alternative here
+                    // would be to replace the `inner` value by an enum that can be a pointer
+                    // into a synced reader or an owning popinter to an allocated record.
+                    unsafe {
+                        htslib::bcf_copy(
+                            record.inner,
+                            *(*(*self.inner).readers.offset(0)).buffer.offset(0),
+                        );
+                    }
 
-                record.set_header(self.header.clone());
+                    record.set_header_unchecked(self.header.clone());
 
-                match self.current_region {
-                    Some((rid, _start, end)) => {
-                        if record.rid().is_some() && rid == record.rid().unwrap()
-                            && record.pos() <= end
+                    if let Some((rid, _start, end)) = self.current_region {
+                        if record.rid().is_none() || rid != record.rid().unwrap()
+                            || record.pos() > end
                         {
-                            Ok(())
-                        } else {
-                            Err(ReadError::NoMoreRecord)
+                            return Err(ReadError::NoMoreRecord);
                         }
                     }
-                    None => Ok(()),
+                    if self.pass_only && !record.is_pass() {
+                        continue;
+                    }
+                    return Ok(());
                 }
             }
         }
@@ -303,12 +370,20 @@ impl Drop for IndexedReader {
     }
 }
 
+lazy_static! {
+    static ref TRANSLATE_LOCK: Mutex<()> = Mutex::new(());
+}
+
 /// A VCF/BCF writer.
 #[derive(Debug)]
 pub struct Writer {
     inner: *mut htslib::htsFile,
-    header: Rc<HeaderView>,
+    header: Arc<HeaderView>,
     subset: Option<SampleSubset>,
+    strict: bool,
+    header_written: bool,
+    // the last record header found to assign the same IDs as the header of this writer
+    checked_header: Option<Arc<HeaderView>>,
 }
 
 unsafe impl Send for Writer {}
@@ -372,16 +447,49 @@ impl Writer {
         };
 
         let htsfile = try!(bcf_open(path, mode));
-        unsafe { htslib::bcf_hdr_write(htsfile, header.inner) };
+        // the header is written before the first record, such that it can still be replaced
         Ok(Writer {
             inner: htsfile,
-            header: Rc::new(HeaderView::new(unsafe {
-                htslib::bcf_hdr_dup(header.inner)
-            })),
+            header: Arc::new(dup_header(header)),
             subset: header.subset.clone(),
+            strict: false,
+            header_written: false,
+            checked_header: None,
         })
     }
 
+    /// Replace the header of this writer, which is only possible before the first record has
+    /// been written.
+    ///
+    /// Changes to the `Header` the writer has been created with do not affect the writer, use
+    /// this method to add header lines afterwards.  Records created by `empty_record()`
+    /// before have to be translated to the new header (see `translate()`).
+    ///
+    /// # Arguments
+    ///
+    /// - `header` - The new header, e.g., created with `Header::from_template(writer.header())`
+    ///   and modified.
+    pub fn set_header(&mut self, header: &Header) -> Result<(), WriteError> {
+        if self.header_written {
+            return Err(WriteError::HeaderWritten);
+        }
+        self.header = Arc::new(dup_header(header));
+        self.subset = header.subset.clone();
+        self.checked_header = None;
+        Ok(())
+    }
+
+    /// Write the header if this has not been done yet.
+    fn write_header(&mut self) -> Result<(), WriteError> {
+        if !self.header_written {
+            if unsafe { htslib::bcf_hdr_write(self.inner, self.header.inner) } < 0 {
+                return Err(WriteError::Some);
+            }
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
     /// Obtain reference to the lightweight `HeaderView` of the BCF header.
     pub fn header(&self) -> &HeaderView {
         &self.header
@@ -396,14 +504,34 @@ impl Writer {
 
     /// Translate record to header of this writer.
     ///
+    /// The header of this writer has to contain all contigs, INFO, FORMAT and FILTER
+    /// definitions used by the record, e.g., by merging the headers of the inputs with
+    /// `Header::merge()`.  Note that htslib computes the translation table once per source
+    /// header, so the records of one source header should only be translated to one writer.
+    ///
     /// # Arguments
     ///
     /// - `record` - The `Record` to translate.
     pub fn translate(&mut self, record: &mut record::Record) {
-        unsafe {
-            htslib::bcf_translate(self.header.inner, record.header().inner, record.inner);
+        {
+            // htslib lazily stores the translation table in the source header, which may be
+            // shared with other threads
+            let _guard = TRANSLATE_LOCK.lock().unwrap();
+            unsafe {
+                htslib::bcf_translate(self.header.inner, record.header().inner, record.inner);
+            }
         }
-        record.set_header(self.header.clone());
+        record.set_header_unchecked(self.header.clone());
+    }
+
+    /// Translate `record` to the header of this writer and write it, see `translate()`.
+    ///
+    /// # Arguments
+    ///
+    /// - `record` - The `Record` to translate and write.
+    pub fn translate_and_write(&mut self, record: &mut record::Record) -> Result<(), WriteError> {
+        self.translate(record);
+        self.write(record)
     }
 
     /// Subset samples of record to match header of this writer.
@@ -427,10 +555,22 @@ impl Writer {
 
     /// Write `record` to the Writer.
     ///
+    /// The header of the record has to assign the same IDs as the header of this writer (see
+    /// `HeaderView::check_same_ids()`), which is checked once per record header.  Otherwise,
+    /// the record has to be translated first (see `translate()`).  IDs that are used by the
+    /// record but not defined in the header of this writer are only detected in strict mode
+    /// (see `set_strict()`).
+    ///
     /// # Arguments
     ///
     /// - `record` - The `Record` to write.
     pub fn write(&mut self, record: &record::Record) -> Result<(), WriteError> {
+        if self.strict {
+            try!(self.validate(record));
+        } else {
+            try!(self.check_header(record));
+        }
+        try!(self.write_header());
         if unsafe { htslib::bcf_write(self.inner, self.header.inner, record.inner) } == -1 {
             Err(WriteError::Some)
         } else {
@@ -438,6 +578,121 @@ impl Writer {
         }
     }
 
+    /// Enable or disable strict mode (disabled by default).
+    ///
+    /// In strict mode, `write()` fails instead of writing broken output if the record refers
+    /// to a contig, `FILTER`, `INFO` or `FORMAT` ID that is not defined in the header of this
+    /// writer or has a different numeric ID there, e.g., because `translate()` has not been
+    /// called for a record read with another header.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Check that the header of `record` assigns the same IDs as the header of this writer.
+    fn check_header(&mut self, record: &record::Record) -> Result<(), WriteError> {
+        let header = record.header_arc();
+        if Arc::ptr_eq(header, &self.header) {
+            return Ok(());
+        }
+        if let Some(ref checked) = self.checked_header {
+            if Arc::ptr_eq(header, checked) {
+                return Ok(());
+            }
+        }
+        try!(
+            self.header
+                .check_same_ids(header)
+                .map_err(|_| WriteError::HeaderMismatch)
+        );
+        self.checked_header = Some(header.clone());
+        Ok(())
+    }
+
+    /// Check that all IDs used by `record` are defined in the header of this writer, with the
+    /// same name as in the header of the record.
+    fn validate(&self, record: &record::Record) -> Result<(), WriteError> {
+        unsafe { htslib::bcf_unpack(record.inner, htslib::BCF_UN_ALL as i32) };
+        let inner = record.inner();
+        let header = record.header().inner;
+        if inner.rid >= 0 && !self.is_defined(header, htslib::BCF_DT_CTG, inner.rid, None) {
+            return Err(WriteError::UndefinedContig(id_name(
+                header,
+                htslib::BCF_DT_CTG,
+                inner.rid,
+            )));
+        }
+        let d = &inner.d;
+        for i in 0..d.n_flt as isize {
+            let id = unsafe { *d.flt.offset(i) };
+            if !self.is_defined(header, htslib::BCF_DT_ID, id, Some(htslib::BCF_HL_FLT)) {
+                return Err(WriteError::UndefinedFilter(id_name(
+                    header,
+                    htslib::BCF_DT_ID,
+                    id,
+                )));
+            }
+        }
+        for i in 0..inner.n_info() as isize {
+            let info = unsafe { &*d.info.offset(i) };
+            // skip removed entries
+            if !info.vptr.is_null()
+                && !self.is_defined(header, htslib::BCF_DT_ID, info.key, Some(htslib::BCF_HL_INFO))
+            {
+                return Err(WriteError::UndefinedInfo(id_name(
+                    header,
+                    htslib::BCF_DT_ID,
+                    info.key,
+                )));
+            }
+        }
+        for i in 0..inner.n_fmt() as isize {
+            let fmt = unsafe { &*d.fmt.offset(i) };
+            if !fmt.p.is_null()
+                && !self.is_defined(header, htslib::BCF_DT_ID, fmt.id, Some(htslib::BCF_HL_FMT))
+            {
+                return Err(WriteError::UndefinedFormat(id_name(
+                    header,
+                    htslib::BCF_DT_ID,
+                    fmt.id,
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return whether the `id` from dictionary `dict` of the record header `src` has the same
+    /// name and ID in the header of this writer and, if given, is defined as `hl_type`
+    /// (e.g., `BCF_HL_INFO`) there.
+    fn is_defined(
+        &self,
+        src: *const htslib::bcf_hdr_t,
+        dict: u32,
+        id: i32,
+        hl_type: Option<u32>,
+    ) -> bool {
+        let dst = self.header.inner;
+        unsafe {
+            if id < 0 || id >= (*src).n[dict as usize] || id >= (*dst).n[dict as usize] {
+                return false;
+            }
+            let src_pair = *(*src).id[dict as usize].offset(id as isize);
+            let dst_pair = *(*dst).id[dict as usize].offset(id as isize);
+            if src_pair.key.is_null()
+                || dst_pair.key.is_null()
+                || ffi::CStr::from_ptr(src_pair.key) != ffi::CStr::from_ptr(dst_pair.key)
+            {
+                return false;
+            }
+            match hl_type {
+                // as `bcf_hdr_idinfo_exists()`
+                Some(hl_type) => {
+                    !dst_pair.val.is_null() && (*dst_pair.val).info[hl_type as usize] & 0xf != 0xf
+                }
+                None => true,
+            }
+        }
+    }
+
     /// Activate multi-threaded BCF write support in htslib. This should permit faster
     /// writing of large BCF files.
     ///
@@ -451,6 +706,8 @@ impl Writer {
 
 impl Drop for Writer {
     fn drop(&mut self) {
+        // files without records still need a header
+        let _ = self.write_header();
         unsafe {
             htslib::hts_close(self.inner);
         }
@@ -475,6 +732,19 @@ impl<'a, R: Read> Iterator for Records<'a, R> {
     }
 }
 
+/// Return the name of `id` in dictionary `dict` of the header, for error messages.
+fn id_name(header: *const htslib::bcf_hdr_t, dict: u32, id: i32) -> String {
+    unsafe {
+        if id >= 0 && id < (*header).n[dict as usize] {
+            let key = (*(*header).id[dict as usize].offset(id as isize)).key;
+            if !key.is_null() {
+                return ffi::CStr::from_ptr(key).to_string_lossy().into_owned();
+            }
+        }
+    }
+    format!("#{}", id)
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum BCFError {
@@ -516,6 +786,15 @@ fn bcf_open(path: &[u8], mode: &[u8]) -> Result<*mut htslib::htsFile, BCFError>
     }
 }
 
+/// Duplicate a header for use by a writer.  The header is synchronized first, such that samples
+/// and header lines added since are not lost in the copy.
+fn dup_header(header: &Header) -> HeaderView {
+    unsafe {
+        htslib::bcf_hdr_sync(header.inner);
+        HeaderView::new(htslib::bcf_hdr_dup(header.inner))
+    }
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum ReadError {
@@ -568,6 +847,28 @@ quick_error! {
         Some {
             description("failed to write record")
         }
+        HeaderWritten {
+            description("header cannot be replaced after writing records")
+        }
+        HeaderMismatch {
+            description("header of record does not match header of writer, translate record")
+        }
+        UndefinedContig(name: String) {
+            description("contig not defined in header")
+            display("contig {} not defined in header", name)
+        }
+        UndefinedFilter(name: String) {
+            description("FILTER not defined in header")
+            display("FILTER {} not defined in header", name)
+        }
+        UndefinedInfo(name: String) {
+            description("INFO field not defined in header")
+            display("INFO field {} not defined in header", name)
+        }
+        UndefinedFormat(name: String) {
+            description("FORMAT field not defined in header")
+            display("FORMAT field {} not defined in header", name)
+        }
     }
 }
 
@@ -584,12 +885,25 @@ quick_error! {
 mod tests {
     extern crate tempdir;
     use super::*;
-    use bcf::header::Id;
-    use bcf::record::Numeric;
+    use bcf::header::{
+        CompatibilityError, Id, IdKind, RemoveError, RenameError, SubsetError, TagDefinition,
+        TagLength, TagType,
+    };
+    use bcf::record::{
+        genotype_index, genotype_order, AlleleWriteError, Breakend, BreakendParseError,
+        CheckRefError, FillTag, FillTagsError,
+        FormatReadError, Genotype, GenotypeAllele, JoinError, NormalizeError, Numeric, Side,
+        SvInfo, SvInfoError, SvType, TagWriteError, VariantType,
+        MISSING_FLOAT_BITS, MISSING_INTEGER, VECTOR_END_FLOAT_BITS, VECTOR_END_INTEGER,
+    };
+    use bcf::record_builder::RecordBuildError;
+    use faidx;
     use std::fs::File;
     use std::io::prelude::Read as IoRead;
+    use std::io::prelude::Write as IoWrite;
     use std::path::Path;
     use std::str;
+    use std::thread;
 
     fn _test_read<P: AsRef<Path>>(path: &P) {
         let mut bcf = Reader::from_path(path).ok().expect("Error opening file.");
@@ -648,6 +962,38 @@ mod tests {
         bcf.set_threads(2).unwrap();
     }
 
+    #[test]
+    fn test_reader_set_cache_size() {
+        let mut bcf = IndexedReader::from_path(&"test/test.bcf")
+            .ok()
+            .expect("Error opening file.");
+        bcf.set_cache_size(10 * 1024 * 1024);
+        let mut bcf = Reader::from_path(&"test/test.bcf")
+            .ok()
+            .expect("Error opening file.");
+        bcf.set_cache_size(10 * 1024 * 1024);
+        assert!(bcf.records().next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_reader_pass_only() {
+        let mut bcf = Reader::from_path(&"test/test_filters.vcf")
+            .ok()
+            .expect("Error opening file.");
+        bcf.set_pass_only(true);
+
+        let mut ids = Vec::new();
+        let mut depths = Vec::new();
+        for record in bcf.records() {
+            let mut record = record.ok().expect("Error reading record.");
+            assert!(record.is_pass());
+            ids.push(record.id());
+            depths.push(record.info(b"DP").integer().unwrap().unwrap()[0]);
+        }
+        assert_eq!(ids, vec![b"a".to_vec(), b"c".to_vec(), b"d".to_vec()]);
+        assert_eq!(depths, vec![10, 7, 12]);
+    }
+
     #[test]
     fn test_writer_set_threads() {
         let path = &"test/test.bcf";
@@ -816,56 +1162,2176 @@ mod tests {
     }
 
     #[test]
-    fn test_header_ids() {
-        let vcf = Reader::from_path(&"test/test_string.vcf")
+    fn test_genotypes_iter() {
+        let mut vcf = Reader::from_path(&"test/test_string.vcf")
             .ok()
             .expect("Error opening file.");
-        let header = &vcf.header();
-        use bcf::header::Id;
+        let mut rec = vcf.records().next().unwrap().unwrap();
+        let genotypes = rec.genotypes().expect("Error reading genotypes");
+        assert_eq!(genotypes.len(), 2);
+        let genotypes: Vec<Genotype> = genotypes.iter().collect();
+        assert_eq!(
+            *genotypes[0],
+            vec![GenotypeAllele::UnphasedMissing, GenotypeAllele::Unphased(1)]
+        );
+        assert_eq!(*genotypes[1], vec![GenotypeAllele::Unphased(1), GenotypeAllele::Unphased(1)]);
+        assert_eq!(genotypes[1][1].index(), Some(1));
+    }
 
-        assert_eq!(header.id_to_name(Id(4)), b"GT");
-        assert_eq!(header.name_to_id(b"GT").unwrap(), Id(4));
-        assert!(header.name_to_id(b"XX").is_err());
+    #[test]
+    fn test_push_genotypes() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let header = Header::from_template(reader.header());
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            let mut record = reader.records().next().unwrap().unwrap();
+            writer.translate(&mut record);
+            record
+                .push_genotypes(&[
+                    GenotypeAllele::Unphased(0),
+                    GenotypeAllele::Phased(1),
+                    GenotypeAllele::UnphasedMissing,
+                    GenotypeAllele::PhasedMissing,
+                ])
+                .unwrap();
+            writer.write(&record).unwrap();
+            record.push_genotype_vectors(&[&[GenotypeAllele::Unphased(1)], &[]]).unwrap();
+            writer.write(&record).unwrap();
+            match record.push_genotype_vectors(&[&[]]) {
+                Err(TagWriteError::InvalidSampleCount) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let expected = [["0|1", ".|."], ["1", "."]];
+        for (rec, exp_gts) in reader.records().zip(expected.iter()) {
+            let mut rec = rec.unwrap();
+            let genotypes = rec.genotypes().unwrap();
+            assert_eq!(format!("{}", genotypes.get(0)), exp_gts[0]);
+            assert_eq!(format!("{}", genotypes.get(1)), exp_gts[1]);
+        }
+        for gt in &[
+            GenotypeAllele::Unphased(3),
+            GenotypeAllele::Phased(0),
+            GenotypeAllele::UnphasedMissing,
+            GenotypeAllele::PhasedMissing,
+        ] {
+            assert_eq!(GenotypeAllele::from_encoded(gt.to_encoded()), *gt);
+        }
     }
 
     #[test]
-    fn test_header_samples() {
-        let vcf = Reader::from_path(&"test/test_string.vcf")
+    fn test_push_genotype_vectors_empty_record() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let mut header = Header::new();
+            header
+                .push_sample(b"s1")
+                .push_sample(b"s2")
+                .add_contig(b"chrX", None)
+                .unwrap()
+                .add_format(b"GT", TagLength::Fixed(1), TagType::String, "Genotype")
+                .unwrap();
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            let mut record = writer.empty_record();
+            record.set_rid(&Some(0));
+            record.set_alleles(&[&b"A"[..], &b"C"[..]]).unwrap();
+            record
+                .push_genotype_vectors(&[
+                    &[GenotypeAllele::Unphased(0), GenotypeAllele::Phased(1)],
+                    &[GenotypeAllele::Unphased(1)],
+                ])
+                .unwrap();
+            writer.write(&record).unwrap();
+            record.push_genotype_vectors(&[&[GenotypeAllele::Unphased(1)], &[]]).unwrap();
+            writer.write(&record).unwrap();
+            match record.push_genotype_vectors(&[&[GenotypeAllele::Unphased(1)]]) {
+                Err(TagWriteError::InvalidSampleCount) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        let mut reader = Reader::from_path(&path)
             .ok()
             .expect("Error opening file.");
-        let header = &vcf.header();
+        let expected = [["0|1", "1"], ["1", "."]];
+        let mut count = 0;
+        for (rec, exp_gts) in reader.records().zip(expected.iter()) {
+            let mut rec = rec.unwrap();
+            let genotypes = rec.genotypes().unwrap();
+            assert_eq!(format!("{}", genotypes.get(0)), exp_gts[0]);
+            assert_eq!(format!("{}", genotypes.get(1)), exp_gts[1]);
+            count += 1;
+        }
+        assert_eq!(count, 2);
+    }
 
-        assert_eq!(header.id_to_sample(Id(0)), b"one");
-        assert_eq!(header.id_to_sample(Id(1)), b"two");
-        assert_eq!(header.sample_to_id(b"one").unwrap(), Id(0));
-        assert_eq!(header.sample_to_id(b"two").unwrap(), Id(1));
-        assert!(header.sample_to_id(b"three").is_err());
+    #[test]
+    fn test_has_filter_name() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = reader.header();
+        let mut record = reader.empty_record();
+        assert!(record.has_filter_name(b"PASS").unwrap());
+        assert!(record.has_filter_name(b".").unwrap());
+        assert!(!record.has_filter_name(b"FILTER1").unwrap());
+        record.set_filters(&[header.name_to_id(b"FILTER1").unwrap()]);
+        assert!(record.has_filter_name(b"FILTER1").unwrap());
+        assert!(!record.has_filter_name(b"PASS").unwrap());
+        let filter1 = header.name_to_id(b"FILTER1").unwrap();
+        assert_eq!(record.filters().collect::<Vec<_>>(), vec![filter1]);
+        assert!(record.has_filter_name(b"INFO1").is_err());
+        assert!(record.has_filter_name(b"FILTER3").is_err());
     }
 
     #[test]
-    fn test_header_records() {
-        let vcf = Reader::from_path(&"test/test_string.vcf")
+    fn test_set_alleles() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
             .ok()
             .expect("Error opening file.");
-        let records = vcf.header().header_records();
-        assert_eq!(records.len(), 9);
+        let mut record = reader.records().next().unwrap().unwrap();
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"AG"[..]]);
+        record.set_alleles(&[b"AT", b"A", b"ATT"]).unwrap();
+        assert_eq!(record.alleles(), vec![&b"AT"[..], &b"A"[..], &b"ATT"[..]]);
+        assert_eq!(record.allele_count(), 3);
+        for alleles in &[&[][..], &[&b""[..]][..], &[&b"A"[..], &b"C,G"[..]][..]] {
+            match record.set_alleles(alleles) {
+                Err(AlleleWriteError::InvalidAllele) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        assert_eq!(record.allele_count(), 3);
+    }
 
-        match &records[0] {
-            &HeaderRecord::Filter {
-                ref key,
-                ref values,
-            } => {
-                assert_eq!(key, "FILTER");
-                assert_eq!(values["ID"], "PASS");
+    #[test]
+    fn test_record_ids() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        assert_eq!(record.ids(), vec![&b"rs1234"[..]]);
+        record.push_id(b"rs5678").unwrap();
+        record.push_id(b"rs1234").unwrap();
+        assert_eq!(record.id(), b"rs1234;rs5678");
+        assert_eq!(record.ids(), vec![&b"rs1234"[..], &b"rs5678"[..]]);
+        record.clear_id().unwrap();
+        assert!(record.ids().is_empty());
+        assert_eq!(record.id(), b".");
+    }
+
+    #[test]
+    fn test_trim_alleles() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        record.set_alleles(&[b"A", b"AG", b"C"]).unwrap();
+        record.trim_alleles().unwrap();
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"AG"[..]]);
+
+        record
+            .push_genotypes(&[
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Unphased(0),
+            ])
+            .unwrap();
+        record.trim_alleles().unwrap();
+        assert_eq!(record.alleles(), vec![&b"A"[..]]);
+    }
+
+    #[test]
+    fn test_remove_alleles() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        record.set_alleles(&[b"A", b"C", b"AG"]).unwrap();
+        record
+            .push_genotypes(&[
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Unphased(2),
+                GenotypeAllele::Unphased(2),
+                GenotypeAllele::Unphased(2),
+            ])
+            .unwrap();
+
+        assert!(record.remove_alleles(&[0]).is_err());
+        assert!(record.remove_alleles(&[3]).is_err());
+
+        record.remove_alleles(&[1]).unwrap();
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"AG"[..]]);
+        let genotypes = record.genotypes().unwrap();
+        assert_eq!(
+            *genotypes.get(0),
+            vec![GenotypeAllele::Unphased(0), GenotypeAllele::Unphased(1)]
+        );
+        assert_eq!(
+            *genotypes.get(1),
+            vec![GenotypeAllele::Unphased(1), GenotypeAllele::Unphased(1)]
+        );
+    }
+
+    #[test]
+    fn test_variant_types() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        assert_eq!(record.variant_types(), vec![VariantType::Insertion]);
+        assert_eq!(record.variant_type(), Some(VariantType::Insertion));
+
+        record
+            .set_alleles(&[b"AC", b"GC", b"TG", b"A", b"ACT", b"<DEL>", b"A]19:100]", b"*"])
+            .unwrap();
+        assert_eq!(
+            record.variant_types(),
+            vec![
+                VariantType::Snv,
+                VariantType::Mnv,
+                VariantType::Deletion,
+                VariantType::Insertion,
+                VariantType::Symbolic,
+                VariantType::Breakend,
+                VariantType::Reference,
+            ]
+        );
+        assert_eq!(record.variant_type(), Some(VariantType::Complex));
+
+        record.set_alleles(&[b"AC", b"GC", b"*"]).unwrap();
+        assert_eq!(record.variant_type(), Some(VariantType::Snv));
+        record.set_alleles(&[b"AC"]).unwrap();
+        assert_eq!(record.variant_type(), None);
+    }
+
+    #[test]
+    fn test_end_rlen() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##INFO=<ID=END,Number=1,Type=Integer,Description=\"End\">");
+        });
+        assert_eq!(record.pos(), 3111938);
+        assert_eq!(record.end(), 3111939);
+        assert_eq!(record.rlen(), 1);
+        record.set_alleles(&[b"A", b"<DEL>"]).unwrap();
+        assert_eq!(record.end(), 3111939);
+        record.push_info_integer(b"END", &[3112000]).unwrap();
+        assert_eq!(record.end(), 3112000);
+        assert_eq!(record.rlen(), 62);
+    }
+
+    #[test]
+    fn test_record_clone() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        record.set_pos(10);
+        record.set_alleles(&[b"A", b"C"]).unwrap();
+
+        let mut copy = record.clone();
+        assert_eq!(copy.pos(), 10);
+        assert_eq!(copy.alleles(), vec![&b"A"[..], &b"C"[..]]);
+        assert_eq!(copy.id(), b"rs1234");
+        assert_eq!(copy.header().sample_count(), 2);
+        assert_eq!(
+            copy.format(b"FS1").string().unwrap(),
+            vec![&b"LongString1"[..], &b"ss1"[..]]
+        );
+
+        copy.set_pos(20);
+        copy.set_alleles(&[b"A", b"G"]).unwrap();
+        assert_eq!(record.pos(), 10);
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"C"[..]]);
+    }
+
+    #[test]
+    fn test_record_to_vcf_string() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let record = reader.records().next().unwrap().unwrap();
+        let expected = "19\t3111939\trs1234\tA\tAG\t.\tPASS\tS1=string1;N1=1;F1=1\t\
+                        GT:FS1:FN1\t./1:LongString1:1\t1/1:ss1:2";
+        assert_eq!(record.to_vcf_string().unwrap(), expected);
+        assert_eq!(record.to_vcf_bytes().unwrap(), expected.as_bytes());
+        assert_eq!(format!("{}", record), expected);
+    }
+
+    #[test]
+    fn test_record_from_vcf_line() {
+        let reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let line = b"19\t3111939\trs1234\tA\tAG\t.\tPASS\tS1=string1;N1=1;F1=1\t\
+                     GT:FS1:FN1\t./1:LongString1:1\t1/1:ss1:2\n";
+        let mut record = Record::from_vcf_line(reader.header(), line).unwrap();
+        assert_eq!(record.pos(), 3111938);
+        assert_eq!(record.id(), b"rs1234");
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"AG"[..]]);
+        assert_eq!(record.info(b"S1").string().unwrap(), Some(vec![&b"string1"[..]]));
+        assert_eq!(record.format(b"FN1").integer().unwrap(), vec![&[1][..], &[2][..]]);
+        assert_eq!(record.to_vcf_bytes().unwrap(), &line[..line.len() - 1]);
+
+        let mut record = reader.empty_record();
+        record.parse_vcf_line(b"19\t100\t.\tG\tC\t10\t.\t.\tGT\t0/1\t1/1").unwrap();
+        assert_eq!(record.pos(), 99);
+        assert_eq!(record.qual(), 10.0);
+        assert!(record.parse_vcf_line(b"19\t100\t.\tG\0\tC").is_err());
+    }
+
+    #[test]
+    fn test_unpack() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        reader.set_unpack(Unpack::Str);
+        let mut record = reader.records().next().unwrap().unwrap();
+        let unpacked = |record: &Record| record.inner().unpacked as u32;
+        assert_eq!(unpacked(&record), htslib::BCF_UN_STR);
+        assert_eq!(record.alleles(), vec![&b"A"[..], &b"AG"[..]]);
+        assert_eq!(record.id(), b"rs1234");
+        assert!(record.is_pass());
+        assert_eq!(unpacked(&record), htslib::BCF_UN_STR | htslib::BCF_UN_FLT);
+
+        record.unpack(Unpack::Format);
+        assert_eq!(
+            unpacked(&record),
+            htslib::BCF_UN_STR | htslib::BCF_UN_FLT | htslib::BCF_UN_FMT
+        );
+        assert_eq!(record.format(b"FN1").integer().unwrap(), vec![&[1][..], &[2][..]]);
+        assert_eq!(record.info(b"N1").integer().unwrap(), Some(&[1][..]));
+        assert_eq!(unpacked(&record), htslib::BCF_UN_ALL);
+    }
+
+    #[test]
+    fn test_info_owned() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        let n1 = record.info(b"N1").integer_owned().unwrap();
+        let f1 = record.info(b"F1").float_owned().unwrap();
+        let s1 = record.info(b"S1").string_owned().unwrap();
+        let wrong_type = record.info(b"N1").float_owned();
+        assert_eq!(n1, Some(vec![1]));
+        assert_eq!(f1, Some(vec![1.0]));
+        assert_eq!(s1, Some(vec![b"string1".to_vec()]));
+        assert!(wrong_type.is_err());
+        assert!(record.info(b"X").integer_owned().is_err());
+    }
+
+    #[test]
+    fn test_record_samples() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        let samples = record.samples().collect::<Vec<_>>();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].index(), 0);
+        assert_eq!(samples[1].name(), b"two");
+        assert_eq!(
+            *samples[0].genotype().unwrap(),
+            vec![GenotypeAllele::UnphasedMissing, GenotypeAllele::Unphased(1)]
+        );
+        assert_eq!(samples[1].string(b"FS1").unwrap(), b"ss1");
+        assert_eq!(samples[0].integer(b"FN1").unwrap(), vec![1]);
+        assert!(samples[0].float(b"FN1").is_err());
+        assert!(samples[0].depth().is_err());
+
+        record.push_format_integer(b"FN1", &[i32::missing(), 300]).unwrap();
+        record.push_format_float(b"FF1", &[1.5, 2.5]).unwrap();
+        let values = record
+            .samples()
+            .map(|sample| (sample.integer(b"FN1").unwrap(), sample.float(b"FF1").unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![(vec![i32::missing()], vec![1.5]), (vec![300], vec![2.5])]);
+    }
+
+    #[test]
+    fn test_record_builder() {
+        let reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let header = Header::from_template(reader.header());
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            let mut builder = RecordBuilder::new();
+            builder
+                .contig(b"19")
+                .pos(3111938)
+                .id(b"rs1234")
+                .alleles(&[b"A", b"AG"])
+                .qual(20.0)
+                .filter(b"PASS")
+                .info_string(b"S1", &[b"string1"])
+                .info_integer(b"N1", &[1])
+                .format_string(b"FS1", &[b"LongString1", b"ss1"])
+                .format_integer(b"FN1", &[1, 2])
+                .genotypes(&[
+                    GenotypeAllele::UnphasedMissing,
+                    GenotypeAllele::Unphased(1),
+                    GenotypeAllele::Unphased(1),
+                    GenotypeAllele::Unphased(1),
+                ]);
+            let record = builder.build(&writer).unwrap();
+            writer.write(&record).unwrap();
+
+            let mut record = writer.empty_record();
+            builder.pos(3111939).qual(f32::missing());
+            builder.build_into(&mut record).unwrap();
+            writer.write(&record).unwrap();
+
+            match RecordBuilder::new().pos(1).alleles(&[b"A"]).build(&writer) {
+                Err(RecordBuildError::MissingContig) => (),
+                r => panic!("unexpected result: {:?}", r),
             }
-            _ => {
-                assert!(false);
+            match builder.contig(b"20").build(&writer) {
+                Err(RecordBuildError::UndefinedContig(ref contig)) if contig == "20" => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            builder.contig(b"19");
+            match builder.info_float(b"N1", &[1.0]).build(&writer) {
+                Err(RecordBuildError::UnexpectedType(ref tag)) if tag == "N1" => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            builder.info_integer(b"N1", &[1]);
+            match builder.format_integer(b"FN1", &[1]).build(&writer) {
+                Err(RecordBuildError::InvalidSampleCount(ref tag)) if tag == "FN1" => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            builder.format_integer(b"FN1", &[1, 2]);
+            match builder.format_integer(b"XX", &[1, 2]).build(&writer) {
+                Err(RecordBuildError::UndefinedFormat(ref tag)) if tag == "XX" => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+
+            let mut builder = RecordBuilder::new();
+            builder.contig(b"19").pos(1).alleles(&[b"A"]);
+            for (tag, builder) in vec![
+                ("N1", builder.clone().info_integer(b"N1", &[])),
+                ("F1", builder.clone().info_float(b"F1", &[])),
+                ("S1", builder.clone().info_string(b"S1", &[])),
+                ("FN1", builder.clone().format_integer(b"FN1", &[])),
+                ("FF1", builder.clone().format_float(b"FF1", &[])),
+                ("GT", builder.clone().genotypes(&[])),
+            ] {
+                match builder.build(&writer) {
+                    Err(RecordBuildError::MissingValues(ref t)) if t == tag => (),
+                    r => panic!("unexpected result: {:?}", r),
+                }
             }
         }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let lines = reader
+            .records()
+            .map(|r| r.unwrap().to_vcf_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "19\t3111939\trs1234\tA\tAG\t20\tPASS\tS1=string1;N1=1\t\
+                 GT:FS1:FN1\t./1:LongString1:1\t1/1:ss1:2",
+                "19\t3111940\trs1234\tA\tAG\t.\tPASS\tS1=string1;N1=1\t\
+                 GT:FS1:FN1\t./1:LongString1:1\t1/1:ss1:2",
+            ]
+        );
     }
 
-    // Helper function reading full file into string.
-    fn read_all<P: AsRef<Path>>(path: P) -> String {
+    #[test]
+    fn test_normalize() {
+        let reference = faidx::Reader::from_path("test/test_gc.fa")
+            .ok()
+            .expect("Error opening file.");
+        let mut header = Header::new();
+        header.add_contigs_from_fai(&reference).unwrap();
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let mut record = writer.empty_record();
+        record.set_rid(&Some(0));
+
+        // deletion of an AT unit in the ATAT... repeat starting at position 100
+        record.set_pos(110);
+        record.set_alleles(&[b"ATA", b"A"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), true);
+        assert_eq!(record.pos(), 99);
+        assert_eq!(record.alleles(), vec![&b"CAT"[..], &b"C"[..]]);
+        assert_eq!(record.normalize(&reference).unwrap(), false);
+
+        // insertions with common bases at both ends
+        record.set_pos(0);
+        record.set_alleles(&[b"GCG", b"GCTTG", b"GCGCG"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), true);
+        assert_eq!(record.pos(), 1);
+        assert_eq!(record.alleles(), vec![&b"C"[..], &b"CTT"[..], &b"CGC"[..]]);
+
+        record.set_pos(0);
+        record.set_alleles(&[b"gcg", b"gtg"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), true);
+        assert_eq!(record.pos(), 1);
+        assert_eq!(record.alleles(), vec![&b"C"[..], &b"T"[..]]);
+
+        record.set_alleles(&[b"C", b"<DEL>"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), false);
+
+        // sites without a distinct alternative allele are left unchanged
+        record.set_alleles(&[b"CGC"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), false);
+        assert_eq!(record.pos(), 1);
+        assert_eq!(record.alleles(), vec![&b"CGC"[..]]);
+        record.set_alleles(&[b"CGC", b"CGC"]).unwrap();
+        assert_eq!(record.normalize(&reference).unwrap(), false);
+        assert_eq!(record.pos(), 1);
+        assert_eq!(record.alleles(), vec![&b"CGC"[..], &b"CGC"[..]]);
+
+        record.set_alleles(&[b"T", b"C"]).unwrap();
+        match record.normalize(&reference) {
+            Err(NormalizeError::ReferenceMismatch) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        record.set_rid(&None);
+        match record.normalize(&reference) {
+            Err(NormalizeError::MissingContig) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_split_multiallelic() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##INFO=<ID=AC,Number=A,Type=Integer,Description=\"AC\">");
+            header.push_record(b"##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"AD\">");
+        });
+
+        assert_eq!(record.split_multiallelic().unwrap().len(), 1);
+
+        record.set_alleles(&[b"A", b"AG", b"C"]).unwrap();
+        record.push_info_integer(b"AC", &[1, 2]).unwrap();
+        record.push_format_integer(b"AD", &[5, 3, 0, 4, 0, 6]).unwrap();
+        record
+            .push_genotypes(&[
+                GenotypeAllele::Unphased(1),
+                GenotypeAllele::Unphased(2),
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Phased(2),
+            ])
+            .unwrap();
+        let mut split = record.split_multiallelic().unwrap();
+        assert_eq!(split.len(), 2);
+
+        assert_eq!(split[0].alleles(), vec![&b"A"[..], &b"AG"[..]]);
+        assert_eq!(split[0].info(b"AC").integer().unwrap(), Some(&[1][..]));
+        assert_eq!(split[0].format(b"AD").integer().unwrap(), vec![&[5, 3][..], &[4, 0][..]]);
+        let genotypes = split[0].genotypes().unwrap();
+        assert_eq!(
+            *genotypes.get(0),
+            vec![GenotypeAllele::Unphased(1), GenotypeAllele::Unphased(0)]
+        );
+        assert_eq!(
+            *genotypes.get(1),
+            vec![GenotypeAllele::Unphased(0), GenotypeAllele::Phased(0)]
+        );
+
+        assert_eq!(split[1].alleles(), vec![&b"A"[..], &b"C"[..]]);
+        assert_eq!(split[1].info(b"AC").integer().unwrap(), Some(&[2][..]));
+        assert_eq!(split[1].format(b"AD").integer().unwrap(), vec![&[5, 0][..], &[4, 6][..]]);
+        let genotypes = split[1].genotypes().unwrap();
+        assert_eq!(
+            *genotypes.get(0),
+            vec![GenotypeAllele::Unphased(0), GenotypeAllele::Unphased(1)]
+        );
+        assert_eq!(
+            *genotypes.get(1),
+            vec![GenotypeAllele::Unphased(0), GenotypeAllele::Phased(1)]
+        );
+
+        assert_eq!(record.allele_count(), 3);
+    }
+
+    #[test]
+    fn test_join_multiallelic() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##INFO=<ID=AC,Number=A,Type=Integer,Description=\"AC\">");
+            header.push_record(b"##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"AD\">");
+            header.push_record(b"##FORMAT=<ID=PL,Number=G,Type=Integer,Description=\"PL\">");
+        });
+        record.set_alleles(&[b"A", b"AG", b"C"]).unwrap();
+        record.push_info_integer(b"AC", &[1, 2]).unwrap();
+        record.push_format_integer(b"AD", &[5, 3, 0, 4, 0, 6]).unwrap();
+        record
+            .push_genotypes(&[
+                GenotypeAllele::Unphased(1),
+                GenotypeAllele::Unphased(2),
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Phased(2),
+            ])
+            .unwrap();
+
+        let mut split = record.split_multiallelic().unwrap();
+        split[0].push_format_integer(b"PL", &[0, 10, 20, 1, 11, 21]).unwrap();
+        split[1].push_format_integer(b"PL", &[0, 30, 40, 2, 32, 42]).unwrap();
+        let mut joined = Record::join_multiallelic(&split).unwrap();
+        assert_eq!(joined.alleles(), vec![&b"A"[..], &b"AG"[..], &b"C"[..]]);
+        assert_eq!(joined.info(b"AC").integer().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(
+            joined.format(b"AD").integer().unwrap(),
+            vec![&[5, 3, 0][..], &[4, 0, 6][..]]
+        );
+        let missing = i32::missing();
+        assert_eq!(
+            joined.format(b"PL").integer().unwrap(),
+            vec![
+                &[0, 10, 20, 30, missing, 40][..],
+                &[1, 11, 21, 32, missing, 42][..],
+            ]
+        );
+        let genotypes = joined.genotypes().unwrap();
+        assert_eq!(
+            *genotypes.get(0),
+            vec![GenotypeAllele::Unphased(1), GenotypeAllele::Unphased(2)]
+        );
+        assert_eq!(
+            *genotypes.get(1),
+            vec![GenotypeAllele::Unphased(0), GenotypeAllele::Phased(2)]
+        );
+
+        // reference alleles of different length
+        split[0].set_alleles(&[b"A", b"G"]).unwrap();
+        split[1].set_alleles(&[b"AG", b"A"]).unwrap();
+        let joined = Record::join_multiallelic(&split).unwrap();
+        assert_eq!(joined.alleles(), vec![&b"AG"[..], &b"GG"[..], &b"A"[..]]);
+
+        split[1].set_alleles(&[b"T", b"A"]).unwrap();
+        match Record::join_multiallelic(&split) {
+            Err(JoinError::ReferenceMismatch) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        split[1].set_pos(10);
+        match Record::join_multiallelic(&split) {
+            Err(JoinError::DifferentPosition) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match Record::join_multiallelic(&[]) {
+            Err(JoinError::NoRecords) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_genotype_order() {
+        assert_eq!(
+            genotype_order(3, 2),
+            vec![
+                vec![0, 0],
+                vec![0, 1],
+                vec![1, 1],
+                vec![0, 2],
+                vec![1, 2],
+                vec![2, 2],
+            ]
+        );
+        assert_eq!(genotype_order(3, 1), vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(
+            genotype_order(2, 3),
+            vec![vec![0, 0, 0], vec![0, 0, 1], vec![0, 1, 1], vec![1, 1, 1]]
+        );
+        for (i, gt) in genotype_order(4, 4).iter().enumerate() {
+            assert_eq!(genotype_index(gt), i);
+        }
+        assert_eq!(genotype_index(&[2, 1]), 4);
+    }
+
+    #[test]
+    fn test_genotype_likelihoods() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##FORMAT=<ID=PL,Number=G,Type=Integer,Description=\"PL\">");
+            header.push_record(b"##FORMAT=<ID=GL,Number=G,Type=Float,Description=\"GL\">");
+        });
+        assert!(record.genotype_likelihoods().is_err());
+
+        record.push_format_integer(b"PL", &[0, 10, 20, 30, 5, 0]).unwrap();
+        let likelihoods = record.genotype_likelihoods().unwrap();
+        let first = likelihoods[0].as_ref().unwrap();
+        assert_eq!(first.ploidy(), 2);
+        assert_eq!(first.get(&[1, 0]), Some(10.0));
+        assert_eq!(first.most_likely_genotype(), Some(&[0, 0][..]));
+        assert_eq!(first.genotype_quality(), Some(10.0));
+        let second = likelihoods[1].as_ref().unwrap();
+        assert_eq!(second.most_likely_genotype(), Some(&[1, 1][..]));
+        assert_eq!(second.genotype_quality(), Some(5.0));
+        assert_eq!(
+            second.iter().collect::<Vec<_>>(),
+            vec![
+                (&[0, 0][..], Some(30.0)),
+                (&[0, 1][..], Some(5.0)),
+                (&[1, 1][..], Some(0.0)),
+            ]
+        );
+
+        record.clear_format(b"PL").unwrap();
+        record
+            .push_format_float(b"GL", &[-2.0, 0.0, f32::missing(), -1.0, -3.0, 0.0])
+            .unwrap();
+        let likelihoods = record.genotype_likelihoods().unwrap();
+        let first = likelihoods[0].as_ref().unwrap();
+        assert_eq!(first.most_likely_genotype(), Some(&[0, 1][..]));
+        assert_eq!(first.genotype_quality(), Some(20.0));
+        assert_eq!(first.get(&[1, 1]), None);
+        let second = likelihoods[1].as_ref().unwrap();
+        assert_eq!(second.most_likely_genotype(), Some(&[1, 1][..]));
+        assert_eq!(second.genotype_quality(), Some(10.0));
+    }
+
+    #[test]
+    fn test_dosages() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##FORMAT=<ID=DS,Number=A,Type=Float,Description=\"DS\">");
+            header.push_record(b"##FORMAT=<ID=GP,Number=G,Type=Float,Description=\"GP\">");
+        });
+
+        // genotypes ./1 and 1/1
+        assert_eq!(record.dosages().unwrap(), vec![None, Some(2.0)]);
+        record.push_format_float_vectors(b"GP", &[&[0.25, 0.5, 0.25], &[]]).unwrap();
+        assert_eq!(record.dosages().unwrap(), vec![Some(1.0), Some(2.0)]);
+        record.push_format_float_vectors(b"DS", &[&[0.5], &[]]).unwrap();
+        assert_eq!(record.dosages().unwrap(), vec![Some(0.5), Some(2.0)]);
+
+        for tag in &[&b"GT"[..], b"GP", b"DS"] {
+            record.clear_format(tag).unwrap();
+        }
+        assert!(record.dosages().is_err());
+    }
+
+    #[test]
+    fn test_record_comparison() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let records = reader
+            .records()
+            .map(|r| r.ok().expect("Error reading record."))
+            .collect::<Vec<_>>();
+        let mut other_reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut other = other_reader.records().next().unwrap().unwrap();
+
+        assert!(records[0] == other);
+        assert!(records[1] != other);
+        assert!(records[0].same_site(&other));
+        assert!(!records[1].same_site(&other));
+        assert!(records[0].same_variant(&other));
+
+        // A>AG is the same insertion as AT>AGT
+        other.set_alleles(&[b"AT", b"AGT"]).unwrap();
+        assert!(records[0] != other);
+        assert!(records[0].same_variant(&other));
+        other.set_alleles(&[b"A", b"AC"]).unwrap();
+        assert!(records[0].same_site(&other));
+        assert!(!records[0].same_variant(&other));
+
+        let mut multiallelic = other.clone();
+        multiallelic.set_alleles(&[b"A", b"AC", b"AG"]).unwrap();
+        other.set_alleles(&[b"A", b"AG", b"AC"]).unwrap();
+        assert!(multiallelic.same_variant(&other));
+        assert!(!multiallelic.same_variant(&records[0]));
+
+        other.set_rid(&None);
+        assert!(!records[0].same_site(&other));
+    }
+
+    #[test]
+    fn test_record_packed_size() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let record = reader.records().next().unwrap().unwrap();
+        let (shared, indiv) = record.packed_size();
+        assert!(shared > 0);
+        assert!(indiv > 0);
+
+        let empty = reader.empty_record();
+        assert_eq!(empty.packed_size(), (0, 0));
+    }
+
+    #[test]
+    fn test_has_info_flag() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##INFO=<ID=X1,Number=0,Type=Flag,Description=\"INFO FLAG\">");
+        });
+
+        assert_eq!(record.has_info_flag(b"X1").unwrap(), false);
+        record.push_info_flag(b"X1").unwrap();
+        assert_eq!(record.has_info_flag(b"X1").unwrap(), true);
+        record.clear_info_flag(b"X1").unwrap();
+        assert_eq!(record.has_info_flag(b"X1").unwrap(), false);
+        assert!(record.has_info_flag(b"N1").is_err());
+        assert!(record.has_info_flag(b"X2").is_err());
+    }
+
+    #[test]
+    fn test_missing_values() {
+        assert!(i32::missing().is_missing());
+        assert!(!0.is_missing());
+        assert_eq!(i32::missing(), MISSING_INTEGER);
+        assert_eq!(i32::vector_end(), VECTOR_END_INTEGER);
+        assert!(f32::missing().is_missing());
+        assert!(!f32::missing().is_vector_end());
+        assert!(!f32::NAN.is_missing());
+        assert_eq!(f32::missing().to_bits(), MISSING_FLOAT_BITS);
+        assert_eq!(f32::vector_end().to_bits(), VECTOR_END_FLOAT_BITS);
+
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        record.push_info_missing(b"N1").unwrap();
+        record.push_info_missing(b"F1").unwrap();
+        record.push_info_missing(b"S1").unwrap();
+        assert!(record.info(b"N1").integer().unwrap().unwrap()[0].is_missing());
+        assert!(record.info(b"F1").float().unwrap().unwrap()[0].is_missing());
+        assert_eq!(record.info(b"S1").string().unwrap().unwrap(), vec![b"."]);
+        match record.push_info_missing(b"X") {
+            Err(TagWriteError::UndefinedTag) => (),
+            _ => panic!("expected UndefinedTag error"),
+        }
+
+        record.push_format_missing(b"FN1").unwrap();
+        record.push_format_missing(b"FS1").unwrap();
+        record.push_format_missing(b"GT").unwrap();
+        for values in record.format(b"FN1").integer().unwrap() {
+            assert_eq!(values.len(), 1);
+            assert!(values[0].is_missing());
+        }
+        assert_eq!(record.format(b"FS1").string().unwrap(), vec![&b"."[..], &b"."[..]]);
+        assert_eq!(
+            record.to_vcf_string().unwrap(),
+            "19\t3111939\trs1234\tA\tAG\t.\tPASS\tS1=.;N1=.;F1=.\tGT:FS1:FN1\t.:.:.\t.:.:."
+        );
+    }
+
+    #[test]
+    fn test_missing_values_empty_record() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        let mut header = Header::new();
+        header
+            .push_sample(b"s1")
+            .push_sample(b"s2")
+            .add_contig(b"chr1", None)
+            .unwrap()
+            .add_format(b"GT", TagLength::Fixed(1), TagType::String, "Genotype")
+            .unwrap()
+            .add_format(b"DP", TagLength::Fixed(1), TagType::Integer, "Read depth")
+            .unwrap()
+            .add_format(b"VAF", TagLength::AltAlleles, TagType::Float, "Allele fractions")
+            .unwrap();
+        let writer = Writer::from_path(&path, &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let mut record = writer.empty_record();
+        record.set_rid(&Some(0));
+        record.set_alleles(&[&b"A"[..], &b"C"[..]]).unwrap();
+        record.push_format_missing(b"GT").unwrap();
+        record.push_format_missing(b"DP").unwrap();
+        record.push_format_missing(b"VAF").unwrap();
+        assert_eq!(
+            record.to_vcf_string().unwrap(),
+            "chr1\t1\t.\tA\tC\t0\t.\t.\tGT:DP:VAF\t.:.:.\t.:.:."
+        );
+        match record.push_format_missing(b"AD") {
+            Err(TagWriteError::UndefinedTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_record_packed() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let record = reader.records().next().unwrap().unwrap();
+        let header = reader.header();
+        let id = |name: &[u8]| *header.name_to_id(name).unwrap() as i32;
+
+        let shared = record.packed_shared();
+        assert_eq!(shared.len(), record.packed_size().0);
+        let (value, rest) = packed::read_typed_value(shared).unwrap();
+        assert_eq!(value.string().unwrap(), b"rs1234");
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(value.string().unwrap(), b"A");
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(value.string().unwrap(), b"AG");
+        assert!(value.integers().is_err());
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(value.integers().unwrap(), vec![id(b"PASS")]);
+        let (key, rest) = packed::read_typed_value(rest).unwrap();
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(key.integers().unwrap(), vec![id(b"S1")]);
+        assert_eq!(value.string().unwrap(), b"string1");
+        let (key, rest) = packed::read_typed_value(rest).unwrap();
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(key.integers().unwrap(), vec![id(b"N1")]);
+        assert_eq!(value.value_type(), packed::ValueType::Int8);
+        assert_eq!(value.integers().unwrap(), vec![1]);
+        let (key, rest) = packed::read_typed_value(rest).unwrap();
+        let (value, rest) = packed::read_typed_value(rest).unwrap();
+        assert_eq!(key.integers().unwrap(), vec![id(b"F1")]);
+        assert_eq!(value.floats().unwrap(), vec![1.0]);
+        assert!(rest.is_empty());
+
+        let indiv = record.packed_indiv();
+        let (key, rest) = packed::read_typed_value(indiv).unwrap();
+        assert_eq!(key.integers().unwrap(), vec![id(b"GT")]);
+        let (value_type, count, rest) = packed::read_type_descriptor(rest).unwrap();
+        assert_eq!(count, 2);
+        let (value, _) = packed::read_values(rest, value_type, count * 2).unwrap();
+        // "./1" and "1/1"
+        assert_eq!(value.integers().unwrap(), vec![0, 4, 4, 4]);
+        assert!(packed::read_values(rest, value_type, 100).is_err());
+        assert!(packed::read_typed_value(&[0x16]).is_err());
+        assert!(packed::read_typed_value(&[]).is_err());
+    }
+
+    #[test]
+    fn test_allele_depths() {
+        let mut record = translated_record(|header| {
+            header.push_record(b"##FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"Depths\">");
+            header.push_record(b"##FORMAT=<ID=DP,Number=1,Type=Integer,Description=\"Depth\">");
+        });
+
+        assert!(record.allele_depths().is_err());
+        assert!(record.depths().is_err());
+        record
+            .push_format_integer_vectors(b"AD", &[&[3, 5], &[i32::missing()]])
+            .unwrap();
+        record.push_format_integer(b"DP", &[8, i32::missing()]).unwrap();
+        assert_eq!(
+            record.allele_depths().unwrap(),
+            vec![vec![Some(3), Some(5)], vec![None, None]]
+        );
+        assert_eq!(record.depths().unwrap(), vec![Some(8), None]);
+        let samples = record.samples().collect::<Vec<_>>();
+        assert_eq!(samples[0].allele_depths().unwrap(), vec![Some(3), Some(5)]);
+        assert_eq!(samples[1].depth().unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_ref() {
+        let reference = faidx::Reader::from_path("test/test_gc.fa")
+            .ok()
+            .expect("Error opening file.");
+        let mut header = Header::new();
+        header.add_contigs_from_fai(&reference).unwrap();
+        header.push_record(b"##contig=<ID=CHROMOSOME_X,length=100>");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let mut record = writer.empty_record();
+        record.set_rid(&None);
+        record.set_alleles(&[b"G", b"T"]).unwrap();
+        match record.check_ref(&reference) {
+            Err(CheckRefError::MissingContig) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        record.set_rid(&Some(0));
+        record.check_ref(&reference).unwrap();
+        record.set_pos(100);
+        record.set_alleles(&[b"atAT", b"A"]).unwrap();
+        record.check_ref(&reference).unwrap();
+        record.set_pos(0);
+        record.set_alleles(&[b"GA", b"G"]).unwrap();
+        match record.check_ref(&reference) {
+            Err(CheckRefError::Mismatch {
+                pos,
+                expected,
+                found,
+            }) => {
+                assert_eq!(pos, 0);
+                assert_eq!(expected, b"GC");
+                assert_eq!(found, b"GA");
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        // the reference allele extends beyond the end of the contig
+        record.set_pos(199);
+        record.set_alleles(&[b"TA", b"T"]).unwrap();
+        assert!(record.check_ref(&reference).is_err());
+
+        record.set_rid(&Some(1));
+        match record.check_ref(&reference) {
+            Err(CheckRefError::UnknownContig(name)) => assert_eq!(name, "CHROMOSOME_X"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_fill_tags() {
+        let mut record = translated_record(|_| ());
+        match record.fill_tags(&FillTag::all()) {
+            Err(FillTagsError::UndefinedTag(name)) => assert_eq!(name, "AC"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let mut record = translated_record(|header| {
+            header.add_fill_tags(&FillTag::all()).unwrap();
+        });
+        assert_eq!(
+            record.header().info_type(b"AF").unwrap(),
+            (TagType::Float, TagLength::AltAlleles)
+        );
+
+        // "./1" and "1/1"
+        record.fill_tags(&FillTag::all()).unwrap();
+        assert_eq!(record.info(b"AC").integer().unwrap(), Some(&[3][..]));
+        assert_eq!(record.info(b"AN").integer().unwrap(), Some(&[3][..]));
+        assert_eq!(record.info(b"AF").float().unwrap(), Some(&[1.0][..]));
+        assert_eq!(record.info(b"MAF").float().unwrap(), Some(&[0.0][..]));
+        assert_eq!(record.info(b"NS").integer().unwrap(), Some(&[2][..]));
+
+        record.set_alleles(&[b"A", b"AG", b"AT"]).unwrap();
+        record
+            .push_genotypes(&[
+                GenotypeAllele::Unphased(0),
+                GenotypeAllele::Unphased(2),
+                GenotypeAllele::UnphasedMissing,
+                GenotypeAllele::UnphasedMissing,
+            ])
+            .unwrap();
+        record
+            .fill_tags(&[FillTag::AlleleCount, FillTag::AlleleFrequency, FillTag::SampleCount])
+            .unwrap();
+        assert_eq!(record.info(b"AC").integer().unwrap(), Some(&[0, 1][..]));
+        assert_eq!(record.info(b"AF").float().unwrap(), Some(&[0.0, 0.5][..]));
+        assert_eq!(record.info(b"NS").integer().unwrap(), Some(&[1][..]));
+        // not updated
+        assert_eq!(record.info(b"AN").integer().unwrap(), Some(&[3][..]));
+
+        record.push_genotypes(&[GenotypeAllele::UnphasedMissing; 4]).unwrap();
+        record.fill_tags(&FillTag::all()).unwrap();
+        assert_eq!(record.info(b"AN").integer().unwrap(), Some(&[0][..]));
+        assert!(record.info(b"MAF").float().unwrap().unwrap()[0].is_missing());
+    }
+
+    #[test]
+    fn test_record_set_header() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let headers = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let records = reader
+            .records()
+            .map(|record| record.unwrap())
+            .collect::<Vec<_>>();
+        let mut record = records[0].clone();
+
+        let mut header = Header::from_template(headers.header());
+        let mut writer = Writer::from_path(tmp.path().join("test1.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        match writer.write(&record) {
+            Err(WriteError::HeaderMismatch) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match record.set_header(Arc::new(writer.header().clone())) {
+            Err(CompatibilityError::ContigMismatch(ref name)) if name == "19" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        header = Header::from_template(reader.header());
+        header.push_record(b"##INFO=<ID=X1,Number=1,Type=Integer,Description=\"Added\">");
+        let mut writer = Writer::from_path(tmp.path().join("test2.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        // the header of the writer extends the header of the records
+        writer.write(&record).unwrap();
+        writer.write(&records[1]).unwrap();
+        assert!(record.push_info_integer(b"X1", &[1]).is_err());
+        record.set_header(Arc::new(writer.header().clone())).unwrap();
+        record.push_info_integer(b"X1", &[1]).unwrap();
+        writer.write(&record).unwrap();
+    }
+
+    #[test]
+    fn test_sv_info() {
+        let mut header = Header::new();
+        header.push_record(b"##contig=<ID=1,length=1000000>");
+        header.push_record(b"##contig=<ID=2,length=1000000>");
+        for line in &[
+            &b"##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"Type\">"[..],
+            b"##INFO=<ID=SVLEN,Number=.,Type=Integer,Description=\"Length\">",
+            b"##INFO=<ID=END,Number=1,Type=Integer,Description=\"End\">",
+            b"##INFO=<ID=CIPOS,Number=2,Type=Integer,Description=\"CI around POS\">",
+            b"##INFO=<ID=CIEND,Number=2,Type=Integer,Description=\"CI around END\">",
+            b"##INFO=<ID=MATEID,Number=.,Type=String,Description=\"Mates\">",
+            b"##INFO=<ID=IMPRECISE,Number=0,Type=Flag,Description=\"Imprecise\">",
+            b"##INFO=<ID=EVENT,Number=1,Type=Float,Description=\"Wrong type\">",
+        ] {
+            header.push_record(line);
+        }
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let parse = |line: &[u8]| Record::from_vcf_line(writer.header(), line).unwrap();
+
+        let mut record = parse(
+            b"1\t100\tsv1\tN\t<DEL>\t.\tPASS\t\
+              SVTYPE=DEL;SVLEN=-200;END=300;CIPOS=-10,10;IMPRECISE",
+        );
+        assert_eq!(
+            record.sv_info().unwrap(),
+            Some(SvInfo {
+                sv_type: SvType::Deletion,
+                sv_len: vec![Some(-200)],
+                end: Some(300),
+                cipos: Some((-10, 10)),
+                ciend: None,
+                mate_ids: vec![],
+                event: None,
+                imprecise: true,
+            })
+        );
+
+        let mut record =
+            parse(b"2\t321681\tbnd_W\tG\tG]17:198982]\t.\tPASS\tSVTYPE=BND;MATEID=bnd_Y");
+        let sv_info = record.sv_info().unwrap().unwrap();
+        assert_eq!(sv_info.sv_type, SvType::Breakend);
+        assert_eq!(sv_info.sv_len, vec![None]);
+        assert_eq!(sv_info.mate_ids, vec![b"bnd_Y".to_vec()]);
+        assert!(!sv_info.imprecise);
+
+        assert_eq!(parse(b"1\t100\t.\tA\tC\t.\tPASS\t.").sv_info().unwrap(), None);
+        match parse(b"1\t100\t.\tN\t<X>\t.\t.\tSVTYPE=XYZ").sv_info() {
+            Err(SvInfoError::InvalidSvType(ref value)) if value == "XYZ" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match parse(b"1\t100\t.\tN\t<DEL>\t.\t.\tSVTYPE=DEL;END=50").sv_info() {
+            Err(SvInfoError::InvalidValue(ref name)) if name == "END" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match parse(b"1\t100\t.\tN\t<DEL>\t.\t.\tSVTYPE=DEL;CIPOS=10,-10").sv_info() {
+            Err(SvInfoError::InvalidValue(ref name)) if name == "CIPOS" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match parse(b"1\t100\t.\tN\t<DEL>,<DUP>\t.\t.\tSVTYPE=DEL;SVLEN=-5").sv_info() {
+            Err(SvInfoError::InvalidValueCount(ref name)) if name == "SVLEN" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match parse(b"1\t100\t.\tN\t<DEL>\t.\t.\tSVTYPE=DEL;EVENT=1.5").sv_info() {
+            Err(SvInfoError::UnexpectedType(ref name)) if name == "EVENT" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_breakend() {
+        let paired = |sequence: &[u8], side, contig: &[u8], pos, mate_side| Breakend::Paired {
+            sequence: sequence.to_vec(),
+            side: side,
+            mate_contig: contig.to_vec(),
+            mate_assembled: false,
+            mate_pos: pos,
+            mate_side: mate_side,
+        };
+        let cases = vec![
+            (&b"G]17:198982]"[..], paired(b"G", Side::Left, b"17", 198982, Side::Left)),
+            (b"]13:123456]T", paired(b"T", Side::Right, b"13", 123456, Side::Left)),
+            (b"C[2:321682[", paired(b"C", Side::Left, b"2", 321682, Side::Right)),
+            (b"[17:198983[A", paired(b"A", Side::Right, b"17", 198983, Side::Right)),
+            (
+                b"[HLA-A*01:01:01:01:5[A",
+                paired(b"A", Side::Right, b"HLA-A*01:01:01:01", 5, Side::Right),
+            ),
+            (
+                b"G.",
+                Breakend::Single {
+                    sequence: b"G".to_vec(),
+                    side: Side::Left,
+                },
+            ),
+            (
+                b".TC",
+                Breakend::Single {
+                    sequence: b"TC".to_vec(),
+                    side: Side::Right,
+                },
+            ),
+        ];
+        for (allele, expected) in cases {
+            let breakend = Breakend::parse(allele).unwrap();
+            assert_eq!(breakend, expected);
+            assert_eq!(breakend.to_bytes(), allele);
+        }
+
+        let breakend = Breakend::parse(b"CAGTNNNNNCA[2:321682[").unwrap();
+        assert_eq!(breakend.side(), Side::Left);
+        assert_eq!(breakend.sequence(), b"CAGTNNNNNCA");
+        assert_eq!(breakend.inserted_sequence(), b"AGTNNNNNCA");
+        assert_eq!(Breakend::parse(b".TC").unwrap().inserted_sequence(), b"T");
+
+        let assembled = Breakend::parse(b"A]<ctg1>:7]").unwrap();
+        match assembled {
+            Breakend::Paired {
+                ref mate_contig,
+                mate_assembled,
+                ..
+            } => {
+                assert_eq!(mate_contig, b"ctg1");
+                assert!(mate_assembled);
+            }
+            ref r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(assembled.to_string(), "A]<ctg1>:7]");
+
+        for allele in &[
+            &b"A"[..],
+            b".",
+            b"<DEL>",
+            b"G]17:198982",
+            b"G[17:5]",
+            b"G]17:5]A",
+            b"]17]A",
+            b"[17:5[",
+            b"G]:5]",
+        ] {
+            match Breakend::parse(allele) {
+                Err(BreakendParseError::InvalidNotation(_)) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        match Breakend::parse(b"G]17:abc]") {
+            Err(BreakendParseError::InvalidPosition(ref allele)) if allele == "G]17:abc]" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_header_ids() {
+        let vcf = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = &vcf.header();
+        use bcf::header::Id;
+
+        assert_eq!(header.id_to_name(Id(4)), b"GT");
+        assert_eq!(header.name_to_id(b"GT").unwrap(), Id(4));
+        assert!(header.name_to_id(b"XX").is_err());
+    }
+
+    #[test]
+    fn test_header_samples() {
+        let vcf = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = &vcf.header();
+
+        assert_eq!(header.id_to_sample(Id(0)), b"one");
+        assert_eq!(header.id_to_sample(Id(1)), b"two");
+        assert_eq!(header.sample_to_id(b"one").unwrap(), Id(0));
+        assert_eq!(header.sample_to_id(b"two").unwrap(), Id(1));
+        assert!(header.sample_to_id(b"three").is_err());
+    }
+
+    #[test]
+    fn test_header_records() {
+        let vcf = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let records = vcf.header().header_records();
+        assert_eq!(records.len(), 9);
+
+        match &records[0] {
+            &HeaderRecord::Filter {
+                ref key,
+                ref values,
+            } => {
+                assert_eq!(key, "FILTER");
+                assert_eq!(values["ID"], "PASS");
+            }
+            _ => {
+                assert!(false);
+            }
+        }
+    }
+
+    #[test]
+    fn test_header_contigs() {
+        let vcf = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = vcf.header();
+
+        assert_eq!(header.contig_count(), 2);
+        assert_eq!(header.rid2len(1), Some(59128983));
+        assert_eq!(
+            header.contigs().collect::<Vec<_>>(),
+            vec![
+                (&b"contig1"[..], Some(59128983)),
+                (&b"contig2"[..], Some(59128983)),
+            ]
+        );
+
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let mut header = Header::new();
+        header.push_record(b"##contig=<ID=chrM>");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(
+            writer.header().contigs().collect::<Vec<_>>(),
+            vec![(&b"chrM"[..], None)]
+        );
+    }
+
+    #[test]
+    fn test_header_tag_defs() {
+        let vcf = Reader::from_path(&"test/test_various.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = vcf.header();
+
+        let info_defs = header.info_defs();
+        assert_eq!(
+            info_defs.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(),
+            vec!["S1", "N1", "F1", "X1"]
+        );
+        assert_eq!(
+            info_defs[1],
+            TagDefinition {
+                id: String::from("N1"),
+                length: TagLength::Fixed(1),
+                tag_type: TagType::Integer,
+                description: String::from("Single INFO integer"),
+            }
+        );
+        assert_eq!(info_defs[3].length, TagLength::Fixed(0));
+        assert_eq!(info_defs[3].tag_type, TagType::Flag);
+
+        let format_defs = header.format_defs();
+        assert_eq!(format_defs.len(), 5);
+        assert_eq!(format_defs[0].id, "GT");
+        assert_eq!(format_defs[3].tag_type, TagType::Float);
+        assert_eq!(format_defs[4].tag_type, TagType::String);
+        assert_eq!(header.info_type(b"F1").unwrap(), (TagType::Float, TagLength::Fixed(1)));
+    }
+
+    #[test]
+    fn test_header_add_definitions() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let mut header = Header::new();
+            header
+                .add_contig(b"chr1", Some(1000))
+                .unwrap()
+                .add_filter(b"q10", "Quality below 10")
+                .unwrap()
+                .add_info(b"DP", TagLength::Fixed(1), TagType::Integer, "Total depth")
+                .unwrap()
+                .add_info(b"DB", TagLength::Fixed(0), TagType::Flag, "In \"dbSNP\"")
+                .unwrap()
+                .add_format(b"AD", TagLength::Alleles, TagType::Integer, "Allelic depths")
+                .unwrap();
+            Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+        }
+
+        let vcf = Reader::from_path(&path).ok().expect("Error opening file.");
+        let header = vcf.header();
+        assert_eq!(header.contigs().collect::<Vec<_>>(), vec![(&b"chr1"[..], Some(1000))]);
+        assert!(header.name_to_id(b"q10").is_ok());
+        let info_defs = header.info_defs();
+        assert_eq!(info_defs.len(), 2);
+        assert_eq!(info_defs[0].length, TagLength::Fixed(1));
+        assert_eq!(info_defs[1].tag_type, TagType::Flag);
+        assert_eq!(info_defs[1].description, "In \"dbSNP\"");
+        assert_eq!(header.format_type(b"AD").unwrap(), (TagType::Integer, TagLength::Alleles));
+
+        let mut header = Header::new();
+        assert!(
+            header
+                .add_info(b"1DP", TagLength::Fixed(1), TagType::Integer, "Depth")
+                .is_err()
+        );
+        assert!(
+            header
+                .add_info(b"DP", TagLength::Fixed(0), TagType::Integer, "Depth")
+                .is_err()
+        );
+        assert!(
+            header
+                .add_format(b"FT", TagLength::Fixed(0), TagType::Flag, "Flag")
+                .is_err()
+        );
+        assert!(header.add_filter(b"q10", "Quality\nbelow 10").is_err());
+        assert!(header.add_contig(b"chr 1", None).is_err());
+    }
+
+    #[test]
+    fn test_header_merge() {
+        let various = Reader::from_path(&"test/test_various.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let headers = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let mut header = Header::from_template(various.header());
+        header.merge(headers.header()).unwrap();
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let header = writer.header();
+
+        assert_eq!(
+            header.info_defs().iter().map(|d| d.id.as_str()).collect::<Vec<_>>(),
+            vec!["S1", "N1", "F1", "X1", "INFO1", "INFO2"]
+        );
+        assert_eq!(header.format_defs().len(), 6);
+        assert_eq!(
+            header.contigs().map(|(name, _)| name).collect::<Vec<_>>(),
+            vec![&b"19"[..], &b"contig1"[..], &b"contig2"[..]]
+        );
+        assert!(header.name_to_id(b"FILTER2").is_ok());
+        assert_eq!(header.samples(), vec![&b"one"[..], &b"two"[..]]);
+    }
+
+    #[test]
+    fn test_translate_and_write() {
+        let mut strings = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let headers = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bcf");
+        {
+            // the definitions of `test_string.vcf` are appended, hence their IDs change
+            let mut header = Header::from_template(headers.header());
+            header.merge(strings.header()).unwrap();
+            let mut writer = Writer::from_path(&path, &header, true, false)
+                .ok()
+                .expect("Error opening file.");
+            assert_ne!(
+                writer.header().name2rid(b"19").unwrap(),
+                strings.header().name2rid(b"19").unwrap()
+            );
+            for record in strings.records() {
+                let mut record = record.unwrap();
+                writer.translate_and_write(&mut record).unwrap();
+            }
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let mut records: Vec<Record> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 6);
+        for (i, record) in records.iter_mut().enumerate() {
+            assert_eq!(record.rid().map(|rid| reader.header().rid2name(rid)), Some(&b"19"[..]));
+            assert_eq!(
+                record.info(b"S1").string().unwrap().unwrap()[0],
+                format!("string{}", i + 1).as_bytes()
+            );
+            assert_eq!(record.info(b"N1").integer().unwrap().unwrap()[0], i as i32 + 1);
+            assert_eq!(record.format(b"FN1").integer().unwrap()[1][0], 2);
+        }
+    }
+
+    #[test]
+    fn test_header_to_vcf_string() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header().to_vcf_string(), read_all("test/test_headers.vcf"));
+        assert_eq!(
+            reader.header().to_vcf_bytes(),
+            read_all("test/test_headers.vcf").into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_header_rename_samples() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        let mut header = Header::from_template(reader.header());
+        header.rename_sample(b"two", b"second").unwrap();
+        match header.rename_sample(b"three", b"third") {
+            Err(RenameError::UnknownSample(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match header.set_samples(&[b"a", b"a"]) {
+            Err(RenameError::DuplicateSample(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match header.set_samples(&[b"a"]) {
+            Err(RenameError::InvalidSampleCount) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        {
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            assert_eq!(writer.header().samples(), vec![&b"one"[..], &b"second"[..]]);
+            assert!(writer.header().name_to_id(b"FS1").is_ok());
+            for record in reader.records() {
+                let mut record = record.unwrap();
+                writer.translate_and_write(&mut record).unwrap();
+            }
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header().samples(), vec![&b"one"[..], &b"second"[..]]);
+        let mut record = reader.records().next().unwrap().unwrap();
+        let fs1 = record.format(b"FS1").string().unwrap();
+        assert_eq!(fs1[1], b"ss1");
+    }
+
+    #[test]
+    fn test_header_select_samples() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        let mut header = Header::from_template(reader.header());
+        match header.select_samples(&[0, 2]) {
+            Err(SubsetError::InvalidSampleIndex(2)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match header.select_samples(&[0, 0]) {
+            Err(SubsetError::DuplicateSampleName) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(header.select_samples(&[1, 0]).unwrap(), vec![1, 0]);
+        {
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            assert_eq!(writer.header().samples(), vec![&b"two"[..], &b"one"[..]]);
+            for record in reader.records() {
+                let mut record = record.unwrap();
+                writer.translate(&mut record);
+                writer.subset(&mut record);
+                writer.write(&record).unwrap();
+            }
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header().samples(), vec![&b"two"[..], &b"one"[..]]);
+        let mut record = reader.records().next().unwrap().unwrap();
+        let fs1 = record.format(b"FS1").string().unwrap();
+        assert_eq!(fs1[0], b"ss1");
+        assert_eq!(fs1[1], b"LongString1");
+    }
+
+    #[test]
+    fn test_write_strict() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let headers = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let mut record = reader.records().next().unwrap().unwrap();
+
+        let header = Header::from_template(headers.header());
+        let mut writer = Writer::from_path(tmp.path().join("test1.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        writer.set_strict(true);
+        match writer.write(&record) {
+            Err(WriteError::UndefinedContig(ref name)) if name == "19" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        record.set_rid(&None);
+        match writer.write(&record) {
+            Err(WriteError::UndefinedInfo(ref name)) if name == "S1" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let mut record = reader.records().next().unwrap().unwrap();
+        let mut header = Header::from_template(headers.header());
+        header.merge(reader.header()).unwrap();
+        let mut writer = Writer::from_path(tmp.path().join("test2.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        writer.set_strict(true);
+        assert!(writer.write(&record).is_err());
+        writer.translate_and_write(&mut record).unwrap();
+
+        let mut record = writer.empty_record();
+        record.set_rid(&Some(5));
+        match writer.write(&record) {
+            Err(WriteError::UndefinedContig(ref name)) if name == "#5" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_records_across_threads() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let handles: Vec<_> = reader
+            .records()
+            .map(|record| {
+                let mut record = record.unwrap();
+                thread::spawn(move || {
+                    let rid = record.rid().unwrap();
+                    assert_eq!(record.header().rid2name(rid), b"19");
+                    record.info(b"N1").integer().unwrap().unwrap()[0]
+                })
+            })
+            .collect();
+        let values: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(values, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_read_undefined_tags() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        File::create(&path)
+            .unwrap()
+            .write_all(
+                b"##fileformat=VCFv4.2\n\
+                  ##contig=<ID=1>\n\
+                  #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+                  1\t10\t.\tA\tC\t.\t.\t.\n\
+                  1\t20\t.\tA\tC\t.\t.\tX1=5\n",
+            )
+            .unwrap();
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let mut first = reader.empty_record();
+        reader.read(&mut first).unwrap();
+        let header = first.header_arc();
+        assert!(header.name_to_id(b"X1").is_err());
+
+        // the definition added for the second record does not change the header of the first
+        let mut second = reader.empty_record();
+        reader.read(&mut second).unwrap();
+        assert!(header.name_to_id(b"X1").is_err());
+        assert!(first.header().name_to_id(b"X1").is_err());
+        assert!(second.header().name_to_id(b"X1").is_ok());
+        assert!(reader.header().name_to_id(b"X1").is_ok());
+        // htslib defines undefined tags as strings
+        let handle =
+            thread::spawn(move || second.info(b"X1").string().unwrap().unwrap()[0].to_vec());
+        assert_eq!(handle.join().unwrap(), b"5");
+    }
+
+    #[test]
+    fn test_header_from_text() {
+        let text = read_all("test/test_headers.vcf");
+        let header = Header::from_text(text.as_bytes()).unwrap();
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(writer.header().to_vcf_string(), text);
+        assert_eq!(writer.header().samples(), vec![&b"one"[..], &b"two"[..]]);
+
+        // missing `#CHROM` line
+        let text: Vec<&str> = text.lines().filter(|l| l.starts_with("##")).collect();
+        let text = text.join("\n");
+        assert!(Header::from_text(text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_header_push_structured() {
+        let mut header = Header::new();
+        header
+            .push_structured("ALT", &[("ID", "DEL"), ("Description", "Deletion, \"large\"")])
+            .unwrap()
+            .push_structured("SAMPLE", &[("ID", "s1"), ("Genomes", "Germline,Tumor")])
+            .unwrap()
+            .push_generic("source", "rust-htslib test")
+            .unwrap();
+        assert!(header.push_structured("ALT", &[("I D", "DUP")]).is_err());
+        assert!(header.push_structured("A<LT", &[("ID", "DUP")]).is_err());
+        assert!(header.push_structured("ALT", &[("ID", "DUP\n")]).is_err());
+        assert!(header.push_generic("source", "a\nb").is_err());
+        assert!(header.push_generic("source", "<ID=x>").is_err());
+
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let text = writer.header().to_vcf_string();
+        assert!(text.contains("##ALT=<ID=DEL,Description=\"Deletion, \\\"large\\\"\">\n"));
+        assert!(text.contains("##SAMPLE=<ID=s1,Genomes=\"Germline,Tumor\">\n"));
+        assert!(text.contains("##source=rust-htslib test\n"));
+    }
+
+    #[test]
+    fn test_header_get_generic() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let header = reader.header();
+        assert_eq!(header.get_generic("fileformat"), Some("VCFv4.1"));
+        assert_eq!(header.get_generic("Bar2"), Some("something else"));
+        assert_eq!(header.get_generic("SOME"), None);
+        assert_eq!(header.get_generic("Baz"), None);
+
+        let mut header = Header::from_template(reader.header());
+        header.push_generic("Bar2", "another thing").unwrap();
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(
+            writer.header().get_all_generic("Bar2"),
+            vec!["something else", "another thing"]
+        );
+    }
+
+    #[test]
+    fn test_header_add_contigs_from_fai() {
+        let reference = faidx::Reader::from_path("test/test_gc.fa")
+            .ok()
+            .expect("Error opening file.");
+        let mut header = Header::new();
+        header.add_contigs_from_fai(&reference).unwrap();
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let writer = Writer::from_path(tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(
+            writer.header().contigs().collect::<Vec<_>>(),
+            vec![(&b"CHROMOSOME_I"[..], Some(200))]
+        );
+    }
+
+    #[test]
+    fn test_header_version() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header().version(), "VCFv4.1");
+
+        let mut header = Header::from_template(reader.header());
+        header.set_version("VCFv4.3").unwrap();
+        assert!(header.set_version("4.3").is_err());
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        Writer::from_path(&path, &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert!(read_all(&path).starts_with("##fileformat=VCFv4.3\n"));
+        let reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header().version(), "VCFv4.3");
+    }
+
+    #[test]
+    fn test_header_ids_iter() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let filter = IdKind {
+            filter: true,
+            info: false,
+            format: false,
+        };
+        let ids: Vec<(Id, &[u8], IdKind)> = reader.header().ids().collect();
+        assert_eq!(
+            ids.iter().map(|&(_, name, _)| name).collect::<Vec<_>>(),
+            vec![
+                &b"PASS"[..],
+                &b"FILTER1"[..],
+                &b"FILTER2"[..],
+                &b"INFO1"[..],
+                &b"INFO2"[..],
+                &b"FORMAT1"[..],
+            ]
+        );
+        assert_eq!(ids[0], (Id(0), &b"PASS"[..], filter));
+        assert!(ids[3].2.info && !ids[3].2.format);
+        assert!(ids[5].2.format && !ids[5].2.filter);
+        for &(id, name, _) in &ids {
+            assert_eq!(reader.header().name_to_id(name).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_header_remove_checked() {
+        let reader = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut header = Header::from_template(reader.header());
+        header
+            .remove_filter_checked(b"FILTER2")
+            .unwrap()
+            .remove_info_checked(b"INFO2")
+            .unwrap()
+            .remove_format_checked(b"FORMAT1")
+            .unwrap()
+            .remove_contig_checked(b"contig2")
+            .unwrap()
+            .remove_structured_checked(b"Foo2")
+            .unwrap()
+            .remove_generic_checked(b"Bar2")
+            .unwrap();
+        match header.remove_info_checked(b"INFO2") {
+            Err(RemoveError::UnknownTag(ref tag)) if tag == "INFO2" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(header.remove_info_checked(b"FILTER1").is_err());
+        assert!(header.remove_filter_checked(b"INFO1").is_err());
+        assert!(header.remove_generic_checked(b"Bar3").is_err());
+        assert!(header.remove_structured_checked(b"Foo2").is_err());
+    }
+
+    #[test]
+    fn test_writer_set_header() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let mut header = Header::from_template(reader.header());
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            // has no effect on the writer
+            header.push_generic("source", "ignored").unwrap();
+
+            let mut header = Header::from_template(writer.header());
+            header
+                .add_info(b"X1", TagLength::Fixed(1), TagType::Integer, "Added later")
+                .unwrap();
+            writer.set_header(&header).unwrap();
+            assert!(writer.header().info_type(b"X1").is_ok());
+
+            for record in reader.records() {
+                let mut record = record.unwrap();
+                writer.translate(&mut record);
+                record.push_info_integer(b"X1", &[42]).unwrap();
+                writer.write(&record).unwrap();
+            }
+            match writer.set_header(&header) {
+                Err(WriteError::HeaderWritten) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        assert!(!read_all(&path).contains("##source=ignored"));
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let mut count = 0;
+        for record in reader.records() {
+            let mut record = record.unwrap();
+            assert_eq!(record.info(b"X1").integer().unwrap().unwrap()[0], 42);
+            count += 1;
+        }
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    fn test_header_compatible() {
+        let strings = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let headers = Reader::from_path(&"test/test_headers.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        assert!(strings.header().compatible_with(strings.header()));
+        assert!(strings.header() == strings.header());
+        match strings.header().check_compatible(headers.header()) {
+            Err(CompatibilityError::ContigMismatch(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        // an additional INFO field is compatible, but not equal
+        let mut header = Header::from_template(strings.header());
+        header.add_info(b"X1", TagLength::Fixed(1), TagType::Integer, "Extra").unwrap();
+        let writer = Writer::from_path(tmp.path().join("test1.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        assert!(writer.header().compatible_with(strings.header()));
+        assert!(writer.header() != strings.header());
+
+        let mut header = Header::from_template(strings.header());
+        header
+            .remove_info(b"N1")
+            .add_info(b"N1", TagLength::Fixed(1), TagType::Float, "Changed type")
+            .unwrap();
+        let writer = Writer::from_path(tmp.path().join("test2.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        match writer.header().check_compatible(strings.header()) {
+            Err(CompatibilityError::InfoMismatch(ref id)) if id == "N1" => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let header = Header::from_template_subset(strings.header(), &[b"one"]).unwrap();
+        let writer = Writer::from_path(tmp.path().join("test3.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        match writer.header().check_compatible(strings.header()) {
+            Err(CompatibilityError::SampleMismatch) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_header_sample_pedigree_defs() {
+        let mut header = Header::new();
+        header
+            .push_sample(b"mother")
+            .push_sample(b"child")
+            .push_structured(
+                "SAMPLE",
+                &[
+                    ("ID", "child"),
+                    ("Genomes", "Germline"),
+                    ("Description", "The child, \"proband\" (\\)"),
+                ],
+            )
+            .unwrap()
+            .push_structured("SAMPLE", &[("ID", "mother")])
+            .unwrap()
+            .push_structured("PEDIGREE", &[("Child", "child"), ("Mother", "mother")])
+            .unwrap();
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        Writer::from_path(&path, &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        let reader = Reader::from_path(&path).ok().expect("Error opening file.");
+
+        let samples = reader.header().sample_defs();
+        assert_eq!(samples.len(), 2);
+        let child = &samples["child"];
+        assert_eq!(child.id, "child");
+        assert_eq!(child.description, Some(String::from("The child, \"proband\" (\\)")));
+        assert_eq!(child.values.get("Genomes").map(|v| v.as_str()), Some("Germline"));
+        assert_eq!(child.values.len(), 1);
+        assert_eq!(samples["mother"].description, None);
+
+        let pedigrees = reader.header().pedigree_defs();
+        assert_eq!(pedigrees.len(), 1);
+        assert_eq!(pedigrees[0].child(), Some("child"));
+        assert_eq!(pedigrees[0].mother(), Some("mother"));
+        assert_eq!(pedigrees[0].father(), None);
+    }
+
+    #[test]
+    fn test_clear_info() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let header = Header::from_template(reader.header());
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            for record in reader.records() {
+                let mut record = record.unwrap();
+                writer.translate(&mut record);
+                record.clear_info_integer(b"N1").unwrap();
+                record.clear_info_float(b"F1").unwrap();
+                assert_eq!(record.info(b"N1").integer().unwrap(), None);
+                assert_eq!(record.info(b"F1").float().unwrap(), None);
+                assert!(record.info(b"S1").string().unwrap().is_some());
+                writer.write(&record).unwrap();
+            }
+        }
+
+        for line in read_all(&path).lines().filter(|l| !l.starts_with('#')) {
+            assert_eq!(line.split('\t').nth(7).unwrap().split(';').count(), 1);
+        }
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        for record in reader.records() {
+            let mut record = record.unwrap();
+            assert_eq!(record.info(b"N1").integer().unwrap(), None);
+            record.clear_info_string(b"S1").unwrap();
+            assert_eq!(record.info(b"S1").string().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_push_format_vectors() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let mut header = Header::new();
+            header
+                .push_sample(b"s1")
+                .push_sample(b"s2")
+                .push_sample(b"s3")
+                .add_contig(b"chr1", None)
+                .unwrap()
+                .add_format(b"AD", TagLength::Alleles, TagType::Integer, "Allelic depths")
+                .unwrap()
+                .add_format(b"AF", TagLength::AltAlleles, TagType::Float, "Allele fractions")
+                .unwrap();
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            let mut record = writer.empty_record();
+            record.set_rid(&Some(0));
+            record.set_alleles(&[&b"A"[..], &b"C"[..], &b"G"[..]]).unwrap();
+            record.push_format_integer_vectors(b"AD", &[&[10, 5, 1], &[3], &[]]).unwrap();
+            record
+                .push_format_float_vectors(b"AF", &[&[0.5, 0.25], &[f32::missing()], &[]])
+                .unwrap();
+            match record.push_format_integer_vectors(b"AD", &[&[1]]) {
+                Err(TagWriteError::InvalidSampleCount) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            writer.write(&record).unwrap();
+        }
+
+        let text = read_all(&path);
+        let line = text.lines().last().unwrap();
+        assert!(line.ends_with("\tAD:AF\t10,5,1:0.5,0.25\t3:.\t.:."));
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        let ad = record.format(b"AD").integer().unwrap();
+        assert_eq!(ad[0], &[10, 5, 1]);
+        assert_eq!(ad[1], &[3]);
+        assert!(ad[2][0].is_missing());
+    }
+
+    #[test]
+    fn test_clear_format() {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.vcf");
+        {
+            let header = Header::from_template(reader.header());
+            let mut writer = Writer::from_path(&path, &header, true, true)
+                .ok()
+                .expect("Error opening file.");
+            for record in reader.records() {
+                let mut record = record.unwrap();
+                writer.translate(&mut record);
+                record.clear_format(b"FS1").unwrap();
+                match record.format(b"FS1").string() {
+                    Err(FormatReadError::MissingTag) => (),
+                    r => panic!("unexpected result: {:?}", r),
+                }
+                writer.write(&record).unwrap();
+            }
+        }
+
+        for line in read_all(&path).lines().filter(|l| !l.starts_with('#')) {
+            assert_eq!(line.split('\t').nth(8), Some("GT:FN1"));
+        }
+    }
+
+    // Helper function reading full file into string.
+    fn read_all<P: AsRef<Path>>(path: P) -> String {
         let mut file = File::open(path.as_ref())
             .expect(&format!("Unable to open the file: {:?}", path.as_ref()));
         let mut contents = String::new();
@@ -874,6 +3340,25 @@ mod tests {
         contents
     }
 
+    // Helper function returning the first record of `test/test_string.vcf`, translated to the
+    // header of the file modified by `edit_header`, e.g., to add tag definitions.
+    fn translated_record<F: FnOnce(&mut Header)>(edit_header: F) -> Record {
+        let mut reader = Reader::from_path(&"test/test_string.vcf")
+            .ok()
+            .expect("Error opening file.");
+        let mut record = reader.records().next().unwrap().unwrap();
+        let mut header = Header::from_template(reader.header());
+        edit_header(&mut header);
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let mut writer = Writer::from_path(&tmp.path().join("test.vcf"), &header, true, true)
+            .ok()
+            .expect("Error opening file.");
+        writer.translate(&mut record);
+        record
+    }
+
     // Open `test_various.vcf`, add a record from scratch to it and write it out again.
     //
     // This exercises the full functionality of updating information in a `record::Record`.
@@ -0,0 +1,207 @@
+// Copyright 2014 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Low-level decoding of the packed representation of BCF records.
+//!
+//! Records keep the site and sample data in the packed form of the BCF format until they are
+//! unpacked (see `Record::packed_shared()` and `Record::packed_indiv()`).  The shared block
+//! contains the ID, the alleles, the FILTER entries and the INFO entries as typed values, each
+//! INFO entry being preceded by the header id of its key.  The individual block contains, for
+//! each FORMAT entry, the header id of its key, a type descriptor and the values of all
+//! samples.  The functions of this module allow to inspect these blocks without decoding the
+//! record, e.g., for skipping records on cheap criteria.  All values are little-endian.
+
+use std::i16;
+use std::i8;
+
+use ieee754::Ieee754;
+
+use bcf::record::{MISSING_INTEGER, VECTOR_END_INTEGER};
+use htslib;
+
+/// Type of packed BCF values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Null,
+    Int8,
+    Int16,
+    Int32,
+    Float,
+    Char,
+}
+
+impl ValueType {
+    fn from_code(code: u8) -> Result<Self, PackedError> {
+        match code as u32 {
+            htslib::BCF_BT_NULL => Ok(ValueType::Null),
+            htslib::BCF_BT_INT8 => Ok(ValueType::Int8),
+            htslib::BCF_BT_INT16 => Ok(ValueType::Int16),
+            htslib::BCF_BT_INT32 => Ok(ValueType::Int32),
+            htslib::BCF_BT_FLOAT => Ok(ValueType::Float),
+            htslib::BCF_BT_CHAR => Ok(ValueType::Char),
+            _ => Err(PackedError::InvalidType(code)),
+        }
+    }
+
+    /// Return the size of a single value in bytes.
+    pub fn size(&self) -> usize {
+        match *self {
+            ValueType::Null => 0,
+            ValueType::Int8 | ValueType::Char => 1,
+            ValueType::Int16 => 2,
+            ValueType::Int32 | ValueType::Float => 4,
+        }
+    }
+}
+
+/// A vector of packed BCF values of the same type.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TypedValue<'a> {
+    value_type: ValueType,
+    count: usize,
+    data: &'a [u8],
+}
+
+impl<'a> TypedValue<'a> {
+    /// Return the type of the values.
+    pub fn value_type(&self) -> ValueType {
+        self.value_type
+    }
+
+    /// Return the number of values.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Return true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Return the undecoded bytes of the values.
+    pub fn raw(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Decode integer values, mapping the missing and vector end values of the narrower types
+    /// to `bcf::record::MISSING_INTEGER` and `bcf::record::VECTOR_END_INTEGER`.
+    pub fn integers(&self) -> Result<Vec<i32>, PackedError> {
+        let values = match self.value_type {
+            ValueType::Null => Vec::new(),
+            ValueType::Int8 => self.data
+                .iter()
+                .map(|&b| match b as i8 {
+                    i8::MIN => MISSING_INTEGER,
+                    v if v == i8::MIN + 1 => VECTOR_END_INTEGER,
+                    v => v as i32,
+                })
+                .collect(),
+            ValueType::Int16 => self.data
+                .chunks(2)
+                .map(|b| match (b[0] as u16 | (b[1] as u16) << 8) as i16 {
+                    i16::MIN => MISSING_INTEGER,
+                    v if v == i16::MIN + 1 => VECTOR_END_INTEGER,
+                    v => v as i32,
+                })
+                .collect(),
+            ValueType::Int32 => self.data.chunks(4).map(|b| read_u32(b) as i32).collect(),
+            _ => return Err(PackedError::UnexpectedType),
+        };
+        Ok(values)
+    }
+
+    /// Decode float values.  Import `bcf::record::Numeric` for missing value handling.
+    pub fn floats(&self) -> Result<Vec<f32>, PackedError> {
+        match self.value_type {
+            ValueType::Null => Ok(Vec::new()),
+            ValueType::Float => Ok(self.data
+                .chunks(4)
+                .map(|b| Ieee754::from_bits(read_u32(b)))
+                .collect()),
+            _ => Err(PackedError::UnexpectedType),
+        }
+    }
+
+    /// Return the characters, without trailing padding.
+    pub fn string(&self) -> Result<&'a [u8], PackedError> {
+        match self.value_type {
+            ValueType::Null => Ok(&self.data[..0]),
+            ValueType::Char => {
+                let end = self.data.iter().position(|&c| c == 0).unwrap_or(self.data.len());
+                Ok(&self.data[..end])
+            }
+            _ => Err(PackedError::UnexpectedType),
+        }
+    }
+}
+
+fn read_u32(b: &[u8]) -> u32 {
+    b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24
+}
+
+/// Decode the type descriptor at the start of `data`, returning the value type, the number of
+/// values and the remaining bytes.
+pub fn read_type_descriptor(data: &[u8]) -> Result<(ValueType, usize, &[u8]), PackedError> {
+    if data.is_empty() {
+        return Err(PackedError::Truncated);
+    }
+    let value_type = try!(ValueType::from_code(data[0] & 0x0f));
+    let count = (data[0] >> 4) as usize;
+    if count < 15 {
+        return Ok((value_type, count, &data[1..]));
+    }
+    // the actual count follows as a typed integer
+    let (count, rest) = try!(read_typed_value(&data[1..]));
+    match count.integers().ok().and_then(|count| count.first().cloned()) {
+        Some(count) if count >= 0 => Ok((value_type, count as usize, rest)),
+        _ => Err(PackedError::InvalidCount),
+    }
+}
+
+/// Read `count` values of the given type from the start of `data`, returning them and the
+/// remaining bytes.  This is needed for FORMAT entries, where the type descriptor gives the
+/// number of values per sample.
+pub fn read_values(
+    data: &[u8],
+    value_type: ValueType,
+    count: usize,
+) -> Result<(TypedValue, &[u8]), PackedError> {
+    let size = value_type.size() * count;
+    if data.len() < size {
+        return Err(PackedError::Truncated);
+    }
+    let value = TypedValue {
+        value_type: value_type,
+        count: count,
+        data: &data[..size],
+    };
+    Ok((value, &data[size..]))
+}
+
+/// Read a typed value, i.e., a type descriptor followed by the values, from the start of
+/// `data`, returning it and the remaining bytes.
+pub fn read_typed_value(data: &[u8]) -> Result<(TypedValue, &[u8]), PackedError> {
+    let (value_type, count, rest) = try!(read_type_descriptor(data));
+    read_values(rest, value_type, count)
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum PackedError {
+        Truncated {
+            description("packed data ends within a value")
+        }
+        InvalidType(code: u8) {
+            description("invalid type in packed data")
+            display("invalid type {} in packed data", code)
+        }
+        InvalidCount {
+            description("invalid number of values in packed data")
+        }
+        UnexpectedType {
+            description("values are not of the requested type")
+        }
+    }
+}
@@ -6,41 +6,69 @@
 use std::f32;
 use std::ffi;
 use std::fmt;
+use std::i16;
 use std::i32;
+use std::i8;
 use std::ptr;
-use std::rc::Rc;
 use std::slice;
+use std::str;
+use std::sync::Arc;
 
 use ieee754::Ieee754;
 use itertools::Itertools;
 
-use bcf::header::{HeaderView, Id};
+use bcf::header::{CompatibilityError, HeaderView, Id, IdError, TagLength, TagType};
+use faidx;
 use htslib;
 
-const MISSING_INTEGER: i32 = i32::MIN;
-const VECTOR_END_INTEGER: i32 = i32::MIN + 1;
+/// Integer value marking a missing entry in a BCF record.
+pub const MISSING_INTEGER: i32 = i32::MIN;
+/// Integer value marking the end of a vector that is shorter than the others of a FORMAT field.
+pub const VECTOR_END_INTEGER: i32 = i32::MIN + 1;
+/// Bit pattern of the float value marking a missing entry in a BCF record (a signalling NaN).
+pub const MISSING_FLOAT_BITS: u32 = 0x7F800001;
+/// Bit pattern of the float value marking the end of a vector that is shorter than the others of
+/// a FORMAT field.
+pub const VECTOR_END_FLOAT_BITS: u32 = 0x7F800002;
 lazy_static! {
-    static ref MISSING_FLOAT: f32 = Ieee754::from_bits(0x7F800001);
-    static ref VECTOR_END_FLOAT: f32 = Ieee754::from_bits(0x7F800002);
+    static ref MISSING_FLOAT: f32 = Ieee754::from_bits(MISSING_FLOAT_BITS);
+    static ref VECTOR_END_FLOAT: f32 = Ieee754::from_bits(VECTOR_END_FLOAT_BITS);
 }
 
 /// Common methods for numeric INFO and FORMAT entries
+///
+/// Since missing float values are NaNs, they cannot be detected by comparison; use
+/// `is_missing()` instead.
 pub trait Numeric {
     /// Return true if entry is a missing value
     fn is_missing(&self) -> bool;
 
     /// Return missing value for storage in BCF record.
     fn missing() -> Self;
+
+    /// Return true if entry marks the end of a shorter vector.
+    fn is_vector_end(&self) -> bool;
+
+    /// Return the value marking the end of a shorter vector.
+    fn vector_end() -> Self;
 }
 
 impl Numeric for f32 {
     fn is_missing(&self) -> bool {
-        self.bits() == MISSING_FLOAT.bits()
+        self.bits() == MISSING_FLOAT_BITS
     }
 
     fn missing() -> f32 {
         *MISSING_FLOAT
     }
+
+    fn is_vector_end(&self) -> bool {
+        self.bits() == VECTOR_END_FLOAT_BITS
+    }
+
+    fn vector_end() -> f32 {
+        *VECTOR_END_FLOAT
+    }
 }
 
 impl Numeric for i32 {
@@ -51,22 +79,13 @@ impl Numeric for i32 {
     fn missing() -> i32 {
         MISSING_INTEGER
     }
-}
-
-trait NumericUtils {
-    /// Return true if entry marks the end of the record.
-    fn is_vector_end(&self) -> bool;
-}
 
-impl NumericUtils for f32 {
     fn is_vector_end(&self) -> bool {
-        self.bits() == VECTOR_END_FLOAT.bits()
+        *self == VECTOR_END_INTEGER
     }
-}
 
-impl NumericUtils for i32 {
-    fn is_vector_end(&self) -> bool {
-        *self == VECTOR_END_INTEGER
+    fn vector_end() -> i32 {
+        VECTOR_END_INTEGER
     }
 }
 
@@ -75,13 +94,13 @@ impl NumericUtils for i32 {
 #[derive(Debug)]
 pub struct Record {
     pub inner: *mut htslib::bcf1_t,
-    header: Rc<HeaderView>,
+    header: Arc<HeaderView>,
     buffer: *mut ::std::os::raw::c_void,
 }
 
 impl Record {
     /// Construct record with reference to header `HeaderView`, for create-internal use.
-    pub(crate) fn new(header: Rc<HeaderView>) -> Self {
+    pub(crate) fn new(header: Arc<HeaderView>) -> Self {
         let inner = unsafe {
             let inner = htslib::bcf_init();
             // Always unpack record.
@@ -95,13 +114,84 @@ impl Record {
         }
     }
 
+    /// Create a record from a line of a VCF file, e.g., as returned by `tbx::Reader`.
+    ///
+    /// Note that the record is associated with a copy of the given header.  When parsing many
+    /// lines, create a record with `empty_record()` of the reader or writer and use
+    /// `parse_vcf_line()` instead.
+    pub fn from_vcf_line(header: &HeaderView, line: &[u8]) -> Result<Self, VcfParseError> {
+        let mut record = Record::new(Arc::new(header.clone()));
+        try!(record.parse_vcf_line(line));
+        Ok(record)
+    }
+
+    /// Replace the contents of this record by parsing a line of a VCF file with the associated
+    /// header.  A trailing newline is ignored.
+    pub fn parse_vcf_line(&mut self, line: &[u8]) -> Result<(), VcfParseError> {
+        let line = match line.last() {
+            Some(&b'\n') => &line[..line.len() - 1],
+            _ => line,
+        };
+        if line.contains(&0) {
+            return Err(VcfParseError::Some);
+        }
+        // vcf_parse modifies the given string in place
+        let mut text = line.to_vec();
+        text.push(0);
+        let mut buf = htslib::kstring_t {
+            l: line.len(),
+            m: text.len(),
+            s: text.as_mut_ptr() as *mut i8,
+        };
+        unsafe {
+            if htslib::vcf_parse(&mut buf, self.header().inner, self.inner) != 0 {
+                return Err(VcfParseError::Some);
+            }
+            // Always unpack record.
+            htslib::bcf_unpack(self.inner, htslib::BCF_UN_ALL as i32);
+        }
+        Ok(())
+    }
+
+    /// Decode the given parts of the record, if not done yet.
+    ///
+    /// Accessors decode the parts they need themselves, so this is only needed when working
+    /// with `inner()` directly.  Records read by `bcf::Reader` are fully decoded unless
+    /// configured otherwise with `Reader::set_unpack()`, while records read by
+    /// `bcf::IndexedReader` are decoded on demand.
+    pub fn unpack(&mut self, level: Unpack) {
+        unsafe { htslib::bcf_unpack(self.inner, level.as_flags()) };
+    }
+
     /// Return associated header.
     pub fn header(&self) -> &HeaderView {
         self.header.as_ref()
     }
 
-    /// Set the record header.
-    pub(crate) fn set_header(&mut self, header: Rc<HeaderView>) {
+    /// Return the shared pointer to the associated header.
+    pub(crate) fn header_arc(&self) -> &Arc<HeaderView> {
+        &self.header
+    }
+
+    /// Associate the record with the given header, without translating it.
+    ///
+    /// The record refers to contigs and `FILTER`, `INFO` and `FORMAT` entries by their
+    /// numeric IDs, which are kept.  Hence, the new header has to assign the same IDs (see
+    /// `HeaderView::check_same_ids()`), e.g., because it has been created from the old header
+    /// by `Header::from_template()` and lines have been added.  Otherwise, the record has to
+    /// be translated with `bcf::Writer::translate()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an ID of the old header refers to a different name in the new one.
+    pub fn set_header(&mut self, header: Arc<HeaderView>) -> Result<(), CompatibilityError> {
+        try!(self.header.check_same_ids(&header));
+        self.header = header;
+        Ok(())
+    }
+
+    /// Set the record header without checking it, e.g., after translation.
+    pub(crate) fn set_header_unchecked(&mut self, header: Arc<HeaderView>) {
         self.header = header;
     }
 
@@ -140,7 +230,8 @@ impl Record {
         }
     }
 
-    // Update the internal reference ID number.
+    /// Update the internal reference ID number, e.g., as obtained by `HeaderView::name2rid()`
+    /// of the header of this record.  `None` sets the contig to missing.
     pub fn set_rid(&mut self, rid: &Option<u32>) {
         match rid {
             &Some(rid) => self.inner_mut().rid = rid as i32,
@@ -148,20 +239,43 @@ impl Record {
         }
     }
 
-    // Return 0-based position.
+    /// Return 0-based position.
     pub fn pos(&self) -> u32 {
         self.inner().pos as u32
     }
 
     /// Set 0-based position.
+    ///
+    /// Note that `INFO/END` is not updated, so it has to be shifted accordingly when moving
+    /// records with an end position (see `end()`), e.g., after liftover.
     pub fn set_pos(&mut self, pos: i32) {
         self.inner_mut().pos = pos;
     }
 
+    /// Return the 0-based, exclusive end position, i.e., the 1-based position of the last
+    /// reference base covered by the record.
+    ///
+    /// This is taken from `INFO/END` if present (e.g., for symbolic structural variant alleles)
+    /// and from the length of the reference allele otherwise.
+    pub fn end(&mut self) -> u32 {
+        let info_end = match self.info(b"END").integer() {
+            Ok(Some(end)) if !end.is_empty() && !end[0].is_missing() => Some(end[0] as u32),
+            _ => None,
+        };
+        info_end.unwrap_or(self.pos() + self.inner().rlen as u32)
+    }
+
+    /// Return the length of the reference region covered by the record, honoring `INFO/END`
+    /// (see `end()`).
+    pub fn rlen(&mut self) -> u32 {
+        self.end().saturating_sub(self.pos())
+    }
+
     /// Return the value of the ID column.
     ///
-    /// When empty, returns `b".".to_vec()`.
+    /// When empty, returns `b".".to_vec()`.  Decodes the record up to the alleles.
     pub fn id(&self) -> Vec<u8> {
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_STR as i32) };
         if self.inner().d.id.is_null() {
             b".".to_vec()
         } else {
@@ -170,6 +284,23 @@ impl Record {
         }
     }
 
+    /// Return the IDs of the ID column, which are separated by semicolons.
+    ///
+    /// Returns an empty vector when the ID column is empty (`.`).  Decodes the record up to
+    /// the alleles.
+    pub fn ids(&self) -> Vec<&[u8]> {
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_STR as i32) };
+        if self.inner().d.id.is_null() {
+            return Vec::new();
+        }
+        let id = unsafe { ffi::CStr::from_ptr(self.inner().d.id) }.to_bytes();
+        if id == b"." {
+            Vec::new()
+        } else {
+            id.split(|&c| c == b';').filter(|id| !id.is_empty()).collect()
+        }
+    }
+
     /// Update the ID string to the given value.
     pub fn set_id(&mut self, id: &[u8]) -> Result<(), IdWriteError> {
         if unsafe {
@@ -220,17 +351,21 @@ impl Record {
 
     /// Return `Filters` iterator for enumerating all filters that have been set.
     ///
-    /// A record having the `PASS` filter will return an empty `Filter` here.
+    /// A record having the `PASS` filter yields its ID (`0`), a record without filters (`.`)
+    /// none.  Decodes the record up to FILTER.
     pub fn filters(&self) -> Filters {
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_FLT as i32) };
         Filters::new(self)
     }
 
-    /// Query whether the filter with the given ID has been set.
+    /// Query whether the filter with the given ID has been set, the `PASS` ID (`0`) also matches
+    /// records without filters (`.`).  Decodes the record up to FILTER.
     ///
     /// # Arguments
     ///
     /// - `flt_id` - The filter ID to query for.
     pub fn has_filter(&self, flt_id: &Id) -> bool {
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_FLT as i32) };
         if **flt_id == 0 && self.inner().d.n_flt == 0 {
             return true;
         }
@@ -242,6 +377,36 @@ impl Record {
         false
     }
 
+    /// Query whether the filter with the given name has been set, `PASS` also matches records
+    /// without filters (`.`).
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The name of the filter, e.g., `b"q10"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the filter is not defined in the header.
+    pub fn has_filter_name(&self, name: &[u8]) -> Result<bool, IdError> {
+        let cname = ffi::CString::new(name).unwrap();
+        match unsafe {
+            htslib::bcf_has_filter(self.header().inner, self.inner, cname.as_ptr() as *mut i8)
+        } {
+            1 => Ok(true),
+            0 => Ok(false),
+            _ => Err(IdError::UnknownID(String::from_utf8_lossy(name).into_owned())),
+        }
+    }
+
+    /// Return `true` if the record passed all filters, i.e., FILTER is `PASS` or missing (`.`).
+    ///
+    /// Only the shared fields up to FILTER are unpacked for this check.
+    pub fn is_pass(&self) -> bool {
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_FLT as i32) };
+        let d = self.inner().d;
+        d.n_flt == 0 || (d.n_flt == 1 && unsafe { *d.flt } == 0)
+    }
+
     /// Set the given filters IDs to the FILTER column.
     ///
     /// Setting an empty slice removes all filters.
@@ -294,9 +459,9 @@ impl Record {
 
     /// Get alleles strings.
     ///
-    /// The first allele is the reference allele.
+    /// The first allele is the reference allele.  Decodes the record up to the alleles.
     pub fn alleles(&self) -> Vec<&[u8]> {
-        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_ALL as i32) };
+        unsafe { htslib::bcf_unpack(self.inner, htslib::BCF_UN_STR as i32) };
         let n = self.inner().n_allele() as usize;
         let dec = self.inner().d;
         let alleles = unsafe { slice::from_raw_parts(dec.allele, n) };
@@ -306,7 +471,22 @@ impl Record {
     }
 
     /// Set alleles.
+    ///
+    /// The first allele is the reference allele.  Note that INFO and FORMAT values depending
+    /// on the number of alleles are not updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no allele is given or an allele is empty or contains a comma, tab,
+    /// newline or NUL character.
     pub fn set_alleles(&mut self, alleles: &[&[u8]]) -> Result<(), AlleleWriteError> {
+        let valid = !alleles.is_empty()
+            && alleles.iter().all(|allele| {
+                !allele.is_empty() && !allele.iter().any(|c| b",\t\n\0".contains(c))
+            });
+        if !valid {
+            return Err(AlleleWriteError::InvalidAllele);
+        }
         let cstrings: Vec<ffi::CString> = alleles
             .iter()
             .map(|vec| ffi::CString::new(*vec).unwrap())
@@ -324,6 +504,8 @@ impl Record {
             )
         } == 0
         {
+            // invalidate the variant types cached by htslib
+            self.inner_mut().d.var_type = -1;
             Ok(())
         } else {
             Err(AlleleWriteError::Some)
@@ -335,12 +517,12 @@ impl Record {
         self.inner().qual
     }
 
-    /// Set variant quality.
+    /// Set variant quality, use `f32::missing()` (see `Numeric`) for a missing value.
     pub fn set_qual(&mut self, qual: f32) {
         self.inner_mut().qual = qual;
     }
 
-    /// Get the value of the given info tag.
+    /// Get the value of the given info tag.  Reading the value decodes all shared fields.
     pub fn info<'a>(&'a mut self, tag: &'a [u8]) -> Info {
         Info {
             record: self,
@@ -348,6 +530,29 @@ impl Record {
         }
     }
 
+    /// Check whether the given `Type=Flag` info tag is set.
+    ///
+    /// Fails with `InfoReadError::UndefinedTag` if the tag is not defined in the header and
+    /// with `InfoReadError::UnexpectedType` if it is defined with a type other than `Flag`.
+    pub fn has_info_flag(&mut self, tag: &[u8]) -> Result<bool, InfoReadError> {
+        self.info(tag).flag()
+    }
+
+    /// Check whether the given info tag is present in the record, regardless of its type.
+    ///
+    /// A tag removed with one of the `clear_info_*()` functions is only marked for removal
+    /// until the record is written, which the htslib accessors do not take into account.
+    fn has_info(&mut self, tag: &[u8]) -> bool {
+        unsafe {
+            let info = htslib::bcf_get_info(
+                self.header().inner,
+                self.inner,
+                ffi::CString::new(tag).unwrap().as_ptr() as *mut i8,
+            );
+            !info.is_null() && !(*info).vptr.is_null()
+        }
+    }
+
     /// Get the number of samples.
     pub fn sample_count(&self) -> u32 {
         self.inner().n_sample()
@@ -358,16 +563,426 @@ impl Record {
         self.inner().n_allele()
     }
 
-    // TODO fn push_genotypes(&mut self, Genotypes) {}?
+    /// Return the sizes in bytes of the packed shared (site) and individual (sample) parts of
+    /// the record, i.e., `(shared, indiv)`.
+    ///
+    /// This reflects the record as read or last written, without the memory used by the
+    /// decoded fields.  Modifications made since are only accounted for after writing the
+    /// record.
+    pub fn packed_size(&self) -> (usize, usize) {
+        (self.inner().shared.l, self.inner().indiv.l)
+    }
+
+    /// Return the packed shared (site) part of the record, starting at the ID column.
+    ///
+    /// Use the functions of `bcf::packed` to decode it.  Like `packed_size()`, this reflects
+    /// the record as read or last written.
+    pub fn packed_shared(&self) -> &[u8] {
+        kstring_bytes(&self.inner().shared)
+    }
+
+    /// Return the packed individual (sample) part of the record, see `packed_shared()`.
+    pub fn packed_indiv(&self) -> &[u8] {
+        kstring_bytes(&self.inner().indiv)
+    }
+
+    /// Classify each alternative allele with respect to the reference allele.
+    ///
+    /// The spanning deletion allele `*` is classified as `VariantType::Reference`.
+    pub fn variant_types(&mut self) -> Vec<VariantType> {
+        let n_allele = self.allele_count() as i32;
+        (1..n_allele)
+            .map(|i| {
+                let t = unsafe { htslib::bcf_get_variant_type(self.inner, i) } as u32;
+                let alleles = self.alleles();
+                let (reference, alt) = (alleles[0], alleles[i as usize]);
+                // htslib recognizes `*` and breakends only after single base references
+                match t {
+                    htslib::VCF_REF => VariantType::Reference,
+                    _ if alt == b"*" => VariantType::Reference,
+                    _ if alt.starts_with(b"<") => VariantType::Symbolic,
+                    htslib::VCF_BND => VariantType::Breakend,
+                    _ if alt.iter().any(|&b| b == b'[' || b == b']') => VariantType::Breakend,
+                    htslib::VCF_SNP => VariantType::Snv,
+                    htslib::VCF_MNP => VariantType::Mnv,
+                    htslib::VCF_INDEL if alt.len() > reference.len() => VariantType::Insertion,
+                    htslib::VCF_INDEL if alt.len() < reference.len() => VariantType::Deletion,
+                    _ => VariantType::Complex,
+                }
+            })
+            .collect()
+    }
+
+    /// Classify the record as a whole, returning `None` if there is no alternative allele.
+    ///
+    /// If the alternative alleles are of different types, `VariantType::Complex` is returned.
+    /// Alleles without variation (e.g., `<*>` in gVCF files) are ignored if there are others.
+    pub fn variant_type(&mut self) -> Option<VariantType> {
+        let types = self.variant_types();
+        let mut variants = types.iter().filter(|&&t| t != VariantType::Reference);
+        match variants.next() {
+            Some(&first) => if variants.all(|&t| t == first) {
+                Some(first)
+            } else {
+                Some(VariantType::Complex)
+            },
+            None => types.first().cloned(),
+        }
+    }
+
+    /// Get the structural variant fields of the INFO column (as defined by the VCF
+    /// specification), returning `None` if `SVTYPE` is not set.
+    ///
+    /// Fields not defined in the header are treated as not set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is set but defined with another type than in the VCF
+    /// specification, or has an invalid number of values or an invalid value, e.g., an unknown
+    /// `SVTYPE` or an `END` before the position of the record.
+    pub fn sv_info(&mut self) -> Result<Option<SvInfo>, SvInfoError> {
+        let sv_type = match try!(optional_info(self, b"SVTYPE", |mut info| info.string_owned())) {
+            Some(values) => try!(SvType::from_bytes(&values[0])),
+            None => return Ok(None),
+        };
+        let n_alt = self.allele_count().saturating_sub(1) as usize;
+
+        let sv_len = match try!(optional_info(self, b"SVLEN", |mut info| info.integer_owned())) {
+            Some(values) => {
+                if values.len() != n_alt {
+                    return Err(SvInfoError::InvalidValueCount("SVLEN".to_owned()));
+                }
+                values.iter().map(|&v| Some(v).filter(|v| !v.is_missing())).collect()
+            }
+            None => vec![None; n_alt],
+        };
+        let end = match try!(optional_info(self, b"END", |mut info| info.integer_owned())) {
+            Some(ref values) if !values[0].is_missing() => {
+                if values[0] < self.pos() as i32 + 1 {
+                    return Err(SvInfoError::InvalidValue("END".to_owned()));
+                }
+                Some(values[0] as u32)
+            }
+            _ => None,
+        };
+        let mut interval = |tag: &[u8]| {
+            let name = String::from_utf8_lossy(tag).into_owned();
+            match try!(optional_info(self, tag, |mut info| info.integer_owned())) {
+                Some(values) => {
+                    if values.len() != 2 {
+                        return Err(SvInfoError::InvalidValueCount(name));
+                    }
+                    if values.iter().any(|v| v.is_missing()) || values[0] > values[1] {
+                        return Err(SvInfoError::InvalidValue(name));
+                    }
+                    Ok(Some((values[0], values[1])))
+                }
+                None => Ok(None),
+            }
+        };
+        let cipos = try!(interval(b"CIPOS"));
+        let ciend = try!(interval(b"CIEND"));
+        let mate_ids = try!(optional_info(self, b"MATEID", |mut info| info.string_owned()))
+            .unwrap_or_else(Vec::new);
+        let event = try!(optional_info(self, b"EVENT", |mut info| info.string_owned()))
+            .map(|mut values| values.swap_remove(0));
+        let imprecise = try!(optional_info(self, b"IMPRECISE", |mut info| info.flag().map(Some)))
+            .unwrap_or(false);
+
+        Ok(Some(SvInfo {
+            sv_type: sv_type,
+            sv_len: sv_len,
+            end: end,
+            cipos: cipos,
+            ciend: ciend,
+            mate_ids: mate_ids,
+            event: event,
+            imprecise: imprecise,
+        }))
+    }
+
+    /// Set the genotypes (`GT`) of all samples, which have to have the same ploidy.
+    ///
+    /// Note that in BCF, the phasing is stored with the second and following alleles, i.e.,
+    /// `1|0` is `[Unphased(1), Phased(0)]`.
+    ///
+    /// # Arguments
+    ///
+    /// - `genotypes` - a flattened, two-dimensional array, the first dimension contains the
+    ///                 alleles of each sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `GT` is not present in header.
+    pub fn push_genotypes(&mut self, genotypes: &[GenotypeAllele]) -> Result<(), TagWriteError> {
+        let encoded: Vec<i32> = genotypes.iter().map(|gt| gt.to_encoded()).collect();
+        self.push_format_integer(b"GT", &encoded)
+    }
+
+    /// Set the genotypes (`GT`) with the alleles of each sample, which may have a different
+    /// ploidy, e.g., haploid calls of males on chromosome X.
+    ///
+    /// An empty slice is written as missing genotype (`.`).
+    ///
+    /// # Arguments
+    ///
+    /// - `genotypes` - The alleles of each sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `GT` is not present in header or the number of genotypes differs from
+    /// the number of samples.
+    pub fn push_genotype_vectors(
+        &mut self,
+        genotypes: &[&[GenotypeAllele]],
+    ) -> Result<(), TagWriteError> {
+        let missing = [GenotypeAllele::UnphasedMissing.to_encoded()];
+        let encoded: Vec<Vec<i32>> = genotypes
+            .iter()
+            .map(|alleles| alleles.iter().map(|gt| gt.to_encoded()).collect())
+            .collect();
+        let encoded: Vec<&[i32]> = encoded
+            .iter()
+            .map(|alleles| {
+                if alleles.is_empty() {
+                    &missing[..]
+                } else {
+                    &alleles[..]
+                }
+            })
+            .collect();
+        let data = try!(self.flatten_per_sample(&encoded));
+        self.push_format(b"GT", &data, htslib::BCF_HT_INT)
+    }
 
-    /// Get genotypes as vector of one `Genotype` per sample.
+    /// Get genotypes as vector of one `Genotype` per sample.  Decodes the FORMAT fields.
     pub fn genotypes(&mut self) -> Result<Genotypes, FormatReadError> {
         Ok(Genotypes {
             encoded: try!(self.format(b"GT").integer()),
         })
     }
 
-    /// Get the value of the given format tag for each sample.
+    /// Get the genotype likelihoods of each sample from the `PL` tag or, if not present, the
+    /// `GL` tag.  Decodes the FORMAT fields.
+    ///
+    /// The ploidy of each sample is inferred from the number of values.  Samples whose number
+    /// of values does not match any ploidy yield `None`.
+    pub fn genotype_likelihoods(
+        &mut self,
+    ) -> Result<Vec<Option<GenotypeLikelihoods>>, FormatReadError> {
+        let n_allele = self.allele_count() as usize;
+        let phred = match self.format(b"PL").integer() {
+            Ok(values) => values
+                .iter()
+                .map(|v| {
+                    v.iter()
+                        .map(|&pl| if pl.is_missing() { None } else { Some(pl as f64) })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => try!(self.format(b"GL").float())
+                .iter()
+                .map(|v| {
+                    v.iter()
+                        .map(|&gl| if gl.is_missing() { None } else { Some(-10.0 * gl as f64) })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        };
+        Ok(phred
+            .into_iter()
+            .map(|phred| {
+                ploidy(n_allele, phred.len()).map(|ploidy| GenotypeLikelihoods {
+                    genotypes: genotype_order(n_allele, ploidy),
+                    phred: phred,
+                })
+            })
+            .collect())
+    }
+
+    /// Get the alternative allele dosage of each sample, i.e., the expected number of
+    /// alternative alleles, summed over all alternative alleles.  Decodes the FORMAT fields.
+    ///
+    /// For each sample, the dosage is taken from the `DS` tag if present, computed from the
+    /// genotype probabilities in the `GP` tag otherwise and counted from the genotype (`GT`)
+    /// as a last resort.  Samples with missing values in all of these yield `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if none of the tags is present in the record.
+    pub fn dosages(&mut self) -> Result<Vec<Option<f64>>, FormatReadError> {
+        let n_allele = self.allele_count() as usize;
+        let n_samples = self.header().sample_count() as usize;
+        let ds = self.format(b"DS")
+            .float()
+            .ok()
+            .map(|values| values.iter().map(|v| v.to_vec()).collect::<Vec<_>>());
+        let gp = self.format(b"GP")
+            .float()
+            .ok()
+            .map(|values| values.iter().map(|v| v.to_vec()).collect::<Vec<_>>());
+        let gt = self.format(b"GT")
+            .integer()
+            .ok()
+            .map(|values| values.iter().map(|v| v.to_vec()).collect::<Vec<_>>());
+        if ds.is_none() && gp.is_none() && gt.is_none() {
+            return Err(FormatReadError::MissingTag);
+        }
+
+        let from_ds = |ds: &[f32]| {
+            if ds.is_empty() || ds.iter().any(|v| v.is_missing()) {
+                None
+            } else {
+                Some(ds.iter().map(|&v| v as f64).sum())
+            }
+        };
+        let from_gp = |gp: &[f32]| {
+            if gp.iter().any(|v| v.is_missing()) {
+                return None;
+            }
+            ploidy(n_allele, gp.len()).map(|ploidy| {
+                genotype_order(n_allele, ploidy)
+                    .iter()
+                    .zip(gp)
+                    .map(|(gt, &p)| gt.iter().filter(|&&a| a > 0).count() as f64 * p as f64)
+                    .sum()
+            })
+        };
+        let from_gt = |gt: &[i32]| {
+            // encoded alleles below 2 are missing, from 4 on alternative
+            if gt.is_empty() || gt.iter().any(|&e| e < 2) {
+                None
+            } else {
+                Some(gt.iter().filter(|&&e| e >= 4).count() as f64)
+            }
+        };
+        Ok((0..n_samples)
+            .map(|i| {
+                ds.as_ref()
+                    .and_then(|ds| from_ds(&ds[i]))
+                    .or_else(|| gp.as_ref().and_then(|gp| from_gp(&gp[i])))
+                    .or_else(|| gt.as_ref().and_then(|gt| from_gt(&gt[i])))
+            })
+            .collect())
+    }
+
+    /// Return the allelic depths (`FORMAT/AD`) of each sample, with one entry per allele, the
+    /// reference allele first.  Missing values yield `None`, as do alleles beyond the values
+    /// given for a sample.
+    pub fn allele_depths(&mut self) -> Result<Vec<Vec<Option<i32>>>, FormatReadError> {
+        let n_allele = self.allele_count() as usize;
+        let ad = try!(self.format(b"AD").integer());
+        Ok(ad.iter()
+            .map(|values| {
+                (0..n_allele)
+                    .map(|i| values.get(i).cloned().filter(|v| !v.is_missing()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Return the read depth (`FORMAT/DP`) of each sample, `None` if missing.
+    pub fn depths(&mut self) -> Result<Vec<Option<i32>>, FormatReadError> {
+        let dp = try!(self.format(b"DP").integer());
+        Ok(dp.iter()
+            .map(|values| values.first().cloned().filter(|v| !v.is_missing()))
+            .collect())
+    }
+
+    /// Compute the given INFO fields from the genotypes, like the `fill-tags` plugin of
+    /// bcftools, e.g., after subsetting the samples.  Existing values are replaced.
+    ///
+    /// The fields have to be defined in the header, see `Header::add_fill_tags()`.  Allele
+    /// frequencies are missing if no allele has been called.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is not defined in the header or defined with another type,
+    /// or if the record has no genotypes.
+    pub fn fill_tags(&mut self, tags: &[FillTag]) -> Result<(), FillTagsError> {
+        for tag in tags {
+            let name = || String::from_utf8_lossy(tag.id()).into_owned();
+            match self.header().info_type(tag.id()) {
+                Ok((tag_type, _)) if tag_type == tag.definition().1 => (),
+                Ok(_) => return Err(FillTagsError::UnexpectedType(name())),
+                Err(_) => return Err(FillTagsError::UndefinedTag(name())),
+            }
+        }
+
+        let n_allele = self.allele_count() as usize;
+        let mut counts = vec![0; n_allele];
+        let mut n_samples = 0;
+        {
+            let genotypes = try!(
+                self.genotypes()
+                    .map_err(|_| FillTagsError::MissingGenotypes)
+            );
+            for genotype in genotypes.iter() {
+                let mut called = false;
+                for i in genotype.iter().filter_map(|allele| allele.index()) {
+                    if let Some(count) = counts.get_mut(i as usize) {
+                        *count += 1;
+                        called = true;
+                    }
+                }
+                if called {
+                    n_samples += 1;
+                }
+            }
+        }
+        let an: i32 = counts.iter().sum();
+        let frequencies = counts
+            .iter()
+            .map(|&count| {
+                if an > 0 {
+                    count as f32 / an as f32
+                } else {
+                    f32::missing()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for tag in tags {
+            let id = tag.id();
+            // remove previous values first, as htslib cannot enlarge values it has allocated
+            // itself, e.g., after the number of alleles has changed
+            let cleared = match tag.definition().1 {
+                TagType::Integer => self.clear_info_integer(id),
+                _ => self.clear_info_float(id),
+            };
+            try!(cleared.map_err(|_| FillTagsError::Some));
+            let result = match *tag {
+                FillTag::AlleleCount => self.push_info_integer(id, &counts[1..]),
+                FillTag::AlleleNumber => self.push_info_integer(id, &[an]),
+                FillTag::AlleleFrequency => self.push_info_float(id, &frequencies[1..]),
+                FillTag::MinorAlleleFrequency => {
+                    // without called alleles, the frequencies are missing and cannot be sorted
+                    let maf = if an == 0 {
+                        f32::missing()
+                    } else {
+                        let mut sorted = frequencies.clone();
+                        sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+                        sorted.get(1).cloned().unwrap_or(0.0)
+                    };
+                    self.push_info_float(id, &[maf])
+                }
+                FillTag::SampleCount => self.push_info_integer(id, &[n_samples]),
+            };
+            try!(result.map_err(|_| FillTagsError::Some));
+        }
+        Ok(())
+    }
+
+    /// Return an iterator over the samples of the record, yielding a `SampleView` for each,
+    /// which gives access to the FORMAT values of that sample.  Decodes the FORMAT fields.
+    pub fn samples(&self) -> Samples {
+        Samples {
+            record: self,
+            idx: 0,
+        }
+    }
+
+    /// Get the value of the given format tag for each sample.  Decodes the FORMAT fields.
     pub fn format<'a>(&'a mut self, tag: &'a [u8]) -> Format {
         Format::new(self, tag)
     }
@@ -417,6 +1032,74 @@ impl Record {
         self.push_format(tag, data, htslib::BCF_HT_STR)
     }
 
+    /// Add an integer-typed FORMAT tag with a vector of values for each sample.
+    ///
+    /// The vectors may have different lengths, shorter ones are padded as required by BCF.
+    /// An empty vector is written as missing value (`.`), use `i32::missing()` (see `Numeric`)
+    /// for single missing values.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    /// - `data` - One vector of values for each sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header or the number of vectors differs from the
+    /// number of samples.
+    pub fn push_format_integer_vectors(
+        &mut self,
+        tag: &[u8],
+        data: &[&[i32]],
+    ) -> Result<(), TagWriteError> {
+        let data = try!(self.flatten_per_sample(data));
+        self.push_format(tag, &data, htslib::BCF_HT_INT)
+    }
+
+    /// Add a float-typed FORMAT tag with a vector of values for each sample, see
+    /// `push_format_integer_vectors()`.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    /// - `data` - One vector of values for each sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header or the number of vectors differs from the
+    /// number of samples.
+    pub fn push_format_float_vectors(
+        &mut self,
+        tag: &[u8],
+        data: &[&[f32]],
+    ) -> Result<(), TagWriteError> {
+        let data = try!(self.flatten_per_sample(data));
+        self.push_format(tag, &data, htslib::BCF_HT_REAL)
+    }
+
+    /// Flatten per-sample vectors, padding them to the same length.
+    fn flatten_per_sample<T: Copy + Numeric>(
+        &self,
+        data: &[&[T]],
+    ) -> Result<Vec<T>, TagWriteError> {
+        if data.len() != self.header().sample_count() as usize {
+            return Err(TagWriteError::InvalidSampleCount);
+        }
+        let width = data.iter().map(|values| values.len()).max().unwrap_or(0).max(1);
+        let mut result = Vec::with_capacity(width * data.len());
+        for values in data {
+            if values.is_empty() {
+                result.push(T::missing());
+            } else {
+                result.extend_from_slice(values);
+            }
+            for _ in values.len().max(1)..width {
+                result.push(T::vector_end());
+            }
+        }
+        Ok(result)
+    }
+
     /// Add a format tag. Data is a flattened two-dimensional array.
     /// The first dimension contains one array for each sample.
     fn push_format<T>(&mut self, tag: &[u8], data: &[T], ht: u32) -> Result<(), TagWriteError> {
@@ -438,7 +1121,58 @@ impl Record {
         }
     }
 
-    // TODO: should we add convenience methods clear_format_*?
+    /// Remove the FORMAT tag from the record, tags that are not present are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    pub fn clear_format(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
+        // the type is ignored when removing the tag
+        unsafe {
+            if htslib::bcf_update_format(
+                self.header().inner,
+                self.inner,
+                ffi::CString::new(tag).unwrap().as_ptr() as *mut i8,
+                ptr::null(),
+                0,
+                htslib::BCF_HT_INT as i32,
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(TagWriteError::Some)
+            }
+        }
+    }
+
+    /// Set the FORMAT tag to a single missing value for every sample, according to the type of
+    /// the tag in the header.  For `GT`, a missing haploid genotype is written.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header or is of type `Flag`.
+    pub fn push_format_missing(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
+        let sample_count = self.header().sample_count() as usize;
+        if sample_count == 0 {
+            return Err(TagWriteError::InvalidSampleCount);
+        }
+        if tag == b"GT" {
+            return self.push_genotypes(&vec![GenotypeAllele::UnphasedMissing; sample_count]);
+        }
+        match self.header().format_type(tag) {
+            Ok((TagType::Integer, _)) => {
+                self.push_format_integer(tag, &vec![i32::missing(); sample_count])
+            }
+            Ok((TagType::Float, _)) => {
+                self.push_format_float(tag, &vec![f32::missing(); sample_count])
+            }
+            Ok((TagType::String, _)) => {
+                self.push_format_string(tag, &vec![&b"."[..]; sample_count])
+            }
+            Ok((TagType::Flag, _)) => Err(TagWriteError::UnexpectedType),
+            Err(_) => Err(TagWriteError::UndefinedTag),
+        }
+    }
 
     /// Add a string-typed FORMAT tag.
     ///
@@ -476,28 +1210,51 @@ impl Record {
     }
 
     /// Add an integer-typed INFO entry.
-    pub fn push_info_integer(&mut self, tag: &[u8], data: &[i32]) -> Result<(), TagWriteError> {
-        self.push_info(tag, data, htslib::BCF_HT_INT)
-    }
-
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    /// - `data` - The values, must not be empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header.
+    pub fn push_info_integer(&mut self, tag: &[u8], data: &[i32]) -> Result<(), TagWriteError> {
+        self.push_info(tag, data, htslib::BCF_HT_INT)
+    }
+
     /// Remove the integer-typed INFO entry.
     pub fn clear_info_integer(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
-        self.push_info::<i32>(tag, &[], htslib::BCF_HT_INT)
+        self.update_info::<i32>(tag, &[], htslib::BCF_HT_INT)
     }
 
     /// Add a float-typed INFO entry.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    /// - `data` - The values, must not be empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header.
     pub fn push_info_float(&mut self, tag: &[u8], data: &[f32]) -> Result<(), TagWriteError> {
         self.push_info(tag, data, htslib::BCF_HT_REAL)
     }
 
     /// Remove the float-typed INFO entry.
     pub fn clear_info_float(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
-        self.push_info::<u8>(tag, &[], htslib::BCF_HT_REAL)
+        self.update_info::<f32>(tag, &[], htslib::BCF_HT_REAL)
     }
 
-    /// Add a not INFO tag.
+    /// Add a numeric INFO tag.
     fn push_info<T>(&mut self, tag: &[u8], data: &[T], ht: u32) -> Result<(), TagWriteError> {
         assert!(data.len() > 0);
+        self.update_info(tag, data, ht)
+    }
+
+    /// Update a numeric INFO tag, removing it if `data` is empty.
+    fn update_info<T>(&mut self, tag: &[u8], data: &[T], ht: u32) -> Result<(), TagWriteError> {
         unsafe {
             if htslib::bcf_update_info(
                 self.header().inner,
@@ -516,6 +1273,10 @@ impl Record {
     }
 
     /// Set flag into the INFO column.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header.
     pub fn push_info_flag(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
         self.push_info_string_impl(tag, &["".as_bytes()], htslib::BCF_HT_FLAG)
     }
@@ -526,6 +1287,15 @@ impl Record {
     }
 
     /// Add a string-typed INFO entry.
+    ///
+    /// # Arguments
+    ///
+    /// - `tag` - The tag's string.
+    /// - `data` - The values, which are joined by commas.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header.
     pub fn push_info_string(&mut self, tag: &[u8], data: &[&[u8]]) -> Result<(), TagWriteError> {
         self.push_info_string_impl(tag, data, htslib::BCF_HT_STR)
     }
@@ -535,6 +1305,23 @@ impl Record {
         self.push_info_string_impl(tag, &[], htslib::BCF_HT_STR)
     }
 
+    /// Set the INFO tag to a single missing value, according to the type of the tag in the
+    /// header.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tag is not present in header or is of type `Flag`, which cannot be
+    /// missing but only be cleared (see `clear_info_flag()`).
+    pub fn push_info_missing(&mut self, tag: &[u8]) -> Result<(), TagWriteError> {
+        match self.header().info_type(tag) {
+            Ok((TagType::Integer, _)) => self.push_info_integer(tag, &[i32::missing()]),
+            Ok((TagType::Float, _)) => self.push_info_float(tag, &[f32::missing()]),
+            Ok((TagType::String, _)) => self.push_info_string(tag, &[b"."]),
+            Ok((TagType::Flag, _)) => Err(TagWriteError::UnexpectedType),
+            Err(_) => Err(TagWriteError::UndefinedTag),
+        }
+    }
+
     /// Add an string-valued INFO tag.
     fn push_info_string_impl(
         &mut self,
@@ -572,13 +1359,881 @@ impl Record {
         }
     }
 
-    /// Remove unused alleles.
+    /// Remove alternative alleles that are not used by the genotypes (`GT`) of any sample,
+    /// e.g., after subsetting the samples.
+    ///
+    /// INFO and FORMAT values with one value per allele (`A`, `R` and `G` number) are updated
+    /// accordingly.  Records without genotypes are left unchanged.
     pub fn trim_alleles(&mut self) -> Result<(), TrimAllelesError> {
         match unsafe { htslib::bcf_trim_alleles(self.header().inner, self.inner) } {
             -1 => Err(TrimAllelesError::Some),
             _ => Ok(()),
         }
     }
+
+    /// Remove the alternative alleles with the given indices (`1` being the first ALT allele).
+    ///
+    /// Genotypes are remapped to the remaining alleles and INFO and FORMAT values with one value
+    /// per allele (`A`, `R` and `G` number) are updated accordingly.
+    pub fn remove_alleles(&mut self, alleles: &[usize]) -> Result<(), RemoveAllelesError> {
+        let n_allele = self.allele_count() as usize;
+        for &i in alleles {
+            if i == 0 || i >= n_allele {
+                return Err(RemoveAllelesError::InvalidIndex(i));
+            }
+        }
+
+        let ret = unsafe {
+            let set = htslib::wrap_kbs_init(n_allele);
+            if set.is_null() {
+                return Err(RemoveAllelesError::Some);
+            }
+            for &i in alleles {
+                htslib::wrap_kbs_insert(set, i as i32);
+            }
+            let ret = htslib::bcf_remove_allele_set(self.header().inner, self.inner, set);
+            htslib::wrap_kbs_destroy(set);
+            ret
+        };
+        match ret {
+            0 => Ok(()),
+            _ => Err(RemoveAllelesError::Some),
+        }
+    }
+
+    /// Return `true` if both records are at the same position of the same contig.
+    ///
+    /// Contigs are compared by name, so records with different headers (e.g., from different
+    /// files) can be compared.
+    pub fn same_site(&self, other: &Record) -> bool {
+        self.pos() == other.pos() && self.contig_name() == other.contig_name()
+    }
+
+    /// Return `true` if both records describe the same variants, i.e., the same alternative
+    /// alleles at the same site, regardless of their order.
+    ///
+    /// Each alternative allele is compared in its minimal representation, i.e., with bases
+    /// shared with the reference allele trimmed, and multiallelic records are equal to the
+    /// corresponding biallelic records taken together.  Indels are not left-aligned, use
+    /// `normalize()` for this first.  Contigs are compared by name, see `same_site()`.
+    pub fn same_variant(&self, other: &Record) -> bool {
+        if self.contig_name() != other.contig_name() {
+            return false;
+        }
+        let mut variants = self.minimal_variants();
+        let mut other_variants = other.minimal_variants();
+        variants.sort();
+        variants.dedup();
+        other_variants.sort();
+        other_variants.dedup();
+        variants == other_variants
+    }
+
+    /// Return the contig name, if set.
+    fn contig_name(&self) -> Option<&[u8]> {
+        self.rid().map(|rid| self.header().rid2name(rid))
+    }
+
+    /// Return the minimal representation of each alternative allele as position, reference
+    /// and alternative sequence, see `same_variant()`.
+    fn minimal_variants(&self) -> Vec<(u32, Vec<u8>, Vec<u8>)> {
+        let alleles = self.alleles();
+        let reference = alleles[0].to_ascii_uppercase();
+        alleles[1..]
+            .iter()
+            .map(|alt| {
+                let mut pos = self.pos();
+                let mut reference = reference.clone();
+                let mut alt = alt.to_ascii_uppercase();
+                if alt.iter().all(|b| b.is_ascii_alphabetic()) {
+                    while reference.len() > 1 && alt.len() > 1 && reference.last() == alt.last() {
+                        reference.pop();
+                        alt.pop();
+                    }
+                    while reference.len() > 1 && alt.len() > 1 && reference[0] == alt[0] {
+                        reference.remove(0);
+                        alt.remove(0);
+                        pos += 1;
+                    }
+                }
+                (pos, reference, alt)
+            })
+            .collect()
+    }
+
+    /// Split the record into one biallelic record per alternative allele, as done by
+    /// `bcftools norm -m-`.
+    ///
+    /// INFO and FORMAT values with one value per allele (`A`, `R` and `G` number) are
+    /// distributed to the new records.  In the genotypes, other alternative alleles are
+    /// replaced by the reference allele, e.g., `1/2` becomes `1/0` and `0/1`.  Records with at
+    /// most one alternative allele are returned as a single copy.
+    pub fn split_multiallelic(&self) -> Result<Vec<Record>, SplitError> {
+        let n_allele = self.allele_count() as usize;
+        if n_allele <= 2 {
+            return Ok(vec![self.clone()]);
+        }
+        (1..n_allele)
+            .map(|i| {
+                let mut record = self.clone();
+                let genotypes = match record.format(b"GT").integer() {
+                    Ok(genotypes) => Some(
+                        genotypes
+                            .iter()
+                            .map(|gt| {
+                                gt.iter()
+                                    .map(|&e| {
+                                        // keep missing alleles, the reference and allele i
+                                        if e < 2 || (e >> 1) - 1 == i as i32 {
+                                            e
+                                        } else {
+                                            2 | (e & 1)
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                    Err(_) => None,
+                };
+                if let Some(genotypes) = genotypes {
+                    let genotypes = genotypes.iter().map(|gt| gt.as_slice()).collect::<Vec<_>>();
+                    try!(
+                        record
+                            .push_format_integer_vectors(b"GT", &genotypes)
+                            .map_err(|_| SplitError::Some)
+                    );
+                }
+                let others = (1..n_allele).filter(|&j| j != i).collect::<Vec<_>>();
+                try!(record.remove_alleles(&others).map_err(|_| SplitError::Some));
+                Ok(record)
+            })
+            .collect()
+    }
+
+    /// Join records at the same position into one multiallelic record, as done by
+    /// `bcftools norm -m+`, i.e., the inverse of `split_multiallelic()`.
+    ///
+    /// The alternative alleles are collected in order of appearance, extending them if the
+    /// reference alleles differ in length (e.g., `A>G` and `AG>A` become `AG>GG,A`).  Integer
+    /// and float INFO and FORMAT values with one value per allele (`A`, `R` and `G` number)
+    /// are merged, taking the first non-missing value, and the alternative alleles of the
+    /// genotypes are combined, e.g., `1/0` and `0/1` become `1/2`.  All other fields are taken
+    /// from the first record.
+    pub fn join_multiallelic(records: &[Record]) -> Result<Record, JoinError> {
+        let mut records = records.to_vec();
+        let (rid, pos, header) = match records.first() {
+            Some(first) => (first.rid(), first.pos(), first.header.clone()),
+            None => return Err(JoinError::NoRecords),
+        };
+        if records.iter().any(|r| r.rid() != rid || r.pos() != pos) {
+            return Err(JoinError::DifferentPosition);
+        }
+        if records.iter().any(|r| r.header().inner != header.inner) {
+            return Err(JoinError::DifferentHeader);
+        }
+
+        // Collect the alleles and map the alleles of each record to them.
+        let reference = records
+            .iter()
+            .map(|r| r.alleles()[0].to_vec())
+            .max_by_key(|a| a.len())
+            .unwrap();
+        let mut alleles = vec![reference.clone()];
+        let mut maps = Vec::new();
+        for record in &records {
+            let record_alleles = record.alleles();
+            if !reference.starts_with(record_alleles[0]) {
+                return Err(JoinError::ReferenceMismatch);
+            }
+            let suffix = &reference[record_alleles[0].len()..];
+            let mut map = vec![0];
+            for alt in &record_alleles[1..] {
+                let mut alt = alt.to_vec();
+                if !suffix.is_empty() {
+                    if !alt.iter().all(|b| b.is_ascii_alphabetic()) {
+                        return Err(JoinError::ReferenceMismatch);
+                    }
+                    alt.extend_from_slice(suffix);
+                }
+                let i = match alleles.iter().position(|a| *a == alt) {
+                    Some(i) => i,
+                    None => {
+                        alleles.push(alt);
+                        alleles.len() - 1
+                    }
+                };
+                map.push(i);
+            }
+            maps.push(map);
+        }
+        let n_allele = alleles.len();
+
+        let mut joined = records[0].clone();
+        let alleles = alleles.iter().map(|a| a.as_slice()).collect::<Vec<_>>();
+        try!(joined.set_alleles(&alleles).map_err(|_| JoinError::Some));
+
+        for def in header.info_defs() {
+            let tag = def.id.as_bytes();
+            if !is_per_allele(&def.length) {
+                continue;
+            }
+            let result = match def.tag_type {
+                TagType::Integer => {
+                    let mut values = None;
+                    for (record, map) in records.iter_mut().zip(&maps) {
+                        if let Ok(Some(v)) = record.info(tag).integer() {
+                            join_values(&mut values, v, map, &def.length, n_allele);
+                        }
+                    }
+                    match values {
+                        Some(values) => joined.push_info_integer(tag, &values),
+                        None => joined.clear_info_integer(tag),
+                    }
+                }
+                TagType::Float => {
+                    let mut values = None;
+                    for (record, map) in records.iter_mut().zip(&maps) {
+                        if let Ok(Some(v)) = record.info(tag).float() {
+                            join_values(&mut values, v, map, &def.length, n_allele);
+                        }
+                    }
+                    match values {
+                        Some(values) => joined.push_info_float(tag, &values),
+                        None => joined.clear_info_float(tag),
+                    }
+                }
+                _ => Ok(()),
+            };
+            try!(result.map_err(|_| JoinError::Some));
+        }
+
+        let n_samples = header.sample_count() as usize;
+        for def in header.format_defs() {
+            let tag = def.id.as_bytes();
+            if !is_per_allele(&def.length) {
+                continue;
+            }
+            let result = match def.tag_type {
+                TagType::Integer => {
+                    let mut values = vec![None; n_samples];
+                    for (record, map) in records.iter_mut().zip(&maps) {
+                        if let Ok(v) = record.format(tag).integer() {
+                            for (joined, v) in values.iter_mut().zip(v) {
+                                join_values(joined, v, map, &def.length, n_allele);
+                            }
+                        }
+                    }
+                    if values.iter().all(|v| v.is_none()) {
+                        joined.clear_format(tag)
+                    } else {
+                        let values = values
+                            .iter()
+                            .map(|v| v.as_ref().map_or(&[][..], |v| v.as_slice()))
+                            .collect::<Vec<_>>();
+                        joined.push_format_integer_vectors(tag, &values)
+                    }
+                }
+                TagType::Float => {
+                    let mut values = vec![None; n_samples];
+                    for (record, map) in records.iter_mut().zip(&maps) {
+                        if let Ok(v) = record.format(tag).float() {
+                            for (joined, v) in values.iter_mut().zip(v) {
+                                join_values(joined, v, map, &def.length, n_allele);
+                            }
+                        }
+                    }
+                    if values.iter().all(|v| v.is_none()) {
+                        joined.clear_format(tag)
+                    } else {
+                        let values = values
+                            .iter()
+                            .map(|v| v.as_ref().map_or(&[][..], |v| v.as_slice()))
+                            .collect::<Vec<_>>();
+                        joined.push_format_float_vectors(tag, &values)
+                    }
+                }
+                _ => Ok(()),
+            };
+            try!(result.map_err(|_| JoinError::Some));
+        }
+
+        // Combine the genotypes, replacing reference and missing alleles by alternative ones.
+        let mut genotypes: Vec<Option<Vec<i32>>> = vec![None; n_samples];
+        let mut has_genotypes = false;
+        for (record, map) in records.iter_mut().zip(&maps) {
+            if let Ok(gts) = record.format(b"GT").integer() {
+                has_genotypes = true;
+                for (joined, gt) in genotypes.iter_mut().zip(gts) {
+                    let gt = gt.iter()
+                        .map(|&e| match map.get(((e >> 1) - 1) as usize) {
+                            Some(&a) if e >= 2 => ((a as i32 + 1) << 1) | (e & 1),
+                            _ => e,
+                        })
+                        .collect::<Vec<_>>();
+                    match *joined {
+                        Some(ref mut joined) => for (j, e) in joined.iter_mut().zip(gt) {
+                            if e >= 4 && *j < 4 {
+                                *j = e;
+                            }
+                        },
+                        None => *joined = Some(gt),
+                    }
+                }
+            }
+        }
+        if has_genotypes {
+            let genotypes = genotypes
+                .iter()
+                .map(|gt| gt.as_ref().map_or(&[][..], |gt| gt.as_slice()))
+                .collect::<Vec<_>>();
+            try!(
+                joined
+                    .push_format_integer_vectors(b"GT", &genotypes)
+                    .map_err(|_| JoinError::Some)
+            );
+        }
+        Ok(joined)
+    }
+
+    /// Check that the reference allele matches the given reference sequence at the position
+    /// of the record, as done by `bcftools norm --check-ref`.  Bases are compared
+    /// case-insensitively.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CheckRefError::Mismatch` with the expected and the found reference allele if
+    /// they differ, or an error if the contig is missing or unknown to the reference.
+    pub fn check_ref(&self, reference: &faidx::Reader) -> Result<(), CheckRefError> {
+        let rid = try!(self.rid().ok_or(CheckRefError::MissingContig));
+        let contig = self.header().rid2name(rid);
+        let found = self.alleles()[0].to_ascii_uppercase();
+        let expected = try!(
+            reference
+                .fetch_seq(contig, self.pos(), self.pos() + found.len() as u32)
+                .map_err(|_| {
+                    CheckRefError::UnknownContig(String::from_utf8_lossy(contig).into_owned())
+                })
+        ).to_ascii_uppercase();
+        if expected != found {
+            return Err(CheckRefError::Mismatch {
+                pos: self.pos(),
+                expected: expected,
+                found: found,
+            });
+        }
+        Ok(())
+    }
+
+    /// Left-align and trim the alleles against the given reference sequence, as done by
+    /// `bcftools norm`.
+    ///
+    /// Bases shared by all alleles are removed from the end and the start, and indels in
+    /// repeats are shifted to their leftmost position, keeping one base before them.  Records
+    /// with fewer than two distinct alleles or with symbolic, breakend or `*` alleles are left
+    /// unchanged.  Returns whether the record has been changed.
+    ///
+    /// Note that `INFO/END` is not updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contig is missing or unknown to the reference, or the
+    /// reference allele does not match the reference sequence.
+    pub fn normalize(&mut self, reference: &faidx::Reader) -> Result<bool, NormalizeError> {
+        let rid = try!(self.rid().ok_or(NormalizeError::MissingContig));
+        let contig = self.header().rid2name(rid).to_owned();
+        let fetch = |begin: u32, end: u32| {
+            reference
+                .fetch_seq(&contig, begin, end)
+                .map(|seq| seq.to_ascii_uppercase())
+                .map_err(|_| {
+                    NormalizeError::UnknownContig(String::from_utf8_lossy(&contig).into_owned())
+                })
+        };
+
+        let mut pos = self.pos();
+        let mut alleles = self.alleles()
+            .iter()
+            .map(|a| a.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        // without an alternative allele that differs from the reference, there is nothing to
+        // normalize
+        if alleles.iter().all(|a| *a == alleles[0]) {
+            return Ok(false);
+        }
+        if alleles
+            .iter()
+            .any(|a| a.is_empty() || !a.iter().all(|b| b"ACGTN".contains(b)))
+        {
+            return Ok(false);
+        }
+        try!(self.check_ref(reference).map_err(|e| match e {
+            CheckRefError::MissingContig => NormalizeError::MissingContig,
+            CheckRefError::UnknownContig(name) => NormalizeError::UnknownContig(name),
+            CheckRefError::Mismatch { .. } => NormalizeError::ReferenceMismatch,
+        }));
+
+        // Trim common bases at the end, extending the alleles to the left when one of them
+        // becomes empty.
+        let mut changed = false;
+        loop {
+            let last = alleles[0].last().cloned();
+            if alleles.iter().all(|a| a.last().cloned() == last)
+                && (pos > 0 || alleles.iter().all(|a| a.len() > 1))
+            {
+                for allele in alleles.iter_mut() {
+                    allele.pop();
+                }
+                changed = true;
+            } else {
+                break;
+            }
+            if alleles.iter().any(|a| a.is_empty()) {
+                pos -= 1;
+                let base = try!(fetch(pos, pos + 1))[0];
+                for allele in alleles.iter_mut() {
+                    allele.insert(0, base);
+                }
+            }
+        }
+        // Trim common bases at the start, keeping at least one base per allele.
+        while alleles.iter().all(|a| a.len() > 1 && a[0] == alleles[0][0]) {
+            for allele in alleles.iter_mut() {
+                allele.remove(0);
+            }
+            pos += 1;
+            changed = true;
+        }
+
+        if changed {
+            self.set_pos(pos as i32);
+            let alleles = alleles.iter().map(|a| a.as_slice()).collect::<Vec<_>>();
+            try!(
+                self.set_alleles(&alleles)
+                    .map_err(|_| NormalizeError::InvalidAlleles)
+            );
+        }
+        Ok(changed)
+    }
+
+    /// Format the record as a VCF line (without trailing newline), using the associated header.
+    pub fn to_vcf_bytes(&self) -> Result<Vec<u8>, VcfFormatError> {
+        let mut buf = htslib::kstring_t {
+            l: 0,
+            m: 0,
+            s: ptr::null_mut(),
+        };
+        unsafe {
+            let ret = htslib::vcf_format(self.header().inner, self.inner, &mut buf);
+            let line = if ret < 0 || buf.s.is_null() {
+                Err(VcfFormatError::Some)
+            } else {
+                let mut line = slice::from_raw_parts(buf.s as *const u8, buf.l as usize).to_vec();
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+                Ok(line)
+            };
+            ::libc::free(buf.s as *mut ::libc::c_void);
+            line
+        }
+    }
+
+    /// Format the record as a VCF line, see `to_vcf_bytes()`.  Invalid UTF-8 sequences are
+    /// replaced.
+    pub fn to_vcf_string(&self) -> Result<String, VcfFormatError> {
+        self.to_vcf_bytes().map(|line| String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+impl fmt::Display for Record {
+    /// Display the record as a VCF line, see `Record::to_vcf_bytes()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_vcf_string() {
+            Ok(line) => write!(f, "{}", line),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// Parts of a record to decode, see `Record::unpack()`.
+///
+/// Each of the shared levels includes the ones before it, i.e., `Filter` also decodes the ID
+/// and the alleles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unpack {
+    /// ID, REF and ALT.
+    Str,
+    /// Everything up to FILTER.
+    Filter,
+    /// Everything up to INFO, i.e., all shared fields.
+    Info,
+    /// The FORMAT fields only.
+    Format,
+    /// The whole record.
+    All,
+}
+
+impl Unpack {
+    fn as_flags(&self) -> i32 {
+        (match *self {
+            Unpack::Str => htslib::BCF_UN_STR,
+            Unpack::Filter => htslib::BCF_UN_FLT,
+            Unpack::Info => htslib::BCF_UN_SHR,
+            Unpack::Format => htslib::BCF_UN_FMT,
+            Unpack::All => htslib::BCF_UN_ALL,
+        }) as i32
+    }
+}
+
+/// Type of an alternative allele, as classified by `Record::variant_types`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariantType {
+    /// No variation, e.g., the `*` allele or the `<*>` allele of gVCF files.
+    Reference,
+    /// Single nucleotide variant.
+    Snv,
+    /// Multi nucleotide variant, i.e., a substitution of several bases.
+    Mnv,
+    Insertion,
+    Deletion,
+    /// Any other sequence change.
+    Complex,
+    /// Symbolic allele, e.g., `<DEL>`.
+    Symbolic,
+    /// Breakend, e.g., `G]17:198982]`, which can be parsed with `Breakend::parse()`.
+    Breakend,
+}
+
+/// Type of a structural variant (`INFO/SVTYPE`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SvType {
+    Deletion,
+    Insertion,
+    Duplication,
+    Inversion,
+    /// Copy number variation.
+    Cnv,
+    /// Breakend, the other breakends are given by `MATEID`.
+    Breakend,
+}
+
+impl SvType {
+    /// Parse a `SVTYPE` value, e.g., `DEL`.
+    pub fn from_bytes(value: &[u8]) -> Result<Self, SvInfoError> {
+        match value {
+            b"DEL" => Ok(SvType::Deletion),
+            b"INS" => Ok(SvType::Insertion),
+            b"DUP" => Ok(SvType::Duplication),
+            b"INV" => Ok(SvType::Inversion),
+            b"CNV" => Ok(SvType::Cnv),
+            b"BND" => Ok(SvType::Breakend),
+            _ => Err(SvInfoError::InvalidSvType(
+                String::from_utf8_lossy(value).into_owned(),
+            )),
+        }
+    }
+
+    /// Return the `SVTYPE` value.
+    pub fn to_bytes(&self) -> &'static [u8] {
+        match *self {
+            SvType::Deletion => b"DEL",
+            SvType::Insertion => b"INS",
+            SvType::Duplication => b"DUP",
+            SvType::Inversion => b"INV",
+            SvType::Cnv => b"CNV",
+            SvType::Breakend => b"BND",
+        }
+    }
+}
+
+/// Structural variant fields of the INFO column, see `Record::sv_info()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvInfo {
+    /// The type of the variant (`SVTYPE`).
+    pub sv_type: SvType,
+    /// The length of the variant for each alternative allele (`SVLEN`), negative for
+    /// deletions.
+    pub sv_len: Vec<Option<i32>>,
+    /// The 1-based end position (`END`).
+    pub end: Option<u32>,
+    /// The confidence interval around the position (`CIPOS`).
+    pub cipos: Option<(i32, i32)>,
+    /// The confidence interval around the end position (`CIEND`).
+    pub ciend: Option<(i32, i32)>,
+    /// The IDs of the mate breakends (`MATEID`).
+    pub mate_ids: Vec<Vec<u8>>,
+    /// The ID of the event the breakend belongs to (`EVENT`).
+    pub event: Option<Vec<u8>>,
+    /// Whether the variant is imprecise (`IMPRECISE`).
+    pub imprecise: bool,
+}
+
+/// Side of a position at which a piece of sequence continues, see `Breakend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A breakend ALT allele as defined by the VCF specification, e.g., `G]17:198982]`.
+///
+/// The sequence consists of the reference base and the inserted sequence, if any.  It replaces
+/// the reference base, such that the reference sequence continues on the side of the position
+/// given by `side`, and is joined to the sequence continuing at the mate position (if any) on
+/// the other side.
+///
+/// # Example
+///
+/// ```
+/// use rust_htslib::bcf::record::{Breakend, Side};
+///
+/// let breakend = Breakend::parse(b"G]17:198982]").unwrap();
+/// assert_eq!(
+///     breakend,
+///     Breakend::Paired {
+///         sequence: b"G".to_vec(),
+///         side: Side::Left,
+///         mate_contig: b"17".to_vec(),
+///         mate_assembled: false,
+///         mate_pos: 198982,
+///         mate_side: Side::Left,
+///     }
+/// );
+/// assert_eq!(breakend.to_string(), "G]17:198982]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Breakend {
+    /// A breakend joined to a mate breakend, `t[p[`, `t]p]`, `]p]t` or `[p[t`.
+    Paired {
+        /// The reference base and the inserted sequence (`t`).
+        sequence: Vec<u8>,
+        /// `Left` if the sequence precedes the mate (`t[p[` and `t]p]`), `Right` otherwise.
+        side: Side,
+        /// The contig of the mate.
+        mate_contig: Vec<u8>,
+        /// Whether the mate contig is an assembled contig in angle brackets, e.g., `<ctg1>`.
+        mate_assembled: bool,
+        /// The 1-based position of the mate.
+        mate_pos: u32,
+        /// The side of the mate position at which the joined sequence continues, `Right` for
+        /// `[` and `Left` for `]`.
+        mate_side: Side,
+    },
+    /// A single breakend, `t.` or `.t`.
+    Single {
+        /// The reference base and the inserted sequence (`t`).
+        sequence: Vec<u8>,
+        /// `Left` for `t.`, `Right` for `.t`.
+        side: Side,
+    },
+}
+
+impl Breakend {
+    /// Parse a breakend ALT allele.
+    pub fn parse(allele: &[u8]) -> Result<Self, BreakendParseError> {
+        let name = || String::from_utf8_lossy(allele).into_owned();
+        let invalid = || BreakendParseError::InvalidNotation(name());
+        let is_sequence = |s: &[u8]| !s.is_empty() && s.iter().all(|b| b.is_ascii_alphabetic());
+        let bracket = match allele.iter().position(|&b| b == b'[' || b == b']') {
+            Some(i) => i,
+            None => {
+                return if allele.len() > 1 && allele[0] == b'.' && is_sequence(&allele[1..]) {
+                    Ok(Breakend::Single {
+                        sequence: allele[1..].to_vec(),
+                        side: Side::Right,
+                    })
+                } else if allele.len() > 1 && allele[allele.len() - 1] == b'.'
+                    && is_sequence(&allele[..allele.len() - 1])
+                {
+                    Ok(Breakend::Single {
+                        sequence: allele[..allele.len() - 1].to_vec(),
+                        side: Side::Left,
+                    })
+                } else {
+                    Err(invalid())
+                };
+            }
+        };
+
+        let b = allele[bracket];
+        let (sequence, side, mate) = if bracket == 0 {
+            let end = try!(
+                allele[1..]
+                    .iter()
+                    .position(|&c| c == b)
+                    .map(|i| i + 1)
+                    .ok_or_else(&invalid)
+            );
+            (&allele[end + 1..], Side::Right, &allele[1..end])
+        } else {
+            if allele.len() < bracket + 2 || allele[allele.len() - 1] != b {
+                return Err(invalid());
+            }
+            (&allele[..bracket], Side::Left, &allele[bracket + 1..allele.len() - 1])
+        };
+        if !is_sequence(sequence) || mate.iter().any(|&c| c == b'[' || c == b']') {
+            return Err(invalid());
+        }
+        let colon = try!(mate.iter().rposition(|&c| c == b':').ok_or_else(&invalid));
+        let (mut mate_contig, mate_pos) = (&mate[..colon], &mate[colon + 1..]);
+        let mate_assembled = mate_contig.len() > 2 && mate_contig[0] == b'<'
+            && mate_contig[mate_contig.len() - 1] == b'>';
+        if mate_assembled {
+            mate_contig = &mate_contig[1..mate_contig.len() - 1];
+        }
+        if mate_contig.is_empty() {
+            return Err(invalid());
+        }
+        let mate_pos = try!(
+            str::from_utf8(mate_pos)
+                .ok()
+                .and_then(|pos| pos.parse::<u32>().ok())
+                .ok_or_else(|| BreakendParseError::InvalidPosition(name()))
+        );
+        Ok(Breakend::Paired {
+            sequence: sequence.to_vec(),
+            side: side,
+            mate_contig: mate_contig.to_vec(),
+            mate_assembled: mate_assembled,
+            mate_pos: mate_pos,
+            mate_side: if b == b'[' { Side::Right } else { Side::Left },
+        })
+    }
+
+    /// Format the breakend as ALT allele.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            Breakend::Paired {
+                ref sequence,
+                side,
+                ref mate_contig,
+                mate_assembled,
+                mate_pos,
+                mate_side,
+            } => {
+                let b = match mate_side {
+                    Side::Left => b']',
+                    Side::Right => b'[',
+                };
+                let mut mate = vec![b];
+                if mate_assembled {
+                    mate.push(b'<');
+                }
+                mate.extend_from_slice(mate_contig);
+                if mate_assembled {
+                    mate.push(b'>');
+                }
+                mate.extend_from_slice(format!(":{}", mate_pos).as_bytes());
+                mate.push(b);
+                match side {
+                    Side::Left => [&sequence[..], &mate[..]].concat(),
+                    Side::Right => [&mate[..], &sequence[..]].concat(),
+                }
+            }
+            Breakend::Single { ref sequence, side } => match side {
+                Side::Left => [&sequence[..], b"."].concat(),
+                Side::Right => [b".", &sequence[..]].concat(),
+            },
+        }
+    }
+
+    /// Return the reference base and the inserted sequence.
+    pub fn sequence(&self) -> &[u8] {
+        match *self {
+            Breakend::Paired { ref sequence, .. } | Breakend::Single { ref sequence, .. } => {
+                sequence
+            }
+        }
+    }
+
+    /// Return the side of the position at which the reference sequence continues.
+    pub fn side(&self) -> Side {
+        match *self {
+            Breakend::Paired { side, .. } | Breakend::Single { side, .. } => side,
+        }
+    }
+
+    /// Return the inserted sequence, i.e., the sequence without the reference base.
+    pub fn inserted_sequence(&self) -> &[u8] {
+        let sequence = self.sequence();
+        match self.side() {
+            Side::Left => &sequence[1..],
+            Side::Right => &sequence[..sequence.len() - 1],
+        }
+    }
+}
+
+impl fmt::Display for Breakend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// INFO field computed from the genotypes by `Record::fill_tags()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillTag {
+    /// `AC`, the number of each alternative allele in the called genotypes.
+    AlleleCount,
+    /// `AN`, the total number of alleles in the called genotypes.
+    AlleleNumber,
+    /// `AF`, the frequency of each alternative allele.
+    AlleleFrequency,
+    /// `MAF`, the frequency of the second most common allele.
+    MinorAlleleFrequency,
+    /// `NS`, the number of samples with at least one called allele.
+    SampleCount,
+}
+
+impl FillTag {
+    /// Return all fields.
+    pub fn all() -> Vec<FillTag> {
+        vec![
+            FillTag::AlleleCount,
+            FillTag::AlleleNumber,
+            FillTag::AlleleFrequency,
+            FillTag::MinorAlleleFrequency,
+            FillTag::SampleCount,
+        ]
+    }
+
+    /// Return the ID of the INFO field.
+    pub fn id(&self) -> &'static [u8] {
+        match *self {
+            FillTag::AlleleCount => b"AC",
+            FillTag::AlleleNumber => b"AN",
+            FillTag::AlleleFrequency => b"AF",
+            FillTag::MinorAlleleFrequency => b"MAF",
+            FillTag::SampleCount => b"NS",
+        }
+    }
+
+    /// Return the number of values, the type and the description of the INFO field.
+    pub fn definition(&self) -> (TagLength, TagType, &'static str) {
+        match *self {
+            FillTag::AlleleCount => (
+                TagLength::AltAlleles,
+                TagType::Integer,
+                "Allele count in genotypes",
+            ),
+            FillTag::AlleleNumber => (
+                TagLength::Fixed(1),
+                TagType::Integer,
+                "Total number of alleles in called genotypes",
+            ),
+            FillTag::AlleleFrequency => (TagLength::AltAlleles, TagType::Float, "Allele frequency"),
+            FillTag::MinorAlleleFrequency => (
+                TagLength::Fixed(1),
+                TagType::Float,
+                "Frequency of the second most common allele",
+            ),
+            FillTag::SampleCount => (
+                TagLength::Fixed(1),
+                TagType::Integer,
+                "Number of samples with data",
+            ),
+        }
+    }
 }
 
 /// Phased or unphased alleles, represented as indices.
@@ -602,6 +2257,16 @@ impl GenotypeAllele {
         }
     }
 
+    /// Encode according to BCF standard, the inverse of `from_encoded()`.
+    pub fn to_encoded(&self) -> i32 {
+        match self {
+            &GenotypeAllele::Unphased(i) => (i + 1) << 1,
+            &GenotypeAllele::Phased(i) => (i + 1) << 1 | 1,
+            &GenotypeAllele::UnphasedMissing => 0,
+            &GenotypeAllele::PhasedMissing => 1,
+        }
+    }
+
     /// Get the index into the list of alleles.
     pub fn index(&self) -> Option<u32> {
         match self {
@@ -652,7 +2317,25 @@ pub struct Genotypes<'a> {
 }
 
 impl<'a> Genotypes<'a> {
-    /// Get genotype of ith sample. So far, only supports diploid genotypes.
+    /// Return the number of samples.
+    pub fn len(&self) -> usize {
+        self.encoded.len()
+    }
+
+    /// Return `true` if there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.encoded.is_empty()
+    }
+
+    /// Iterate over the genotypes of all samples, see `get()`.
+    pub fn iter<'b>(&'b self) -> impl Iterator<Item = Genotype> + 'b {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    /// Get genotype of ith sample.
+    ///
+    /// Samples with lower ploidy than others yield fewer alleles, e.g., for haploid calls on
+    /// chromosome X.
     ///
     /// Note that the result complies with the BCF spec. This means that the
     /// first allele will always be marked as `Unphased`. That is, if you have 1|1 in the VCF,
@@ -668,6 +2351,145 @@ impl<'a> Genotypes<'a> {
     }
 }
 
+/// Genotype likelihoods of one sample, see `Record::genotype_likelihoods()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenotypeLikelihoods {
+    genotypes: Vec<Vec<usize>>,
+    phred: Vec<Option<f64>>,
+}
+
+impl GenotypeLikelihoods {
+    /// Return the ploidy of the sample.
+    pub fn ploidy(&self) -> usize {
+        self.genotypes[0].len()
+    }
+
+    /// Iterate over the genotypes, given as sorted allele indices, and their phred-scaled
+    /// likelihoods (as in `PL`), in the order of the VCF specification.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a [usize], Option<f64>)> + 'a {
+        self.genotypes
+            .iter()
+            .map(|gt| gt.as_slice())
+            .zip(self.phred.iter().cloned())
+    }
+
+    /// Get the phred-scaled likelihood of the genotype with the given alleles, in any order.
+    pub fn get(&self, alleles: &[usize]) -> Option<f64> {
+        if alleles.len() != self.ploidy() {
+            return None;
+        }
+        self.phred.get(genotype_index(alleles)).cloned().and_then(|pl| pl)
+    }
+
+    /// Return the most likely genotype, i.e., the one with the smallest phred-scaled
+    /// likelihood.  `None` if all values are missing.
+    pub fn most_likely_genotype(&self) -> Option<&[usize]> {
+        self.ranked().first().map(|&(i, _)| self.genotypes[i].as_slice())
+    }
+
+    /// Return the genotype quality (as in `GQ`), i.e., the difference between the two smallest
+    /// phred-scaled likelihoods.  `None` if less than two values are present.
+    pub fn genotype_quality(&self) -> Option<f64> {
+        let ranked = self.ranked();
+        if ranked.len() < 2 {
+            None
+        } else {
+            Some(ranked[1].1 - ranked[0].1)
+        }
+    }
+
+    /// Return the indices and values of all present values, sorted by value.
+    fn ranked(&self) -> Vec<(usize, f64)> {
+        let mut ranked = self.phred
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pl)| pl.map(|pl| (i, pl)))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        ranked
+    }
+}
+
+/// Return the index of the genotype with the given alleles, in any order, in fields with one
+/// value per genotype (`G` number, e.g., `PL`), as defined by the VCF specification.
+///
+/// For diploid genotypes `a/b` with `a <= b`, this is `b * (b + 1) / 2 + a`.
+pub fn genotype_index(alleles: &[usize]) -> usize {
+    let mut alleles = alleles.to_vec();
+    alleles.sort();
+    alleles
+        .iter()
+        .enumerate()
+        .map(|(k, &a)| binomial(a + k, k + 1))
+        .sum()
+}
+
+/// Return the genotypes, given as sorted allele indices, of the given ploidy and number of
+/// alleles in the order of fields with one value per genotype, see `genotype_index()`.
+pub fn genotype_order(n_allele: usize, ploidy: usize) -> Vec<Vec<usize>> {
+    let mut genotypes = vec![Vec::new()];
+    for _ in 0..ploidy {
+        // extend each genotype by an allele not smaller than its last one
+        genotypes = genotypes
+            .into_iter()
+            .flat_map(|gt: Vec<usize>| {
+                let first = gt.last().cloned().unwrap_or(0);
+                (first..n_allele).map(move |a| {
+                    let mut gt = gt.clone();
+                    gt.push(a);
+                    gt
+                })
+            })
+            .collect();
+    }
+    genotypes.sort_by_key(|gt| genotype_index(gt));
+    genotypes
+}
+
+/// Return the ploidy of samples with `n_values` values in fields with one value per genotype
+/// for `n_allele` alleles, `None` if there is no such ploidy.
+fn ploidy(n_allele: usize, n_values: usize) -> Option<usize> {
+    (1..n_values + 1)
+        .find(|&ploidy| binomial(n_allele + ploidy - 1, ploidy) >= n_values)
+        .filter(|&ploidy| binomial(n_allele + ploidy - 1, ploidy) == n_values)
+}
+
+/// Binomial coefficient `n` choose `k`.
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Records are equal if their VCF lines (see `Record::to_vcf_bytes()`) are equal, so records
+/// with different headers can be compared.
+impl PartialEq for Record {
+    fn eq(&self, other: &Record) -> bool {
+        match (self.to_vcf_bytes(), other.to_vcf_bytes()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Clone for Record {
+    /// Create a deep copy of the record, associated with the same header.
+    fn clone(&self) -> Self {
+        let inner = unsafe {
+            // bcf_dup packs pending changes of the source record before copying it.
+            let inner = htslib::bcf_dup(self.inner);
+            htslib::bcf_unpack(inner, htslib::BCF_UN_ALL as i32);
+            inner
+        };
+        Record {
+            inner: inner,
+            header: self.header.clone(),
+            buffer: ptr::null_mut(),
+        }
+    }
+}
+
 impl Drop for Record {
     fn drop(&mut self) {
         if !self.buffer.is_null() {
@@ -731,10 +2553,8 @@ impl<'a> Info<'a> {
 
     /// Get flags from tag. `false` if not set.
     pub fn flag(&mut self) -> Result<bool, InfoReadError> {
-        self.data(htslib::BCF_HT_FLAG).map(|data| match data {
-            Some((_, ret)) => ret == 1,
-            None => false,
-        })
+        let set = try!(self.data(htslib::BCF_HT_FLAG)).map_or(false, |(_, ret)| ret == 1);
+        Ok(set && self.record.has_info(self.tag))
     }
 
     /// Get strings from tag. `None` if tag not present in record.
@@ -753,12 +2573,32 @@ impl<'a> Info<'a> {
             })
         })
     }
+
+    /// Get integers from tag as an owned vector, see `integer()`.
+    ///
+    /// Unlike the slice returned by `integer()`, which points into a buffer of the record that
+    /// is reused by the next access, the result does not borrow the record.  Hence, values of
+    /// several tags can be kept at the same time.
+    pub fn integer_owned(&mut self) -> Result<Option<Vec<i32>>, InfoReadError> {
+        self.integer().map(|data| data.map(|data| data.to_vec()))
+    }
+
+    /// Get floats from tag as an owned vector, see `integer_owned()`.
+    pub fn float_owned(&mut self) -> Result<Option<Vec<f32>>, InfoReadError> {
+        self.float().map(|data| data.map(|data| data.to_vec()))
+    }
+
+    /// Get strings from tag as owned vectors, see `integer_owned()`.
+    pub fn string_owned(&mut self) -> Result<Option<Vec<Vec<u8>>>, InfoReadError> {
+        self.string()
+            .map(|data| data.map(|data| data.into_iter().map(|s| s.to_vec()).collect()))
+    }
 }
 
 unsafe impl<'a> Send for Info<'a> {}
 unsafe impl<'a> Sync for Info<'a> {}
 
-fn trim_slice<T: PartialEq + NumericUtils>(s: &[T]) -> &[T] {
+fn trim_slice<T: PartialEq + Numeric>(s: &[T]) -> &[T] {
     s.split(|v| v.is_vector_end())
         .next()
         .expect("Bug: returned slice should not be empty.")
@@ -841,7 +2681,11 @@ impl<'a> Format<'a> {
         })
     }
 
-    /// Get format data as byte slices. To obtain the values strings, use `std::str::from_utf8`.
+    /// Get format data as one byte slice per sample, e.g., for the `FT` tag.
+    ///
+    /// Multiple values of a sample are separated by commas, as in VCF.  The slices point into a
+    /// buffer of the record that is reused by the next access.  To obtain the values strings,
+    /// use `std::str::from_utf8`.
     pub fn string(&mut self) -> Result<Vec<&'a [u8]>, FormatReadError> {
         self.data(htslib::BCF_HT_STR).map(|(n, _)| {
             unsafe { slice::from_raw_parts(self.record.buffer as *const u8, n) }
@@ -888,6 +2732,239 @@ impl<'a> Iterator for Filters<'a> {
     }
 }
 
+/// Iterator over the samples of a record, see `Record::samples()`.
+#[derive(Debug)]
+pub struct Samples<'a> {
+    record: &'a Record,
+    idx: usize,
+}
+
+impl<'a> Iterator for Samples<'a> {
+    type Item = SampleView<'a>;
+
+    fn next(&mut self) -> Option<SampleView<'a>> {
+        if self.idx >= self.record.header().sample_count() as usize {
+            None
+        } else {
+            self.idx += 1;
+            Some(SampleView {
+                record: self.record,
+                idx: self.idx - 1,
+            })
+        }
+    }
+}
+
+/// The FORMAT values of one sample of a record.
+///
+/// The values are decoded directly from the record for the sample only, so iterating over
+/// all samples and tags is as cheap as reading the tags with `Record::format()`.
+#[derive(Debug)]
+pub struct SampleView<'a> {
+    record: &'a Record,
+    idx: usize,
+}
+
+impl<'a> SampleView<'a> {
+    /// Return the 0-based index of the sample.
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+
+    /// Return the name of the sample.
+    pub fn name(&self) -> &'a [u8] {
+        self.record.header().samples()[self.idx]
+    }
+
+    /// Return the FORMAT entry of the given tag and a pointer to the values of the sample.
+    fn fmt(&self, tag: &[u8]) -> Result<(&'a htslib::bcf_fmt_t, *const u8), FormatReadError> {
+        let header = self.record.header();
+        if header.name_to_id(tag).is_err() {
+            return Err(FormatReadError::UndefinedTag);
+        }
+        let fmt = unsafe {
+            htslib::bcf_get_fmt(
+                header.inner,
+                self.record.inner,
+                ffi::CString::new(tag).unwrap().as_ptr() as *mut i8,
+            )
+        };
+        if fmt.is_null() {
+            return Err(FormatReadError::MissingTag);
+        }
+        let fmt = unsafe { &*fmt };
+        let p = unsafe { fmt.p.offset(self.idx as isize * fmt.size as isize) };
+        Ok((fmt, p))
+    }
+
+    /// Get the values of the given integer tag.
+    ///
+    /// Import `bcf::record::Numeric` for missing value handling.
+    pub fn integer(&self, tag: &[u8]) -> Result<Vec<i32>, FormatReadError> {
+        let (fmt, p) = try!(self.fmt(tag));
+        let n = fmt.n as usize;
+        let values: Vec<i32> = match fmt.type_ as u32 {
+            htslib::BCF_BT_INT8 => unsafe { read_values::<i8>(p, n) }
+                .into_iter()
+                .map(|v| match v {
+                    i8::MIN => MISSING_INTEGER,
+                    v if v == i8::MIN + 1 => VECTOR_END_INTEGER,
+                    v => v as i32,
+                })
+                .collect(),
+            htslib::BCF_BT_INT16 => unsafe { read_values::<i16>(p, n) }
+                .into_iter()
+                .map(|v| match v {
+                    i16::MIN => MISSING_INTEGER,
+                    v if v == i16::MIN + 1 => VECTOR_END_INTEGER,
+                    v => v as i32,
+                })
+                .collect(),
+            htslib::BCF_BT_INT32 => unsafe { read_values::<i32>(p, n) },
+            _ => return Err(FormatReadError::UnexpectedType),
+        };
+        Ok(trim_slice(&values).to_vec())
+    }
+
+    /// Get the values of the given float tag.
+    ///
+    /// Import `bcf::record::Numeric` for missing value handling.
+    pub fn float(&self, tag: &[u8]) -> Result<Vec<f32>, FormatReadError> {
+        let (fmt, p) = try!(self.fmt(tag));
+        if fmt.type_ as u32 != htslib::BCF_BT_FLOAT {
+            return Err(FormatReadError::UnexpectedType);
+        }
+        let values = unsafe { read_values::<f32>(p, fmt.n as usize) };
+        Ok(trim_slice(&values).to_vec())
+    }
+
+    /// Get the value of the given string tag.  Multiple values are separated by commas.
+    pub fn string(&self, tag: &[u8]) -> Result<&'a [u8], FormatReadError> {
+        let (fmt, p) = try!(self.fmt(tag));
+        if fmt.type_ as u32 != htslib::BCF_BT_CHAR {
+            return Err(FormatReadError::UnexpectedType);
+        }
+        let value = unsafe { slice::from_raw_parts(p, fmt.n as usize) };
+        Ok(value.split(|c| *c == 0u8).next().unwrap_or(value))
+    }
+
+    /// Get the genotype (`GT`) of the sample.
+    pub fn genotype(&self) -> Result<Genotype, FormatReadError> {
+        self.integer(b"GT").map(|encoded| {
+            Genotype(encoded.into_iter().map(GenotypeAllele::from_encoded).collect())
+        })
+    }
+
+    /// Get the read depth (`DP`) of the sample, `None` if missing.
+    pub fn depth(&self) -> Result<Option<i32>, FormatReadError> {
+        self.single_integer(b"DP")
+    }
+
+    /// Get the allelic depths (`AD`) of the sample, see `Record::allele_depths()`.
+    pub fn allele_depths(&self) -> Result<Vec<Option<i32>>, FormatReadError> {
+        let n_allele = self.record.allele_count() as usize;
+        self.integer(b"AD").map(|values| {
+            (0..n_allele)
+                .map(|i| values.get(i).cloned().filter(|v| !v.is_missing()))
+                .collect()
+        })
+    }
+
+    /// Get the genotype quality (`GQ`) of the sample, `None` if missing.
+    pub fn genotype_quality(&self) -> Result<Option<i32>, FormatReadError> {
+        self.single_integer(b"GQ")
+    }
+
+    fn single_integer(&self, tag: &[u8]) -> Result<Option<i32>, FormatReadError> {
+        self.integer(tag)
+            .map(|values| values.first().cloned().filter(|v| !v.is_missing()))
+    }
+}
+
+/// Get an INFO field with `get` for `Record::sv_info()`, treating undefined fields as not set.
+///
+/// A field defined with an unexpected type is only an error if it is set in the record.
+fn optional_info<T, F>(record: &mut Record, tag: &[u8], get: F) -> Result<Option<T>, SvInfoError>
+where
+    F: FnOnce(Info) -> Result<Option<T>, InfoReadError>,
+{
+    match get(record.info(tag)) {
+        Ok(value) => Ok(value),
+        Err(InfoReadError::UndefinedTag) => Ok(None),
+        Err(InfoReadError::UnexpectedType) if !record.has_info(tag) => Ok(None),
+        Err(InfoReadError::UnexpectedType) => Err(SvInfoError::UnexpectedType(
+            String::from_utf8_lossy(tag).into_owned(),
+        )),
+    }
+}
+
+/// Return whether a field of the given length has one value per allele or genotype.
+fn is_per_allele(length: &TagLength) -> bool {
+    match *length {
+        TagLength::AltAlleles | TagLength::Alleles | TagLength::Genotypes => true,
+        _ => false,
+    }
+}
+
+/// Merge the values of a field with one value per allele or genotype of a record, whose
+/// alleles are mapped to `n_allele` joined alleles by `map`, into `joined`, see
+/// `Record::join_multiallelic()`.  Values not matching the length (e.g., for a different
+/// ploidy) are ignored.
+fn join_values<T: Numeric + Copy>(
+    joined: &mut Option<Vec<T>>,
+    values: &[T],
+    map: &[usize],
+    length: &TagLength,
+    n_allele: usize,
+) {
+    let n = map.len();
+    let (n_joined, indices): (usize, Vec<Option<usize>>) = match *length {
+        TagLength::AltAlleles if values.len() == n - 1 => {
+            (n_allele - 1, map[1..].iter().map(|&i| i.checked_sub(1)).collect())
+        }
+        TagLength::Alleles | TagLength::Genotypes if values.len() == n => {
+            (n_allele, map.iter().map(|&i| Some(i)).collect())
+        }
+        TagLength::Genotypes if values.len() == n * (n + 1) / 2 => {
+            let mut indices = Vec::new();
+            for b in 0..n {
+                for a in 0..b + 1 {
+                    indices.push(Some(genotype_index(&[map[a], map[b]])));
+                }
+            }
+            (n_allele * (n_allele + 1) / 2, indices)
+        }
+        _ => return,
+    };
+    let joined = joined.get_or_insert_with(|| vec![T::missing(); n_joined]);
+    if joined.len() != n_joined {
+        return;
+    }
+    for (&value, &i) in values.iter().zip(indices.iter()) {
+        if let Some(i) = i {
+            if joined[i].is_missing() {
+                joined[i] = value;
+            }
+        }
+    }
+}
+
+/// Return the content of the given string buffer of htslib.
+fn kstring_bytes(s: &htslib::kstring_t) -> &[u8] {
+    if s.s.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(s.s as *const u8, s.l) }
+    }
+}
+
+/// Read `n` values of type `T` from the possibly unaligned pointer `p`.
+unsafe fn read_values<T: Copy>(p: *const u8, n: usize) -> Vec<T> {
+    (0..n)
+        .map(|i| ptr::read_unaligned((p as *const T).offset(i as isize)))
+        .collect()
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum IterFilterError {
@@ -930,6 +3007,15 @@ quick_error! {
         Some {
             description("error writing tag to record")
         }
+        InvalidSampleCount {
+            description("number of values does not match number of samples")
+        }
+        UndefinedTag {
+            description("tag undefined in header")
+        }
+        UnexpectedType {
+            description("tag type differs from header definition")
+        }
     }
 }
 
@@ -948,6 +3034,9 @@ quick_error! {
         Some {
             description("error writing alleles to record")
         }
+        InvalidAllele {
+            description("alleles must be non-empty and must not contain ',', tabs or newlines")
+        }
     }
 }
 
@@ -968,3 +3057,161 @@ quick_error! {
         }
     }
 }
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum JoinError {
+        NoRecords {
+            description("no records to join")
+        }
+        DifferentPosition {
+            description("records to join differ in contig or position")
+        }
+        DifferentHeader {
+            description("records to join have different headers")
+        }
+        ReferenceMismatch {
+            description("reference alleles of records to join are incompatible")
+        }
+        Some {
+            description("error joining records")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum SplitError {
+        Some {
+            description("error splitting multiallelic record")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum BreakendParseError {
+        InvalidNotation(allele: String) {
+            description("invalid breakend notation")
+            display("invalid breakend notation {}", allele)
+        }
+        InvalidPosition(allele: String) {
+            description("invalid mate position in breakend")
+            display("invalid mate position in breakend {}", allele)
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum SvInfoError {
+        UnexpectedType(name: String) {
+            description("tag type differs from the VCF specification")
+            display("type of tag {} differs from the VCF specification", name)
+        }
+        InvalidValueCount(name: String) {
+            description("invalid number of values")
+            display("invalid number of values of tag {}", name)
+        }
+        InvalidValue(name: String) {
+            description("invalid value")
+            display("invalid value of tag {}", name)
+        }
+        InvalidSvType(value: String) {
+            description("invalid SVTYPE")
+            display("invalid SVTYPE {}", value)
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum FillTagsError {
+        UndefinedTag(name: String) {
+            description("tag undefined in header")
+            display("tag {} is undefined in header", name)
+        }
+        UnexpectedType(name: String) {
+            description("tag type differs from the expected type")
+            display("type of tag {} differs from the expected type", name)
+        }
+        MissingGenotypes {
+            description("record has no genotypes")
+        }
+        Some {
+            description("error writing tag to record")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum CheckRefError {
+        MissingContig {
+            description("contig of record is missing")
+        }
+        UnknownContig(name: String) {
+            description("contig unknown to reference")
+            display("contig {} is unknown to reference", name)
+        }
+        Mismatch { pos: u32, expected: Vec<u8>, found: Vec<u8> } {
+            description("reference allele does not match reference sequence")
+            display(
+                "reference allele {} at position {} does not match reference sequence {}",
+                String::from_utf8_lossy(found),
+                pos + 1,
+                String::from_utf8_lossy(expected)
+            )
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum NormalizeError {
+        MissingContig {
+            description("contig of record is missing")
+        }
+        UnknownContig(name: String) {
+            description("contig unknown to reference")
+            display("contig {} is unknown to reference", name)
+        }
+        ReferenceMismatch {
+            description("reference allele does not match reference sequence")
+        }
+        InvalidAlleles {
+            description("error writing normalized alleles")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum VcfParseError {
+        Some {
+            description("error parsing VCF line")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum VcfFormatError {
+        Some {
+            description("error formatting record as VCF")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum RemoveAllelesError {
+        InvalidIndex(index: usize) {
+            description("invalid allele index")
+            display("invalid allele index: {}", index)
+        }
+        Some {
+            description("error removing alleles")
+        }
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright 2014 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Builder for creating BCF records from scratch.
+
+use bcf::header::{HeaderView, TagType};
+use bcf::record::{GenotypeAllele, Numeric, Record};
+use bcf::Writer;
+use htslib;
+
+/// Values of an `INFO` or `FORMAT` tag.
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    Flag,
+    Integer(Vec<i32>),
+    Float(Vec<f32>),
+    String(Vec<Vec<u8>>),
+}
+
+impl Values {
+    fn tag_type(&self) -> TagType {
+        match *self {
+            Values::Flag => TagType::Flag,
+            Values::Integer(_) => TagType::Integer,
+            Values::Float(_) => TagType::Float,
+            Values::String(_) => TagType::String,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match *self {
+            Values::Flag => false,
+            Values::Integer(ref data) => data.is_empty(),
+            Values::Float(ref data) => data.is_empty(),
+            Values::String(ref data) => data.is_empty(),
+        }
+    }
+}
+
+/// A builder for new records, validating all fields against the header of the record.
+///
+/// All setters return the builder, so that calls can be chained.  The builder can be used to
+/// create several records, e.g., differing in position only.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut builder = RecordBuilder::new();
+/// builder
+///     .contig(b"19")
+///     .pos(3111938)
+///     .alleles(&[b"A", b"AG"])
+///     .filter(b"PASS")
+///     .info_integer(b"N1", &[1])
+///     .genotypes(&[
+///         GenotypeAllele::Unphased(0),
+///         GenotypeAllele::Unphased(1),
+///         GenotypeAllele::Unphased(1),
+///         GenotypeAllele::Unphased(1),
+///     ]);
+/// let record = builder.build(&writer)?;
+/// writer.write(&record)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordBuilder {
+    contig: Option<Vec<u8>>,
+    pos: Option<u32>,
+    id: Option<Vec<u8>>,
+    alleles: Vec<Vec<u8>>,
+    qual: Option<f32>,
+    filters: Vec<Vec<u8>>,
+    info: Vec<(Vec<u8>, Values)>,
+    format: Vec<(Vec<u8>, Values)>,
+    genotypes: Option<Vec<GenotypeAllele>>,
+}
+
+impl RecordBuilder {
+    /// Create a new builder without any fields set.
+    pub fn new() -> Self {
+        RecordBuilder::default()
+    }
+
+    /// Set the contig (CHROM) by name.  Required.
+    pub fn contig(&mut self, contig: &[u8]) -> &mut Self {
+        self.contig = Some(contig.to_vec());
+        self
+    }
+
+    /// Set the 0-based position.  Required.
+    pub fn pos(&mut self, pos: u32) -> &mut Self {
+        self.pos = Some(pos);
+        self
+    }
+
+    /// Set the value of the ID column, missing (`.`) by default.
+    pub fn id(&mut self, id: &[u8]) -> &mut Self {
+        self.id = Some(id.to_vec());
+        self
+    }
+
+    /// Set the alleles, the first being the reference allele.  Required.
+    pub fn alleles(&mut self, alleles: &[&[u8]]) -> &mut Self {
+        self.alleles = alleles.iter().map(|a| a.to_vec()).collect();
+        self
+    }
+
+    /// Set the variant quality, missing by default.
+    pub fn qual(&mut self, qual: f32) -> &mut Self {
+        self.qual = Some(qual);
+        self
+    }
+
+    /// Add a filter by name, e.g., `b"PASS"`.  Without filters, FILTER is missing (`.`).
+    pub fn filter(&mut self, filter: &[u8]) -> &mut Self {
+        self.filters.push(filter.to_vec());
+        self
+    }
+
+    /// Set the values of the given integer `INFO` tag.
+    pub fn info_integer(&mut self, tag: &[u8], data: &[i32]) -> &mut Self {
+        self.push_info(tag, Values::Integer(data.to_vec()))
+    }
+
+    /// Set the values of the given float `INFO` tag.
+    pub fn info_float(&mut self, tag: &[u8], data: &[f32]) -> &mut Self {
+        self.push_info(tag, Values::Float(data.to_vec()))
+    }
+
+    /// Set the values of the given string `INFO` tag.
+    pub fn info_string(&mut self, tag: &[u8], data: &[&[u8]]) -> &mut Self {
+        self.push_info(tag, Values::String(data.iter().map(|s| s.to_vec()).collect()))
+    }
+
+    /// Set the given flag `INFO` tag.
+    pub fn info_flag(&mut self, tag: &[u8]) -> &mut Self {
+        self.push_info(tag, Values::Flag)
+    }
+
+    /// Set the values of the given integer `FORMAT` tag, given as the concatenated values of
+    /// all samples (see `Record::push_format_integer()`).
+    pub fn format_integer(&mut self, tag: &[u8], data: &[i32]) -> &mut Self {
+        self.push_format(tag, Values::Integer(data.to_vec()))
+    }
+
+    /// Set the values of the given float `FORMAT` tag, given as the concatenated values of all
+    /// samples (see `Record::push_format_float()`).
+    pub fn format_float(&mut self, tag: &[u8], data: &[f32]) -> &mut Self {
+        self.push_format(tag, Values::Float(data.to_vec()))
+    }
+
+    /// Set the values of the given string `FORMAT` tag, one value per sample.
+    pub fn format_string(&mut self, tag: &[u8], data: &[&[u8]]) -> &mut Self {
+        self.push_format(tag, Values::String(data.iter().map(|s| s.to_vec()).collect()))
+    }
+
+    /// Set the genotypes (`GT`) of all samples, which have to have the same ploidy (see
+    /// `Record::push_genotypes()`).
+    pub fn genotypes(&mut self, genotypes: &[GenotypeAllele]) -> &mut Self {
+        self.genotypes = Some(genotypes.to_vec());
+        self
+    }
+
+    fn push_info(&mut self, tag: &[u8], values: Values) -> &mut Self {
+        self.info.retain(|&(ref t, _)| t.as_slice() != tag);
+        self.info.push((tag.to_vec(), values));
+        self
+    }
+
+    fn push_format(&mut self, tag: &[u8], values: Values) -> &mut Self {
+        self.format.retain(|&(ref t, _)| t.as_slice() != tag);
+        self.format.push((tag.to_vec(), values));
+        self
+    }
+
+    /// Create a new record for the given writer, see `build_into()`.
+    pub fn build(&self, writer: &Writer) -> Result<Record, RecordBuildError> {
+        let mut record = writer.empty_record();
+        try!(self.build_into(&mut record));
+        Ok(record)
+    }
+
+    /// Replace all fields of the given record, e.g., one created by `empty_record()` of a
+    /// reader or writer, by the fields of this builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required field is not set, a tag has no values, or a contig,
+    /// filter or tag is not defined in the header of the record or has a different type or
+    /// number of samples.
+    pub fn build_into(&self, record: &mut Record) -> Result<(), RecordBuildError> {
+        let rid = {
+            let header = record.header();
+            let contig = try!(self.contig.as_ref().ok_or(RecordBuildError::MissingContig));
+            let rid = try!(
+                header
+                    .name2rid(contig)
+                    .map_err(|_| RecordBuildError::UndefinedContig(to_string(contig)))
+            );
+            try!(self.validate_tags(header));
+            rid
+        };
+        let pos = try!(self.pos.ok_or(RecordBuildError::MissingPosition));
+        if self.alleles.is_empty() {
+            return Err(RecordBuildError::MissingAlleles);
+        }
+        let filters = try!(
+            self.filters
+                .iter()
+                .map(|f| {
+                    record
+                        .header()
+                        .name_to_id(f)
+                        .map_err(|_| RecordBuildError::UndefinedFilter(to_string(f)))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        );
+
+        unsafe { htslib::bcf_clear(record.inner) };
+        record.set_rid(&Some(rid));
+        record.set_pos(pos as i32);
+        let alleles = self.alleles.iter().map(|a| a.as_slice()).collect::<Vec<_>>();
+        try!(
+            record
+                .set_alleles(&alleles)
+                .map_err(|_| RecordBuildError::InvalidAlleles)
+        );
+        if let Some(ref id) = self.id {
+            try!(record.set_id(id).map_err(|_| RecordBuildError::InvalidId));
+        }
+        record.set_qual(self.qual.unwrap_or_else(f32::missing));
+        record.set_filters(&filters);
+
+        for &(ref tag, ref values) in &self.info {
+            let tag = tag.as_slice();
+            try!(
+                match *values {
+                    Values::Flag => record.push_info_flag(tag),
+                    Values::Integer(ref data) => record.push_info_integer(tag, data),
+                    Values::Float(ref data) => record.push_info_float(tag, data),
+                    Values::String(ref data) => {
+                        let data = data.iter().map(|s| s.as_slice()).collect::<Vec<_>>();
+                        record.push_info_string(tag, &data)
+                    }
+                }.map_err(|_| RecordBuildError::InvalidTag(to_string(tag)))
+            );
+        }
+        if let Some(ref genotypes) = self.genotypes {
+            try!(
+                record
+                    .push_genotypes(genotypes)
+                    .map_err(|_| RecordBuildError::InvalidTag("GT".to_owned()))
+            );
+        }
+        for &(ref tag, ref values) in &self.format {
+            let tag = tag.as_slice();
+            try!(
+                match *values {
+                    Values::Flag => unreachable!(),
+                    Values::Integer(ref data) => record.push_format_integer(tag, data),
+                    Values::Float(ref data) => record.push_format_float(tag, data),
+                    Values::String(ref data) => {
+                        let data = data.iter().map(|s| s.as_slice()).collect::<Vec<_>>();
+                        record.push_format_string(tag, &data)
+                    }
+                }.map_err(|_| RecordBuildError::InvalidTag(to_string(tag)))
+            );
+        }
+        Ok(())
+    }
+
+    /// Check that all `INFO` and `FORMAT` tags are defined with the right type and that
+    /// `FORMAT` values are given for all samples.
+    fn validate_tags(&self, header: &HeaderView) -> Result<(), RecordBuildError> {
+        for &(ref tag, ref values) in &self.info {
+            match header.info_type(tag) {
+                Ok((tag_type, _)) if tag_type == values.tag_type() => (),
+                Ok(_) => return Err(RecordBuildError::UnexpectedType(to_string(tag))),
+                Err(_) => return Err(RecordBuildError::UndefinedInfo(to_string(tag))),
+            }
+            if values.is_empty() {
+                return Err(RecordBuildError::MissingValues(to_string(tag)));
+            }
+        }
+        let n_samples = header.sample_count() as usize;
+        for &(ref tag, ref values) in &self.format {
+            match header.format_type(tag) {
+                Ok((tag_type, _)) if tag_type == values.tag_type() => (),
+                Ok(_) => return Err(RecordBuildError::UnexpectedType(to_string(tag))),
+                Err(_) => return Err(RecordBuildError::UndefinedFormat(to_string(tag))),
+            }
+            if values.is_empty() {
+                return Err(RecordBuildError::MissingValues(to_string(tag)));
+            }
+            let valid_count = match *values {
+                Values::Flag => false,
+                Values::Integer(ref data) => n_samples > 0 && data.len() % n_samples == 0,
+                Values::Float(ref data) => n_samples > 0 && data.len() % n_samples == 0,
+                Values::String(ref data) => data.len() == n_samples,
+            };
+            if !valid_count {
+                return Err(RecordBuildError::InvalidSampleCount(to_string(tag)));
+            }
+        }
+        if let Some(ref genotypes) = self.genotypes {
+            if genotypes.is_empty() {
+                return Err(RecordBuildError::MissingValues("GT".to_owned()));
+            }
+            if n_samples == 0 || genotypes.len() % n_samples != 0 {
+                return Err(RecordBuildError::InvalidSampleCount("GT".to_owned()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_string(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum RecordBuildError {
+        MissingContig {
+            description("contig not set")
+        }
+        MissingPosition {
+            description("position not set")
+        }
+        MissingAlleles {
+            description("alleles not set")
+        }
+        UndefinedContig(name: String) {
+            description("contig undefined in header")
+            display("contig {} is undefined in header", name)
+        }
+        UndefinedFilter(name: String) {
+            description("filter undefined in header")
+            display("filter {} is undefined in header", name)
+        }
+        UndefinedInfo(tag: String) {
+            description("INFO tag undefined in header")
+            display("INFO tag {} is undefined in header", tag)
+        }
+        UndefinedFormat(tag: String) {
+            description("FORMAT tag undefined in header")
+            display("FORMAT tag {} is undefined in header", tag)
+        }
+        UnexpectedType(tag: String) {
+            description("tag type differs from header definition")
+            display("type of tag {} differs from header definition", tag)
+        }
+        MissingValues(tag: String) {
+            description("no values given for tag")
+            display("no values given for tag {}", tag)
+        }
+        InvalidSampleCount(tag: String) {
+            description("number of values does not match number of samples")
+            display("number of values of tag {} does not match number of samples", tag)
+        }
+        InvalidAlleles {
+            description("invalid alleles")
+        }
+        InvalidId {
+            description("invalid ID")
+        }
+        InvalidTag(tag: String) {
+            description("error writing tag to record")
+            display("error writing tag {} to record", tag)
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Module for random access to FASTA files indexed with `samtools faidx`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::faidx;
+//!
+//! let reader = faidx::Reader::from_path(&"ref.fa").unwrap();
+//! // 0-based, half-open interval
+//! let seq = reader.fetch_seq(b"chr1", 0, 100).unwrap();
+//! ```
+
+use libc;
+use std::ffi;
+use std::path::Path;
+use std::slice;
+
+use htslib;
+
+/// A reader for FASTA files with a `.fai` index.
+///
+/// The index is built automatically if it does not exist yet (this requires write access to
+/// the directory of the FASTA file).
+#[derive(Debug)]
+pub struct Reader {
+    inner: *mut htslib::faidx_t,
+}
+
+unsafe impl Send for Reader {}
+
+impl Reader {
+    /// Create a new Reader from path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path to open.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ReaderPathError> {
+        if let Some(p) = path.as_ref().to_str() {
+            Ok(try!(Self::new(p.as_bytes())))
+        } else {
+            Err(ReaderPathError::InvalidPath)
+        }
+    }
+
+    /// Create a new Reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path.
+    fn new(path: &[u8]) -> Result<Self, ReaderError> {
+        let path = ffi::CString::new(path).unwrap();
+        let inner = unsafe { htslib::fai_load(path.as_ptr()) };
+        if inner.is_null() {
+            Err(ReaderError::InvalidIndex)
+        } else {
+            Ok(Reader { inner: inner })
+        }
+    }
+
+    /// Return number of sequences in the index.
+    pub fn n_seqs(&self) -> u32 {
+        unsafe { htslib::faidx_nseq(self.inner) as u32 }
+    }
+
+    /// Return the names of the sequences in the index.
+    pub fn seq_names(&self) -> Vec<Vec<u8>> {
+        (0..self.n_seqs())
+            .map(|i| unsafe {
+                ffi::CStr::from_ptr(htslib::faidx_iseq(self.inner, i as i32))
+                    .to_bytes()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    /// Return length of the sequence with the given name, if it exists.
+    pub fn seq_len(&self, name: &[u8]) -> Option<u32> {
+        let name = ffi::CString::new(name).unwrap();
+        let len = unsafe { htslib::faidx_seq_len(self.inner, name.as_ptr()) };
+        if len < 0 {
+            None
+        } else {
+            Some(len as u32)
+        }
+    }
+
+    /// Fetch sequence of the 0-based, half-open interval `[begin, end)` of the given sequence.
+    ///
+    /// The interval is truncated to the length of the sequence, so the result may be shorter
+    /// than requested.
+    pub fn fetch_seq(&self, name: &[u8], begin: u32, end: u32) -> Result<Vec<u8>, FetchError> {
+        if begin >= end {
+            return Ok(Vec::new());
+        }
+        let cname = ffi::CString::new(name).unwrap();
+        let mut len = 0;
+        let seq = unsafe {
+            htslib::faidx_fetch_seq(
+                self.inner,
+                cname.as_ptr(),
+                begin as i32,
+                end as i32 - 1,
+                &mut len,
+            )
+        };
+        if seq.is_null() {
+            return Err(FetchError::UnknownSequence);
+        }
+        let result = if len > 0 {
+            unsafe { slice::from_raw_parts(seq as *const u8, len as usize) }.to_owned()
+        } else {
+            Vec::new()
+        };
+        unsafe { libc::free(seq as *mut libc::c_void) };
+        Ok(result)
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        unsafe {
+            htslib::fai_destroy(self.inner);
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ReaderError {
+        InvalidIndex {
+            description("could not load or build FASTA index")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ReaderPathError {
+        InvalidPath {
+            description("invalid path")
+        }
+        ReaderError(err: ReaderError) {
+            from()
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum FetchError {
+        UnknownSequence {
+            description("unknown sequence name")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_seq() {
+        let reader = Reader::from_path(&"test/test_gc.fa")
+            .ok()
+            .expect("Error opening file.");
+
+        assert_eq!(reader.n_seqs(), 1);
+        assert_eq!(reader.seq_names(), vec![b"CHROMOSOME_I".to_vec()]);
+        assert_eq!(reader.seq_len(b"CHROMOSOME_I"), Some(200));
+        assert_eq!(reader.seq_len(b"CHROMOSOME_II"), None);
+
+        assert_eq!(reader.fetch_seq(b"CHROMOSOME_I", 0, 4).unwrap(), b"GCGC");
+        assert_eq!(reader.fetch_seq(b"CHROMOSOME_I", 98, 102).unwrap(), b"GCAT");
+        assert_eq!(reader.fetch_seq(b"CHROMOSOME_I", 198, 300).unwrap(), b"AT");
+        assert!(reader.fetch_seq(b"CHROMOSOME_II", 0, 10).is_err());
+    }
+}
@@ -64,7 +64,7 @@
 //!
 //! // seek to chr1:50000-100000
 //! let tid = bam.header().tid(b"CHROMOSOME_I").unwrap();
-//! bam.fetch(tid, 0, 20).unwrap();
+//! bam.fetch((tid, 0, 20)).unwrap();
 //! // afterwards, read or pileup in this region
 //! ```
 
@@ -88,6 +88,12 @@ extern crate linear_map;
 #[cfg(feature = "serde")]
 extern crate serde;
 
+#[cfg(feature = "async")]
+#[macro_use]
+extern crate futures;
+#[cfg(feature = "async")]
+extern crate tokio_threadpool;
+
 #[cfg(all(test, feature = "serde"))]
 extern crate bincode;
 
@@ -99,8 +105,10 @@ extern crate serde_json;
 
 pub mod bam;
 pub mod bcf;
+pub mod faidx;
 pub mod htslib;
 pub mod prelude;
 pub mod sam;
 pub mod tbx;
+pub mod tpool;
 pub mod utils;
@@ -0,0 +1,215 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Non-blocking access to tabix-indexed files on a tokio thread pool (feature `async`).
+//!
+//! The blocking calls into htslib are run with `tokio_threadpool::blocking()`, such that the
+//! thread pool can move its other tasks to a new thread in the meantime.  Consequently, the
+//! futures and streams returned by `AsyncReader` have to be run on a tokio thread pool, e.g.,
+//! the default tokio runtime.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! extern crate futures;
+//! extern crate rust_htslib;
+//! extern crate tokio;
+//!
+//! use futures::Future;
+//! use rust_htslib::tbx;
+//!
+//! let reader = tbx::AsyncReader::new(tbx::Reader::from_path(&"genes.bed.gz").unwrap());
+//! let task = reader
+//!     .fetch_region("chr1:1,000-2,000")
+//!     .and_then(move |_| reader.records().collect())
+//!     .map(|records| println!("{} records", records.len()))
+//!     .map_err(|e| eprintln!("{}", e));
+//! tokio::run(task);
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use futures::{future, stream, Async, Future, Poll, Stream};
+use tokio_threadpool;
+
+use tbx::{FetchError, Read, ReadError, Reader};
+
+/// A tabix reader whose operations return futures instead of blocking.
+///
+/// The wrapped reader is shared with the returned futures and streams, the operations are
+/// executed in the order in which they are polled.  Hence, wait for `fetch()` to complete
+/// before reading the records of the region.
+#[derive(Debug)]
+pub struct AsyncReader {
+    inner: Arc<Mutex<Reader>>,
+}
+
+impl AsyncReader {
+    /// Create a new `AsyncReader` from a `Reader`.
+    pub fn new(reader: Reader) -> Self {
+        AsyncReader {
+            inner: Arc::new(Mutex::new(reader)),
+        }
+    }
+
+    /// Fetch region given by numeric sequence number and 0-based begin and end position,
+    /// see `Reader::fetch()`.
+    pub fn fetch(
+        &self,
+        tid: u32,
+        start: u32,
+        end: u32,
+    ) -> impl Future<Item = (), Error = AsyncError> + Send {
+        self.run(move |reader| Ok(try!(reader.fetch(tid, start, end))))
+    }
+
+    /// Fetch region given as a string, see `Reader::fetch_region()`.
+    pub fn fetch_region(&self, region: &str) -> impl Future<Item = (), Error = AsyncError> + Send {
+        let region = region.to_owned();
+        self.run(move |reader| Ok(try!(reader.fetch_region(&region))))
+    }
+
+    /// Read the next record of the fetched region, yielding `None` if there are no more records.
+    pub fn read(&self) -> impl Future<Item = Option<Vec<u8>>, Error = AsyncError> + Send {
+        self.run(read_record)
+    }
+
+    /// Return a stream over the records of the fetched region.
+    pub fn records(&self) -> impl Stream<Item = Vec<u8>, Error = AsyncError> + Send {
+        let reader = self.inner.clone();
+        let mut f = read_record;
+        stream::poll_fn(move || poll_blocking(&reader, &mut f))
+    }
+
+    /// Fetch the given region and read all of its records at once.
+    pub fn fetch_records(
+        &self,
+        tid: u32,
+        start: u32,
+        end: u32,
+    ) -> impl Future<Item = Vec<Vec<u8>>, Error = AsyncError> + Send {
+        self.run(move |reader| {
+            try!(reader.fetch(tid, start, end));
+            let mut records = Vec::new();
+            while let Some(record) = try!(read_record(reader)) {
+                records.push(record);
+            }
+            Ok(records)
+        })
+    }
+
+    /// Unwrap the `Reader`, fails and returns `self` if futures or streams created by this
+    /// reader are still alive.
+    pub fn into_inner(self) -> Result<Reader, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => Ok(inner.into_inner().expect("bug: reader lock poisoned")),
+            Err(inner) => Err(AsyncReader { inner: inner }),
+        }
+    }
+
+    /// Return a future running `f` on the reader in a blocking section.
+    fn run<T, F>(&self, mut f: F) -> impl Future<Item = T, Error = AsyncError> + Send
+    where
+        F: FnMut(&mut Reader) -> Result<T, AsyncError> + Send,
+    {
+        let reader = self.inner.clone();
+        future::poll_fn(move || poll_blocking(&reader, &mut f))
+    }
+}
+
+/// Run `f` on the reader in a blocking section of the tokio thread pool.
+fn poll_blocking<T, F>(reader: &Mutex<Reader>, f: &mut F) -> Poll<T, AsyncError>
+where
+    F: FnMut(&mut Reader) -> Result<T, AsyncError>,
+{
+    let result = try_ready!(
+        tokio_threadpool::blocking(|| f(&mut reader.lock().expect("bug: reader lock poisoned")))
+            .map_err(|_| AsyncError::NoThreadPool)
+    );
+    result.map(Async::Ready)
+}
+
+fn read_record(reader: &mut Reader) -> Result<Option<Vec<u8>>, AsyncError> {
+    let mut record = Vec::new();
+    match reader.read(&mut record) {
+        Ok(()) => Ok(Some(record)),
+        Err(ReadError::NoMoreRecord) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum AsyncError {
+        NoThreadPool {
+            description("future must be run on a tokio thread pool")
+        }
+        FetchError(err: FetchError) {
+            from()
+        }
+        ReadError(err: ReadError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_threadpool::ThreadPool;
+
+    #[test]
+    fn test_fetch_records() {
+        let reader = AsyncReader::new(
+            Reader::from_path("test/test_bed3.bed.gz")
+                .ok()
+                .expect("Error opening file."),
+        );
+        let pool = ThreadPool::new();
+
+        let records = pool.spawn_handle(reader.fetch_records(0, 1000, 1003)).wait().unwrap();
+        assert_eq!(records, vec![Vec::from("chr1\t1001\t1002")]);
+        let records = pool.spawn_handle(reader.fetch_records(0, 0, 2000)).wait().unwrap();
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn test_records() {
+        let reader = AsyncReader::new(
+            Reader::from_path("test/test_bed3.bed.gz")
+                .ok()
+                .expect("Error opening file."),
+        );
+        let pool = ThreadPool::new();
+
+        pool.spawn_handle(reader.fetch_region("chr1")).wait().unwrap();
+        let first = pool.spawn_handle(reader.read()).wait().unwrap();
+        assert_eq!(first, Some(Vec::from("chr1\t1001\t1002")));
+        let rest = pool.spawn_handle(reader.records().collect()).wait().unwrap();
+        assert_eq!(rest, vec![Vec::from("chr1\t1004\t1005")]);
+        assert_eq!(pool.spawn_handle(reader.read()).wait().unwrap(), None);
+
+        match pool.spawn_handle(reader.fetch_region("chr5")).wait() {
+            Err(AsyncError::FetchError(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        // the pool drops the completed futures, which share the reader, when shutting down
+        pool.shutdown_on_idle().wait().unwrap();
+        assert!(reader.into_inner().is_ok());
+    }
+
+    #[test]
+    fn test_no_thread_pool() {
+        let reader = AsyncReader::new(
+            Reader::from_path("test/test_bed3.bed.gz")
+                .ok()
+                .expect("Error opening file."),
+        );
+        match reader.fetch_region("chr1").wait() {
+            Err(AsyncError::NoThreadPool) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
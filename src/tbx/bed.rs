@@ -0,0 +1,221 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Typed access to the records of tabix-indexed BED files.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx::{self, bed};
+//!
+//! let mut reader = tbx::Reader::from_path(&"file.bed.gz").unwrap();
+//! reader.fetch_contig("chr1").unwrap();
+//! for record in bed::records(&mut reader) {
+//!     let record = record.unwrap();
+//!     println!("{}\t{}\t{}", record.chrom, record.start, record.end);
+//! }
+//! ```
+
+use std::str;
+
+use tbx::{self, Read, ReadError};
+
+/// Strand of a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+impl Strand {
+    /// Parse strand from `+`, `-`, or `.` (unknown, yielding `None`).
+    pub fn parse(value: &[u8]) -> Result<Option<Strand>, BedError> {
+        match value {
+            b"+" => Ok(Some(Strand::Forward)),
+            b"-" => Ok(Some(Strand::Reverse)),
+            b"." => Ok(None),
+            _ => Err(BedError::InvalidStrand),
+        }
+    }
+}
+
+/// A BED record with 3 to 6 standard columns, additional columns are kept in `rest`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BedRecord {
+    /// Name of the chromosome.
+    pub chrom: String,
+    /// 0-based start position.
+    pub start: u64,
+    /// 0-based, exclusive end position.
+    pub end: u64,
+    /// Name of the feature, if the column is present.
+    pub name: Option<String>,
+    /// Score of the feature, if the column is present and not `.`.
+    pub score: Option<f64>,
+    /// Strand of the feature, if the column is present and not `.`.
+    pub strand: Option<Strand>,
+    /// The remaining columns.
+    pub rest: Vec<String>,
+}
+
+impl BedRecord {
+    /// Parse a BED record from a line, without trailing newline.
+    pub fn parse(line: &[u8]) -> Result<Self, BedError> {
+        let mut fields = line.split(|&c| c == b'\t');
+        let chrom = try!(text(try!(fields.next().ok_or(BedError::MissingColumn))));
+        let start = try!(position(try!(fields.next().ok_or(BedError::MissingColumn))));
+        let end = try!(position(try!(fields.next().ok_or(BedError::MissingColumn))));
+        if end < start {
+            return Err(BedError::InvalidPosition);
+        }
+        let name = match fields.next() {
+            Some(field) => Some(try!(text(field))),
+            None => None,
+        };
+        let score = match fields.next() {
+            Some(b".") | None => None,
+            Some(field) => Some(try!(score(field))),
+        };
+        let strand = match fields.next() {
+            Some(field) => try!(Strand::parse(field)),
+            None => None,
+        };
+        let mut rest = Vec::new();
+        for field in fields {
+            rest.push(try!(text(field)));
+        }
+
+        Ok(BedRecord {
+            chrom: chrom,
+            start: start,
+            end: end,
+            name: name,
+            score: score,
+            strand: strand,
+            rest: rest,
+        })
+    }
+
+    /// Length of the feature.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether the feature is empty (e.g., an insertion point).
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+fn text(field: &[u8]) -> Result<String, BedError> {
+    str::from_utf8(field)
+        .map(|s| s.to_owned())
+        .map_err(|_| BedError::InvalidUtf8)
+}
+
+fn position(field: &[u8]) -> Result<u64, BedError> {
+    str::from_utf8(field)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or(BedError::InvalidPosition)
+}
+
+fn score(field: &[u8]) -> Result<f64, BedError> {
+    str::from_utf8(field)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .ok_or(BedError::InvalidScore)
+}
+
+/// Iterate over the BED records of the region fetched in `reader`.
+pub fn records<'a, R: Read>(reader: &'a mut R) -> Records<'a, R> {
+    Records {
+        inner: reader.records(),
+    }
+}
+
+/// Iterator over BED records, see `records()`.
+#[derive(Debug)]
+pub struct Records<'a, R: 'a + Read> {
+    inner: tbx::Records<'a, R>,
+}
+
+impl<'a, R: Read> Iterator for Records<'a, R> {
+    type Item = Result<BedRecord, BedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|line| match line {
+            Ok(line) => BedRecord::parse(&line),
+            Err(e) => Err(BedError::ReadError(e)),
+        })
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum BedError {
+        MissingColumn {
+            description("BED record has less than three columns")
+        }
+        InvalidUtf8 {
+            description("BED record is not valid UTF-8")
+        }
+        InvalidPosition {
+            description("invalid start or end position in BED record")
+        }
+        InvalidScore {
+            description("invalid score in BED record")
+        }
+        InvalidStrand {
+            description("invalid strand in BED record")
+        }
+        ReadError(err: ReadError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tbx::Reader;
+
+    #[test]
+    fn test_parse() {
+        let record = BedRecord::parse(b"chr1\t10\t20\tfoo\t0.5\t-\tbar\tbaz").unwrap();
+        assert_eq!(record.chrom, "chr1");
+        assert_eq!(record.start, 10);
+        assert_eq!(record.end, 20);
+        assert_eq!(record.len(), 10);
+        assert_eq!(record.name, Some(String::from("foo")));
+        assert_eq!(record.score, Some(0.5));
+        assert_eq!(record.strand, Some(Strand::Reverse));
+        assert_eq!(record.rest, vec![String::from("bar"), String::from("baz")]);
+
+        let record = BedRecord::parse(b"chr1\t10\t20\tfoo\t.\t.").unwrap();
+        assert_eq!(record.score, None);
+        assert_eq!(record.strand, None);
+
+        assert!(BedRecord::parse(b"chr1\t10").is_err());
+        assert!(BedRecord::parse(b"chr1\tfoo\t20").is_err());
+        assert!(BedRecord::parse(b"chr1\t20\t10").is_err());
+        assert!(BedRecord::parse(b"chr1\t10\t20\tfoo\t1\t*").is_err());
+    }
+
+    #[test]
+    fn test_records() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        reader.fetch_contig("chr1").unwrap();
+
+        let records: Vec<BedRecord> = records(&mut reader).map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].chrom, "chr1");
+        assert_eq!(records[1].start, 1004);
+        assert_eq!(records[1].end, 1005);
+        assert_eq!(records[1].name, None);
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Typed access to the records of tabix-indexed GFF3 and GTF files.
+//!
+//! Attributes are parsed from both the GFF3 syntax (`ID=gene1;Parent=a,b`, percent-encoded)
+//! and the GTF syntax (`gene_id "g1"; tag "basic"; tag "CCDS";`).  Each attribute maps to the
+//! list of its values: multiple values are separated by commas in GFF3 and given by repeating
+//! the attribute in GTF.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx::{self, gff};
+//!
+//! let mut reader = tbx::Reader::from_path(&"genes.gff3.gz").unwrap();
+//! reader.fetch_region("chr1:1,000,000-2,000,000").unwrap();
+//! for record in gff::records(&mut reader) {
+//!     let record = record.unwrap();
+//!     if record.feature_type == "gene" {
+//!         println!("{:?}", record.attribute("ID"));
+//!     }
+//! }
+//! ```
+
+use std::str;
+
+use linear_map::LinearMap;
+use url::percent_encoding::percent_decode;
+
+use tbx::bed::Strand;
+use tbx::{self, Read, ReadError};
+
+/// A GFF3 or GTF record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GffRecord {
+    /// Name of the sequence the feature is located on.
+    pub seqid: String,
+    /// Source of the feature, e.g., the program that generated it.
+    pub source: String,
+    /// Type of the feature, e.g., `gene` or `exon`.
+    pub feature_type: String,
+    /// 1-based start position.
+    pub start: u64,
+    /// 1-based, inclusive end position.
+    pub end: u64,
+    /// Score of the feature, `None` for `.`.
+    pub score: Option<f64>,
+    /// Strand of the feature, `None` for `.` (not stranded) and `?` (unknown).
+    pub strand: Option<Strand>,
+    /// Frame (phase) of CDS features, `None` for `.`.
+    pub frame: Option<u8>,
+    /// The attributes and their values.
+    pub attributes: LinearMap<String, Vec<String>>,
+}
+
+impl GffRecord {
+    /// Parse a GFF3 or GTF record from a line, without trailing newline.
+    pub fn parse(line: &[u8]) -> Result<Self, GffError> {
+        let fields: Vec<&[u8]> = line.split(|&c| c == b'\t').collect();
+        if fields.len() < 8 {
+            return Err(GffError::MissingColumn);
+        }
+        let start = try!(number::<u64>(fields[3]).ok_or(GffError::InvalidPosition));
+        let end = try!(number::<u64>(fields[4]).ok_or(GffError::InvalidPosition));
+        if start == 0 || end + 1 < start {
+            return Err(GffError::InvalidPosition);
+        }
+        let score = match fields[5] {
+            b"." => None,
+            field => Some(try!(number::<f64>(field).ok_or(GffError::InvalidScore))),
+        };
+        let strand = match fields[6] {
+            b"+" => Some(Strand::Forward),
+            b"-" => Some(Strand::Reverse),
+            b"." | b"?" => None,
+            _ => return Err(GffError::InvalidStrand),
+        };
+        let frame = match fields[7] {
+            b"." => None,
+            b"0" => Some(0),
+            b"1" => Some(1),
+            b"2" => Some(2),
+            _ => return Err(GffError::InvalidFrame),
+        };
+        let attributes = match fields.get(8) {
+            Some(field) => try!(parse_attributes(field)),
+            None => LinearMap::new(),
+        };
+
+        Ok(GffRecord {
+            seqid: try!(text(fields[0])),
+            source: try!(text(fields[1])),
+            feature_type: try!(text(fields[2])),
+            start: start,
+            end: end,
+            score: score,
+            strand: strand,
+            frame: frame,
+            attributes: attributes,
+        })
+    }
+
+    /// Return the (first) value of the given attribute, if present.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .get(key)
+            .and_then(|values| values.first())
+            .map(|value| value.as_str())
+    }
+}
+
+/// Parse attributes in GFF3 or GTF syntax.
+fn parse_attributes(field: &[u8]) -> Result<LinearMap<String, Vec<String>>, GffError> {
+    let mut attributes: LinearMap<String, Vec<String>> = LinearMap::new();
+    if field == b"." {
+        return Ok(attributes);
+    }
+    let field = try!(str::from_utf8(field).map_err(|_| GffError::InvalidUtf8));
+    for attribute in field.split(';').map(|a| a.trim()).filter(|a| !a.is_empty()) {
+        if let Some(pos) = attribute.find('=') {
+            // GFF3: key=value1,value2 with percent-encoding
+            let key = try!(decode(&attribute[..pos]));
+            let values = attributes.entry(key).or_insert_with(Vec::new);
+            for value in attribute[pos + 1..].split(',') {
+                values.push(try!(decode(value)));
+            }
+        } else if let Some(pos) = attribute.find(char::is_whitespace) {
+            // GTF: key "value"
+            let value = attribute[pos..].trim().trim_matches('"');
+            attributes
+                .entry(attribute[..pos].to_owned())
+                .or_insert_with(Vec::new)
+                .push(value.to_owned());
+        } else {
+            return Err(GffError::InvalidAttribute);
+        }
+    }
+    Ok(attributes)
+}
+
+fn decode(value: &str) -> Result<String, GffError> {
+    percent_decode(value.as_bytes())
+        .decode_utf8()
+        .map(|s| s.into_owned())
+        .map_err(|_| GffError::InvalidUtf8)
+}
+
+fn text(field: &[u8]) -> Result<String, GffError> {
+    str::from_utf8(field)
+        .map(|s| s.to_owned())
+        .map_err(|_| GffError::InvalidUtf8)
+}
+
+fn number<T: str::FromStr>(field: &[u8]) -> Option<T> {
+    str::from_utf8(field).ok().and_then(|s| s.parse::<T>().ok())
+}
+
+/// Iterate over the GFF records of the region fetched in `reader`.
+pub fn records<'a, R: Read>(reader: &'a mut R) -> Records<'a, R> {
+    Records {
+        inner: reader.records(),
+    }
+}
+
+/// Iterator over GFF records, see `records()`.
+#[derive(Debug)]
+pub struct Records<'a, R: 'a + Read> {
+    inner: tbx::Records<'a, R>,
+}
+
+impl<'a, R: Read> Iterator for Records<'a, R> {
+    type Item = Result<GffRecord, GffError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|line| match line {
+            Ok(line) => GffRecord::parse(&line),
+            Err(e) => Err(GffError::ReadError(e)),
+        })
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum GffError {
+        MissingColumn {
+            description("GFF record has less than eight columns")
+        }
+        InvalidUtf8 {
+            description("GFF record is not valid UTF-8")
+        }
+        InvalidPosition {
+            description("invalid start or end position in GFF record")
+        }
+        InvalidScore {
+            description("invalid score in GFF record")
+        }
+        InvalidStrand {
+            description("invalid strand in GFF record")
+        }
+        InvalidFrame {
+            description("invalid frame in GFF record")
+        }
+        InvalidAttribute {
+            description("invalid attribute in GFF record")
+        }
+        ReadError(err: ReadError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gff3() {
+        let record = GffRecord::parse(
+            b"chr1\tsrc\tgene\t1001\t2000\t.\t+\t.\tID=gene1;Name=A%3BB;Parent=a,b",
+        ).unwrap();
+        assert_eq!(record.seqid, "chr1");
+        assert_eq!(record.source, "src");
+        assert_eq!(record.feature_type, "gene");
+        assert_eq!(record.start, 1001);
+        assert_eq!(record.end, 2000);
+        assert_eq!(record.score, None);
+        assert_eq!(record.strand, Some(Strand::Forward));
+        assert_eq!(record.frame, None);
+        assert_eq!(record.attribute("ID"), Some("gene1"));
+        assert_eq!(record.attribute("Name"), Some("A;B"));
+        assert_eq!(
+            record.attributes.get("Parent"),
+            Some(&vec![String::from("a"), String::from("b")])
+        );
+        assert_eq!(record.attribute("foo"), None);
+    }
+
+    #[test]
+    fn test_parse_gtf() {
+        let record = GffRecord::parse(
+            b"chr1\tsrc\tCDS\t1001\t1100\t0.5\t-\t2\tgene_id \"g1\"; tag \"basic\"; tag \"CCDS\";",
+        ).unwrap();
+        assert_eq!(record.score, Some(0.5));
+        assert_eq!(record.strand, Some(Strand::Reverse));
+        assert_eq!(record.frame, Some(2));
+        assert_eq!(record.attribute("gene_id"), Some("g1"));
+        assert_eq!(
+            record.attributes.get("tag"),
+            Some(&vec![String::from("basic"), String::from("CCDS")])
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(GffRecord::parse(b"chr1\tsrc\tgene\t1001\t2000").is_err());
+        assert!(GffRecord::parse(b"chr1\tsrc\tgene\t0\t2000\t.\t+\t.\t.").is_err());
+        assert!(GffRecord::parse(b"chr1\tsrc\tgene\t1\t2000\t.\t*\t.\t.").is_err());
+        assert!(GffRecord::parse(b"chr1\tsrc\tgene\t1\t2000\t.\t+\t3\t.").is_err());
+        assert!(GffRecord::parse(b"chr1\tsrc\tgene\t1\t2000\t.\t+\t.\tfoo").is_err());
+    }
+}
@@ -0,0 +1,412 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Building tabix indices for BGZF-compressed text files.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx::index::{self, TabixConfig};
+//!
+//! // Equivalent to `tabix -p bed file.bed.gz`, writes `file.bed.gz.tbi`.
+//! index::build(&"file.bed.gz", &TabixConfig::bed()).unwrap();
+//! ```
+
+use std::ffi;
+use std::path::Path;
+
+use htslib;
+
+/// Format of the records in a tabix-indexed file, determines how the positions are parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Generic tab-delimited records with sequence, begin and (optionally) end column.
+    Generic,
+    /// SAM records, the end position is computed from the CIGAR string.
+    Sam,
+    /// VCF records, the end position is computed from the reference allele and `INFO/END`.
+    Vcf,
+}
+
+/// Configuration of a tabix index, i.e., how the positions are parsed from the records.
+///
+/// The presets correspond to the ones of `tabix -p`.  Custom column layouts can be configured
+/// with the setters, which can be chained:
+///
+/// ```
+/// use rust_htslib::tbx::TabixConfig;
+///
+/// let mut conf = TabixConfig::new();
+/// conf.set_seq_col(2)
+///     .set_begin_col(4)
+///     .set_end_col(Some(5))
+///     .set_meta_char(b'%');
+/// assert_eq!(conf.begin_col(), 4);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TabixConfig {
+    inner: htslib::tbx_conf_t,
+}
+
+impl TabixConfig {
+    /// Create a new configuration with the defaults of `tabix`, which equal the GFF preset.
+    pub fn new() -> Self {
+        Self::gff()
+    }
+
+    /// Preset for GFF/GTF files.
+    pub fn gff() -> Self {
+        Self::from_raw(htslib::TBX_GENERIC as i32, 1, 4, 5, b'#', 0)
+    }
+
+    /// Preset for BED files (0-based start positions).
+    pub fn bed() -> Self {
+        Self::from_raw(htslib::TBX_UCSC as i32, 1, 2, 3, b'#', 0)
+    }
+
+    /// Preset for PSL files with a leading bin column.
+    pub fn psltbl() -> Self {
+        Self::from_raw(htslib::TBX_UCSC as i32, 15, 17, 18, b'#', 0)
+    }
+
+    /// Preset for SAM files.
+    pub fn sam() -> Self {
+        Self::from_raw(htslib::TBX_SAM as i32, 3, 4, 0, b'@', 0)
+    }
+
+    /// Preset for VCF files.
+    pub fn vcf() -> Self {
+        Self::from_raw(htslib::TBX_VCF as i32, 1, 2, 0, b'#', 0)
+    }
+
+    /// Return the preset with the given name, as accepted by `tabix -p` (`gff`, `bed`, `sam`,
+    /// `vcf`, or `psltbl`).
+    pub fn from_preset(name: &str) -> Option<Self> {
+        match name {
+            "gff" => Some(Self::gff()),
+            "bed" => Some(Self::bed()),
+            "psltbl" => Some(Self::psltbl()),
+            "sam" => Some(Self::sam()),
+            "vcf" => Some(Self::vcf()),
+            _ => None,
+        }
+    }
+
+    /// Return the name of the preset this configuration is equal to, if any.
+    pub fn preset(&self) -> Option<&'static str> {
+        ["gff", "bed", "psltbl", "sam", "vcf"]
+            .iter()
+            .find(|name| Self::from_preset(name).unwrap() == *self)
+            .map(|name| *name)
+    }
+
+    /// Format of the records.
+    pub fn format(&self) -> Format {
+        match self.inner.preset & 0xffff {
+            p if p == htslib::TBX_SAM as i32 => Format::Sam,
+            p if p == htslib::TBX_VCF as i32 => Format::Vcf,
+            _ => Format::Generic,
+        }
+    }
+
+    /// Whether begin positions are 0-based (as in BED files) instead of 1-based.
+    pub fn is_zero_based(&self) -> bool {
+        self.inner.preset & htslib::TBX_UCSC as i32 != 0
+    }
+
+    /// 1-based index of the column containing the sequence name.
+    pub fn seq_col(&self) -> u32 {
+        self.inner.sc as u32
+    }
+
+    /// 1-based index of the column containing the begin position.
+    pub fn begin_col(&self) -> u32 {
+        self.inner.bc as u32
+    }
+
+    /// 1-based index of the column containing the end position, if any.
+    pub fn end_col(&self) -> Option<u32> {
+        if self.inner.ec > 0 {
+            Some(self.inner.ec as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Character starting header and comment lines.
+    pub fn meta_char(&self) -> u8 {
+        self.inner.meta_char as u8
+    }
+
+    /// Number of lines skipped at the beginning of the file.
+    pub fn line_skip(&self) -> u32 {
+        self.inner.line_skip as u32
+    }
+
+    /// Set the format of the records.
+    pub fn set_format(&mut self, format: Format) -> &mut Self {
+        let preset = match format {
+            Format::Generic => htslib::TBX_GENERIC,
+            Format::Sam => htslib::TBX_SAM,
+            Format::Vcf => htslib::TBX_VCF,
+        };
+        self.inner.preset = (self.inner.preset & htslib::TBX_UCSC as i32) | preset as i32;
+        self
+    }
+
+    /// Set whether begin positions are 0-based (as in BED files) instead of 1-based.
+    pub fn set_zero_based(&mut self, zero_based: bool) -> &mut Self {
+        if zero_based {
+            self.inner.preset |= htslib::TBX_UCSC as i32;
+        } else {
+            self.inner.preset &= !(htslib::TBX_UCSC as i32);
+        }
+        self
+    }
+
+    /// Set the 1-based index of the column containing the sequence name.
+    pub fn set_seq_col(&mut self, col: u32) -> &mut Self {
+        assert!(col > 0, "columns are 1-based");
+        self.inner.sc = col as i32;
+        self
+    }
+
+    /// Set the 1-based index of the column containing the begin position.
+    pub fn set_begin_col(&mut self, col: u32) -> &mut Self {
+        assert!(col > 0, "columns are 1-based");
+        self.inner.bc = col as i32;
+        self
+    }
+
+    /// Set the 1-based index of the column containing the end position, or `None` if the
+    /// records have no end column (the end position then equals the begin position).
+    pub fn set_end_col(&mut self, col: Option<u32>) -> &mut Self {
+        assert!(col != Some(0), "columns are 1-based");
+        self.inner.ec = col.unwrap_or(0) as i32;
+        self
+    }
+
+    /// Set the character starting header and comment lines.
+    pub fn set_meta_char(&mut self, meta_char: u8) -> &mut Self {
+        self.inner.meta_char = meta_char as i32;
+        self
+    }
+
+    /// Set the number of lines to skip at the beginning of the file.
+    pub fn set_line_skip(&mut self, line_skip: u32) -> &mut Self {
+        self.inner.line_skip = line_skip as i32;
+        self
+    }
+
+    pub(crate) fn from_inner(inner: htslib::tbx_conf_t) -> Self {
+        TabixConfig { inner: inner }
+    }
+
+    fn from_raw(preset: i32, sc: i32, bc: i32, ec: i32, meta_char: u8, line_skip: i32) -> Self {
+        TabixConfig {
+            inner: htslib::tbx_conf_t {
+                preset: preset,
+                sc: sc,
+                bc: bc,
+                ec: ec,
+                meta_char: meta_char as i32,
+                line_skip: line_skip,
+            },
+        }
+    }
+
+    pub(crate) fn inner(&self) -> &htslib::tbx_conf_t {
+        &self.inner
+    }
+}
+
+impl Default for TabixConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for TabixConfig {
+    fn eq(&self, other: &TabixConfig) -> bool {
+        let (a, b) = (&self.inner, &other.inner);
+        a.preset == b.preset && a.sc == b.sc && a.bc == b.bc && a.ec == b.ec
+            && a.meta_char == b.meta_char && a.line_skip == b.line_skip
+    }
+}
+
+impl Eq for TabixConfig {}
+
+/// Build a tabix index (`.tbi`) for the BGZF-compressed file at `path`.
+///
+/// The index is written next to the file, with the suffix `.tbi` appended.
+///
+/// # Arguments
+///
+/// * `path` - the path of the BGZF-compressed file
+/// * `conf` - the configuration to use for parsing the records
+pub fn build<P: AsRef<Path>>(path: P, conf: &TabixConfig) -> Result<(), BuildError> {
+    let path = match path.as_ref().to_str() {
+        Some(p) => ffi::CString::new(p).unwrap(),
+        None => return Err(BuildError::InvalidPath),
+    };
+    // check the compression first, as older versions of htslib do not report it separately
+    let fp = unsafe { htslib::bgzf_open(path.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+    if !fp.is_null() {
+        let compression = unsafe {
+            let compression = htslib::bgzf_compression(fp);
+            htslib::bgzf_close(fp);
+            compression
+        };
+        if compression != htslib::htsCompression_bgzf as i32 {
+            return Err(BuildError::NotBgzf);
+        }
+    }
+    let ret = unsafe { htslib::tbx_index_build(path.as_ptr(), 0, conf.inner()) };
+    match ret {
+        0 => Ok(()),
+        -2 => Err(BuildError::NotBgzf),
+        _ => Err(BuildError::Some),
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum BuildError {
+        InvalidPath {
+            description("invalid path")
+        }
+        NotBgzf {
+            description("file is not BGZF-compressed")
+        }
+        Some {
+            description("error building tabix index")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use super::*;
+    use std::fs;
+    use tbx::{Read, Reader, Writer};
+
+    #[test]
+    fn test_build() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+        fs::copy("test/test_bed3.bed.gz", &path).unwrap();
+
+        build(&path, &TabixConfig::bed()).unwrap();
+        assert!(tmp.path().join("test.bed.gz.tbi").exists());
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        let chr1_id = reader.tid("chr1").unwrap();
+        assert!(reader.fetch(chr1_id, 1000, 1003).is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr1\t1001\t1002")]);
+    }
+
+    #[test]
+    fn test_config() {
+        let bed = TabixConfig::bed();
+        assert_eq!(bed.preset(), Some("bed"));
+        assert_eq!(bed.format(), Format::Generic);
+        assert!(bed.is_zero_based());
+        assert_eq!(bed.seq_col(), 1);
+        assert_eq!(bed.begin_col(), 2);
+        assert_eq!(bed.end_col(), Some(3));
+        assert_eq!(bed.meta_char(), b'#');
+        assert_eq!(bed.line_skip(), 0);
+
+        let vcf = TabixConfig::from_preset("vcf").unwrap();
+        assert_eq!(vcf.preset(), Some("vcf"));
+        assert_eq!(vcf.format(), Format::Vcf);
+        assert!(!vcf.is_zero_based());
+        assert_eq!(vcf.end_col(), None);
+        assert_eq!(TabixConfig::sam().format(), Format::Sam);
+        assert!(TabixConfig::from_preset("foo").is_none());
+    }
+
+    #[test]
+    fn test_config_setters() {
+        let mut conf = TabixConfig::new();
+        assert_eq!(conf.preset(), Some("gff"));
+        conf.set_seq_col(2)
+            .set_begin_col(4)
+            .set_end_col(Some(5))
+            .set_meta_char(b'%')
+            .set_line_skip(1)
+            .set_zero_based(true);
+        assert_eq!(conf.preset(), None);
+        assert_eq!(conf.format(), Format::Generic);
+        assert!(conf.is_zero_based());
+        assert_eq!(conf.seq_col(), 2);
+        assert_eq!(conf.begin_col(), 4);
+        assert_eq!(conf.end_col(), Some(5));
+        assert_eq!(conf.meta_char(), b'%');
+        assert_eq!(conf.line_skip(), 1);
+
+        conf.set_format(Format::Vcf).set_end_col(None);
+        assert_eq!(conf.format(), Format::Vcf);
+        assert!(conf.is_zero_based());
+        assert_eq!(conf.end_col(), None);
+
+        let mut conf = TabixConfig::bed();
+        conf.set_zero_based(false);
+        assert!(!conf.is_zero_based());
+    }
+
+    #[test]
+    fn test_build_custom_layout() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.txt.gz");
+        let mut conf = TabixConfig::new();
+        conf.set_seq_col(2)
+            .set_begin_col(4)
+            .set_end_col(Some(5))
+            .set_meta_char(b'%');
+        {
+            let mut writer = Writer::from_path(&path, &conf)
+                .ok()
+                .expect("Error opening file.");
+            writer.write(b"%name\tchrom\tx\tstart\tend").unwrap();
+            writer.write(b"a\tchr1\tx\t100\t200").unwrap();
+            writer.write(b"b\tchr1\tx\t300\t400").unwrap();
+            writer.close().unwrap();
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.conf(), conf);
+        assert_eq!(reader.header().len(), 1);
+        // positions are 1-based and inclusive
+        assert!(reader.fetch_region("chr1:200-300").is_ok());
+        assert_eq!(reader.records().count(), 2);
+        assert!(reader.fetch_region("chr1:201-299").is_ok());
+        assert_eq!(reader.records().count(), 0);
+    }
+
+    #[test]
+    fn test_build_uncompressed() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed");
+        fs::copy("test/test_bed3.bed", &path).unwrap();
+
+        match build(&path, &TabixConfig::bed()) {
+            Err(BuildError::NotBgzf) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streaming intersection of two tabix-indexed files, similar to `bedtools intersect -sorted`.
+//!
+//! The records of both files are swept contig by contig in the order of the contigs in the
+//! index of the first file.  Records from the second file that cannot overlap any of the
+//! remaining records of the first file are skipped with the index where possible.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use tbx::{FetchError, Read, ReadError, Reader};
+
+/// If the next record of `a` starts this many bases after the last record read from `b`,
+/// the records of `b` in between are skipped using the index.
+const SKIP_DISTANCE: i64 = 65_536;
+
+/// Interval and line of a record from the second file.
+#[derive(Debug)]
+struct Interval {
+    start: i64,
+    end: i64,
+    line: Vec<u8>,
+}
+
+/// Sweep over two tabix files, yielding each record of the first file together with the
+/// overlapping records of the second file.
+#[derive(Debug)]
+struct Sweep<'a> {
+    a: &'a mut Reader,
+    b: &'a mut Reader,
+    /// Contig names of `a`.
+    contigs: Vec<String>,
+    /// Index of the next contig in `contigs` to fetch.
+    next_contig: usize,
+    /// Whether a contig has been fetched in `a`.
+    in_contig: bool,
+    /// Whether there are no more records in `b` on the current contig.
+    b_done: bool,
+    /// ID of the current contig in `b`.
+    b_tid: u32,
+    /// Records from `b` that may overlap the following records from `a`.
+    active: VecDeque<Interval>,
+    /// Next record from `b`, not yet in `active`.
+    b_next: Option<Interval>,
+    /// Start position of the last record read from `b` and number of such records.
+    b_last_start: i64,
+    b_last_count: usize,
+    /// After skipping in `b`, records with smaller start positions and the given number of
+    /// records with the same start position have been seen already.
+    b_skip_start: i64,
+    b_skip_count: usize,
+}
+
+impl<'a> Sweep<'a> {
+    fn new(a: &'a mut Reader, b: &'a mut Reader) -> Self {
+        let contigs = a.seqnames();
+        Sweep {
+            a: a,
+            b: b,
+            contigs: contigs,
+            next_contig: 0,
+            in_contig: false,
+            b_done: true,
+            b_tid: 0,
+            active: VecDeque::new(),
+            b_next: None,
+            b_last_start: -1,
+            b_last_count: 0,
+            b_skip_start: -1,
+            b_skip_count: 0,
+        }
+    }
+
+    /// Fetch the next contig in both files, return `false` if there is none.
+    fn next_contig(&mut self) -> Result<bool, IntersectError> {
+        if self.next_contig >= self.contigs.len() {
+            return Ok(false);
+        }
+        let name = self.contigs[self.next_contig].clone();
+        self.next_contig += 1;
+
+        let tid = self.a.tid(&name).unwrap();
+        try!(self.a.fetch(tid, 0, i32::max_value() as u32));
+        self.in_contig = true;
+
+        self.active.clear();
+        self.b_next = None;
+        self.b_last_start = -1;
+        self.b_last_count = 0;
+        self.b_skip_start = -1;
+        self.b_skip_count = 0;
+        self.b_done = match self.b.tid(&name) {
+            Ok(tid) => {
+                try!(self.b.fetch(tid, 0, i32::max_value() as u32));
+                self.b_tid = tid;
+                false
+            }
+            Err(_) => true,
+        };
+        Ok(true)
+    }
+
+    /// Read next record from `b`, ignoring records that have been seen before skipping.
+    fn read_b(&mut self) -> Result<Option<Interval>, IntersectError> {
+        loop {
+            let mut line = Vec::new();
+            match self.b.read(&mut line) {
+                Err(ReadError::NoMoreRecord) => return Ok(None),
+                Err(e) => return Err(IntersectError::ReadError(e)),
+                Ok(()) => (),
+            }
+            let (_, start, end) = self.b.current_interval().unwrap();
+            if start < self.b_skip_start {
+                continue;
+            } else if start == self.b_skip_start && self.b_skip_count > 0 {
+                self.b_skip_count -= 1;
+                continue;
+            }
+            if start == self.b_last_start {
+                self.b_last_count += 1;
+            } else {
+                self.b_last_start = start;
+                self.b_last_count = 1;
+            }
+            return Ok(Some(Interval {
+                start: start,
+                end: end,
+                line: line,
+            }));
+        }
+    }
+
+    /// Skip records in `b` that end before `start` using the index.
+    fn skip_b(&mut self, start: i64) -> Result<(), IntersectError> {
+        if let Some(next) = self.b_next.take() {
+            if next.end > start {
+                self.active.push_back(next);
+            }
+        }
+        let tid = self.b_tid;
+        try!(self.b.fetch(tid, start as u32, i32::max_value() as u32));
+        self.b_skip_start = self.b_last_start;
+        self.b_skip_count = self.b_last_count;
+        Ok(())
+    }
+
+    /// Return next record from `a` and the overlapping records from `b`.
+    fn next_group(&mut self) -> Result<Option<(Vec<u8>, Vec<Vec<u8>>)>, IntersectError> {
+        let mut line = Vec::new();
+        loop {
+            if !self.in_contig && !try!(self.next_contig()) {
+                return Ok(None);
+            }
+            match self.a.read(&mut line) {
+                Ok(()) => break,
+                Err(ReadError::NoMoreRecord) => self.in_contig = false,
+                Err(e) => return Err(IntersectError::ReadError(e)),
+            }
+        }
+        let (_, start, end) = self.a.current_interval().unwrap();
+
+        if !self.b_done {
+            let behind = match self.b_next {
+                Some(ref next) => next.start < start,
+                None => true,
+            };
+            if behind && self.b_last_start >= 0 && start - self.b_last_start > SKIP_DISTANCE {
+                try!(self.skip_b(start));
+            }
+        }
+
+        // remove records from `b` that cannot overlap any more records of `a`
+        self.active.retain(|interval| interval.end > start);
+        // add records from `b` starting before the end of the record from `a`
+        while !self.b_done {
+            if self.b_next.is_none() {
+                self.b_next = try!(self.read_b());
+                if self.b_next.is_none() {
+                    self.b_done = true;
+                    break;
+                }
+            }
+            if self.b_next.as_ref().unwrap().start >= end {
+                break;
+            }
+            let next = self.b_next.take().unwrap();
+            if next.end > start {
+                self.active.push_back(next);
+            }
+        }
+
+        let overlapping = self.active
+            .iter()
+            .filter(|interval| interval.start < end)
+            .map(|interval| interval.line.clone())
+            .collect();
+        Ok(Some((line, overlapping)))
+    }
+}
+
+/// Compute the intersection of two tabix-indexed files.
+///
+/// The returned iterator yields all pairs of overlapping records, the record from `a` first.
+/// Both readers are re-fetched while iterating.
+///
+/// # Arguments
+///
+/// * `a` - reader for the first file, contigs are processed in the order of its index
+/// * `b` - reader for the second file
+pub fn intersect<'a>(a: &'a mut Reader, b: &'a mut Reader) -> Intersect<'a> {
+    Intersect {
+        sweep: Sweep::new(a, b),
+        record: Vec::new(),
+        overlapping: Vec::new(),
+    }
+}
+
+/// Annotate the records of a tabix-indexed file with the number of overlapping records
+/// in a second file.
+///
+/// The returned iterator yields each record from `a` (including the ones without overlaps)
+/// together with the number of overlapping records from `b`.
+///
+/// # Arguments
+///
+/// * `a` - reader for the first file, contigs are processed in the order of its index
+/// * `b` - reader for the second file
+pub fn intersect_count<'a>(a: &'a mut Reader, b: &'a mut Reader) -> IntersectCount<'a> {
+    IntersectCount {
+        sweep: Sweep::new(a, b),
+    }
+}
+
+/// Iterator over pairs of overlapping records, see `intersect()`.
+#[derive(Debug)]
+pub struct Intersect<'a> {
+    sweep: Sweep<'a>,
+    /// The current record from `a`.
+    record: Vec<u8>,
+    /// The remaining records from `b` overlapping `record`, in reverse order.
+    overlapping: Vec<Vec<u8>>,
+}
+
+impl<'a> Iterator for Intersect<'a> {
+    type Item = Result<(Vec<u8>, Vec<u8>), IntersectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.overlapping.is_empty() {
+            match self.sweep.next_group() {
+                Ok(Some((record, mut overlapping))) => {
+                    overlapping.reverse();
+                    self.record = record;
+                    self.overlapping = overlapping;
+                }
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let other = self.overlapping.pop().unwrap();
+        let record = if self.overlapping.is_empty() {
+            mem::replace(&mut self.record, Vec::new())
+        } else {
+            self.record.clone()
+        };
+        Some(Ok((record, other)))
+    }
+}
+
+/// Iterator over records with overlap counts, see `intersect_count()`.
+#[derive(Debug)]
+pub struct IntersectCount<'a> {
+    sweep: Sweep<'a>,
+}
+
+impl<'a> Iterator for IntersectCount<'a> {
+    type Item = Result<(Vec<u8>, usize), IntersectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.sweep.next_group() {
+            Ok(Some((record, overlapping))) => Some(Ok((record, overlapping.len()))),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum IntersectError {
+        ReadError(err: ReadError) {
+            from()
+        }
+        FetchError(err: FetchError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let mut a = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        let mut b = Reader::from_path("test/test_bed3_b.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> =
+            intersect(&mut a, &mut b).map(|r| r.unwrap()).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (Vec::from("chr1\t1001\t1002"), Vec::from("chr1\t1000\t1002")),
+                (Vec::from("chr1\t1001\t1002"), Vec::from("chr1\t1001\t1005")),
+                (Vec::from("chr1\t1004\t1005"), Vec::from("chr1\t1001\t1005")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_intersect_count() {
+        let mut a = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        let mut b = Reader::from_path("test/test_bed3_b.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        let counts: Vec<(Vec<u8>, usize)> =
+            intersect_count(&mut a, &mut b).map(|r| r.unwrap()).collect();
+        assert_eq!(
+            counts,
+            vec![
+                (Vec::from("chr1\t1001\t1002"), 2),
+                (Vec::from("chr1\t1004\t1005"), 1),
+                (Vec::from("chr2\t1005\t1006"), 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streaming all lines of (BGZF-compressed) text files, without using an index.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx::LineReader;
+//!
+//! let mut reader = LineReader::from_path(&"file.bed.gz").unwrap();
+//! for line in reader.lines() {
+//!     let line = line.unwrap();
+//!     println!("{}", String::from_utf8_lossy(&line));
+//! }
+//! ```
+
+use libc;
+use std::ffi;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use htslib;
+use tbx::{BGZFError, ReadError, TabixReaderError, TabixReaderPathError, KS_SEP_LINE};
+
+/// A reader for all lines of a text file, which may be BGZF- or gzip-compressed.
+///
+/// In contrast to `Reader`, no index is required.
+#[derive(Debug)]
+pub struct LineReader {
+    hts_file: *mut htslib::htsFile,
+}
+
+unsafe impl Send for LineReader {}
+
+impl LineReader {
+    /// Create a new LineReader from path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path to open.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, TabixReaderPathError> {
+        match path.as_ref().to_str() {
+            Some(p) => Ok(try!(Self::new(p.as_bytes()))),
+            None => Err(TabixReaderPathError::InvalidPath),
+        }
+    }
+
+    /// Create a new LineReader from STDIN.
+    pub fn from_stdin() -> Result<Self, TabixReaderError> {
+        Self::new(b"-")
+    }
+
+    fn new(path: &[u8]) -> Result<Self, TabixReaderError> {
+        let path = ffi::CString::new(path).unwrap();
+        let hts_file =
+            unsafe { htslib::hts_open(path.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+        if hts_file.is_null() {
+            Err(TabixReaderError::BGZFError(BGZFError::Some))
+        } else {
+            Ok(LineReader { hts_file: hts_file })
+        }
+    }
+
+    /// Iterate over the remaining lines of the file, without trailing newline.
+    pub fn lines(&mut self) -> Lines {
+        Lines::new(self.hts_file)
+    }
+}
+
+impl Drop for LineReader {
+    fn drop(&mut self) {
+        unsafe {
+            htslib::hts_close(self.hts_file);
+        }
+    }
+}
+
+/// Iterator over the lines of a file, see `LineReader::lines()` and `Reader::lines()`.
+#[derive(Debug)]
+pub struct Lines<'a> {
+    hts_file: *mut htslib::htsFile,
+    buf: htslib::kstring_t,
+    phantom: PhantomData<&'a mut htslib::htsFile>,
+}
+
+impl<'a> Lines<'a> {
+    /// Create a new iterator, `hts_file` must outlive it.
+    pub(crate) fn new(hts_file: *mut htslib::htsFile) -> Self {
+        Lines {
+            hts_file: hts_file,
+            buf: htslib::kstring_t {
+                l: 0,
+                m: 0,
+                s: ptr::null_mut(),
+            },
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Result<Vec<u8>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match unsafe { htslib::hts_getline(self.hts_file, KS_SEP_LINE, &mut self.buf) } {
+            -1 => None,
+            ret if ret < 0 => Some(Err(ReadError::Truncated)),
+            _ => {
+                let line =
+                    unsafe { slice::from_raw_parts(self.buf.s as *const u8, self.buf.l as usize) };
+                Some(Ok(line.to_owned()))
+            }
+        }
+    }
+}
+
+impl<'a> Drop for Lines<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            libc::free(self.buf.s as *mut libc::c_void);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tbx::{Read, Reader};
+
+    #[test]
+    fn test_lines() {
+        let mut reader = LineReader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        let lines: Vec<Vec<u8>> = reader.lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                Vec::from("#foo"),
+                Vec::from("#bar"),
+                Vec::from("chr1\t1001\t1002"),
+                Vec::from("chr1\t1004\t1005"),
+                Vec::from("chr2\t1005\t1006"),
+            ]
+        );
+        assert_eq!(reader.lines().count(), 0);
+    }
+
+    #[test]
+    fn test_lines_uncompressed() {
+        let mut reader = LineReader::from_path("test/test_bed3.bed")
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_reader_lines() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        reader.fetch_contig("chr2").unwrap();
+        assert_eq!(reader.records().count(), 1);
+
+        assert_eq!(reader.lines().unwrap().count(), 5);
+        // the fetched region has been reset
+        let mut record = Vec::new();
+        assert!(reader.read(&mut record).is_err());
+        reader.fetch_contig("chr1").unwrap();
+        assert_eq!(reader.records().count(), 2);
+    }
+}
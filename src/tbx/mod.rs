@@ -42,26 +42,57 @@
 //! ```
 
 use libc;
+use std::cmp;
 use std::ffi;
+use std::fmt;
 use std::mem;
-use std::path::Path;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
 use std::ptr;
+use std::slice;
 use url::Url;
 
 use htslib;
+use tpool::ThreadPool;
+
+#[cfg(feature = "async")]
+mod async_reader;
+pub mod bed;
+pub mod gff;
+pub mod index;
+mod intersect;
+mod lines;
+mod position;
+mod reheader;
+pub mod vcf;
+
+#[cfg(feature = "async")]
+pub use self::async_reader::{AsyncError, AsyncReader};
+pub use self::index::{Format, TabixConfig};
+pub use self::intersect::{intersect, intersect_count, Intersect, IntersectCount, IntersectError};
+pub use self::lines::{LineReader, Lines};
+pub use self::position::{Position0, Position1};
+pub use self::reheader::{reheader, ReheaderError};
 
 /// A trait for a Tabix reader with a read method.
 pub trait Read: Sized {
     /// Read next line into the given `Vec<u8>` (i.e., ASCII string).
     ///
     /// Use this method in combination with a single allocated record to avoid the reallocations
-    /// occurring with the iterator.
+    /// occurring with the iterator.  The contents of `record` are replaced but its capacity is
+    /// reused.
     ///
     /// # Arguments
     ///
     /// * `record` - the `Vec<u8>` to be filled
     fn read(&mut self, record: &mut Vec<u8>) -> Result<(), ReadError>;
 
+    /// Read next line and return it as a slice of the internal buffer, without copying.
+    ///
+    /// The slice is valid until the next call of a method reading from or seeking in the
+    /// file, which the borrow checker enforces.
+    fn read_ref(&mut self) -> Result<&[u8], ReadError>;
+
     /// Iterator over the lines/records of the seeked region.
     ///
     /// Note that, while being convenient, this is less efficient than pre-allocating a
@@ -69,7 +100,17 @@ pub trait Read: Sized {
     /// the allocation of a new `Vec<u8>`.
     fn records(&mut self) -> Records<Self>;
 
+    /// Consuming iterator over the lines/records of the seeked region.
+    ///
+    /// In contrast to `records()`, the iterator owns the reader, so it can be returned from
+    /// functions or stored in structs.
+    fn into_records(self) -> IntoRecords<Self> {
+        IntoRecords { reader: self }
+    }
+
     /// Return the text headers, split by line.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
     fn header(&self) -> &Vec<String>;
 }
 
@@ -79,11 +120,14 @@ pub trait Read: Sized {
 /// by `tabix`.
 ///
 /// Note that the `tabix` command from `htslib` can actually several more things, including
-/// building indices and converting BCF to VCF text output.  Both is out of scope here.
+/// building indices and converting BCF to VCF text output.  For building indices, see the
+/// `index` module, converting BCF to VCF is out of scope here.
 #[derive(Debug)]
 pub struct Reader {
     /// The header lines (if any).
     header: Vec<String>,
+    /// The header lines (if any) as raw bytes.
+    header_bytes: Vec<Vec<u8>>,
 
     /// The file to read from.
     hts_file: *mut htslib::htsFile,
@@ -99,13 +143,24 @@ pub struct Reader {
     /// The currently fetch region's tid.
     tid: i32,
     /// The currently fetch region's 0-based begin pos.
-    start: i32,
+    start: i64,
     /// The currently fetch region's 0-based end pos.
-    end: i32,
+    end: i64,
+
+    /// The thread pool used for decompression, if any.
+    tpool: Option<ThreadPool>,
+    /// Path and (if given separately) index path for reopening the file, `None` for STDIN
+    /// and file descriptors.
+    source: Option<(Vec<u8>, Option<Vec<u8>>)>,
+    /// Predicate deciding which records to yield, if any.
+    filter: Option<RecordFilter>,
 }
 
 unsafe impl Send for Reader {}
 
+/// Largest position supported by htslib, which uses 32-bit positions.
+const MAX_POS: i64 = i32::max_value() as i64;
+
 /// Redefinition of `KS_SEP_LINE` from `htslib/kseq.h`.
 const KS_SEP_LINE: i32 = 2;
 
@@ -127,50 +182,227 @@ impl Reader {
         Self::new(url.as_str().as_bytes())
     }
 
+    /// Create a new Reader from path, using the tabix index at a separate path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path to open.
+    /// * `index_path` - the path of the tabix index.
+    pub fn from_path_and_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        path: P,
+        index_path: Q,
+    ) -> Result<Self, TabixReaderPathError> {
+        match (path.as_ref().to_str(), index_path.as_ref().to_str()) {
+            (Some(p), Some(i)) => Ok(try!(Self::new_with_index(p.as_bytes(), i.as_bytes()))),
+            _ => Err(TabixReaderPathError::InvalidPath),
+        }
+    }
+
+    /// Create a new Reader from URL, using the tabix index at a separate URL.
+    pub fn from_url_and_index(url: &Url, index_url: &Url) -> Result<Self, TabixReaderError> {
+        Self::new_with_index(url.as_str().as_bytes(), index_url.as_str().as_bytes())
+    }
+
+    /// Create a new Reader from path, using the tabix index given in memory, e.g., the
+    /// contents of a `.tbi` file fetched beforehand.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path to open.
+    /// * `index` - the contents of the tabix index.
+    pub fn from_path_and_index_data<P: AsRef<Path>>(
+        path: P,
+        index: &[u8],
+    ) -> Result<Self, TabixReaderPathError> {
+        match path.as_ref().to_str() {
+            Some(p) => Ok(try!(Self::new_with_index(p.as_bytes(), &data_url(index)))),
+            None => Err(TabixReaderPathError::InvalidPath),
+        }
+    }
+
+    /// Create a new Reader from URL, using the tabix index given in memory.
+    pub fn from_url_and_index_data(url: &Url, index: &[u8]) -> Result<Self, TabixReaderError> {
+        Self::new_with_index(url.as_str().as_bytes(), &data_url(index))
+    }
+
+    /// Create a new Reader from STDIN, using the tabix index at the given path.
+    ///
+    /// Note that fetching regions requires seeking, so STDIN has to be redirected from a file
+    /// (e.g., `< file.bed.gz`) rather than being a pipe.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_path` - the path of the tabix index
+    pub fn from_stdin<P: AsRef<Path>>(index_path: P) -> Result<Self, TabixReaderPathError> {
+        match index_path.as_ref().to_str() {
+            Some(index) => Ok(try!(Self::new_with_index(b"-", index.as_bytes()))),
+            None => Err(TabixReaderPathError::InvalidPath),
+        }
+    }
+
+    /// Create a new Reader from a file descriptor, using the tabix index at the given path.
+    ///
+    /// The reader takes ownership of the file descriptor and closes it when dropped.  Note
+    /// that fetching regions requires seeking, so the file descriptor cannot be a pipe.
+    ///
+    /// # Arguments
+    ///
+    /// * `fd` - the file descriptor to read from
+    /// * `index_path` - the path of the tabix index
+    pub fn from_fd<P: AsRef<Path>>(fd: RawFd, index_path: P) -> Result<Self, TabixReaderPathError> {
+        let index_path = match index_path.as_ref().to_str() {
+            Some(p) => ffi::CString::new(p).unwrap(),
+            None => return Err(TabixReaderPathError::InvalidPath),
+        };
+        let path = ffi::CString::new("-").unwrap();
+        let mode = ffi::CString::new("r").unwrap();
+        let hts_file = unsafe {
+            let hfile = htslib::hdopen(fd, mode.as_ptr());
+            if hfile.is_null() {
+                return Err(TabixReaderError::BGZFError(BGZFError::Some).into());
+            }
+            htslib::hts_hopen(hfile, path.as_ptr(), mode.as_ptr())
+        };
+        let tbx = unsafe { htslib::tbx_index_load2(path.as_ptr(), index_path.as_ptr()) };
+        Ok(try!(Self::from_raw(hts_file, tbx, None)))
+    }
+
     /// Create a new Reader.
     ///
     /// # Arguments
     ///
     /// * `path` - the path.
     fn new(path: &[u8]) -> Result<Self, TabixReaderError> {
-        let path = ffi::CString::new(path).unwrap();
+        Self::open(path, None, None)
+    }
+
+    /// Create a new Reader with the index at a separate location.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path. Use "-" for stdin.
+    /// * `index_path` - the path of the index.
+    fn new_with_index(path: &[u8], index_path: &[u8]) -> Result<Self, TabixReaderError> {
+        Self::open(path, Some(index_path), None)
+    }
+
+    /// Open the file and load the index from the given (or the default) location.
+    ///
+    /// The header is only read from the file if it is not given.
+    fn open(
+        path: &[u8],
+        index_path: Option<&[u8]>,
+        header: Option<(Vec<String>, Vec<Vec<u8>>)>,
+    ) -> Result<Self, TabixReaderError> {
+        let cpath = ffi::CString::new(path).unwrap();
         let hts_file =
-            unsafe { htslib::hts_open(path.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+            unsafe { htslib::hts_open(cpath.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+        let tbx = match index_path {
+            Some(index_path) => {
+                let index_path = ffi::CString::new(index_path).unwrap();
+                unsafe { htslib::tbx_index_load2(cpath.as_ptr(), index_path.as_ptr()) }
+            }
+            None => unsafe { htslib::tbx_index_load(cpath.as_ptr()) },
+        };
+        let mut reader = try!(Self::from_raw(hts_file, tbx, header));
+        if path != b"-" {
+            reader.source = Some((path.to_owned(), index_path.map(|p| p.to_owned())));
+        }
+        Ok(reader)
+    }
+
+    /// Create a new Reader from the opened file and the loaded index, reading the header if
+    /// it is not given.
+    ///
+    /// Takes ownership of `hts_file` and `tbx`, both of which may be null on errors.
+    fn from_raw(
+        hts_file: *mut htslib::htsFile,
+        tbx: *mut htslib::tbx_t,
+        header: Option<(Vec<String>, Vec<Vec<u8>>)>,
+    ) -> Result<Self, TabixReaderError> {
+        if hts_file.is_null() || tbx.is_null() {
+            unsafe {
+                if !hts_file.is_null() {
+                    htslib::hts_close(hts_file);
+                }
+                if !tbx.is_null() {
+                    htslib::tbx_destroy(tbx);
+                }
+            }
+            return if hts_file.is_null() {
+                Err(TabixReaderError::BGZFError(BGZFError::Some))
+            } else {
+                Err(TabixReaderError::InvalidIndex)
+            };
+        }
+
         let hts_format = unsafe { (*htslib::hts_get_format(hts_file)).format };
-        let tbx = unsafe { htslib::tbx_index_load(path.as_ptr()) };
-        let mut header = Vec::new();
         let mut buf = htslib::kstring_t {
             l: 0,
             m: 0,
             s: ptr::null_mut(),
         };
-        unsafe {
-            while htslib::hts_getline(hts_file, KS_SEP_LINE, &mut buf) >= 0 {
-                if buf.l > 0 && (*buf.s) as i32 == (*tbx).conf.meta_char {
-                    header.push(String::from(ffi::CStr::from_ptr(buf.s).to_str().unwrap()));
-                } else {
-                    break;
+        let (header, header_bytes) = match header {
+            Some(header) => header,
+            None => {
+                let mut header_bytes = Vec::new();
+                unsafe {
+                    while htslib::hts_getline(hts_file, KS_SEP_LINE, &mut buf) >= 0 {
+                        if buf.l > 0 && (*buf.s) as i32 == (*tbx).conf.meta_char {
+                            header_bytes.push(ffi::CStr::from_ptr(buf.s).to_bytes().to_owned());
+                        } else {
+                            break;
+                        }
+                    }
                 }
+                let header = header_bytes
+                    .iter()
+                    .map(|line| String::from_utf8_lossy(line).into_owned())
+                    .collect();
+                (header, header_bytes)
             }
-        }
+        };
 
-        if tbx.is_null() {
-            Err(TabixReaderError::InvalidIndex)
-        } else {
-            Ok(Reader {
-                header,
-                hts_file,
-                hts_format,
-                tbx,
-                buf,
-                itr: None,
-                tid: -1,
-                start: -1,
-                end: -1,
-            })
+        Ok(Reader {
+            header,
+            header_bytes,
+            hts_file,
+            hts_format,
+            tbx,
+            buf,
+            itr: None,
+            tid: -1,
+            start: -1,
+            end: -1,
+            tpool: None,
+            source: None,
+            filter: None,
+        })
+    }
+
+    /// Open another reader for the same file, e.g., for querying it from another thread.
+    ///
+    /// The file and the index are opened again, while the header is copied from this reader.
+    /// The fetched region, cache size, threading settings and filter are not transferred.  Readers
+    /// created from STDIN or file descriptors cannot be cloned.
+    pub fn try_clone(&self) -> Result<Self, TabixReaderError> {
+        match self.source {
+            Some((ref path, ref index_path)) => Self::open(
+                path,
+                index_path.as_ref().map(|p| p.as_slice()),
+                Some((self.header.clone(), self.header_bytes.clone())),
+            ),
+            None => Err(TabixReaderError::NotReopenable),
         }
     }
 
+    /// Return the text headers as raw bytes, split by line.
+    ///
+    /// In contrast to `header()`, this does not assume the header to be valid UTF-8.
+    pub fn header_bytes(&self) -> &Vec<Vec<u8>> {
+        &self.header_bytes
+    }
+
     /// Get sequence/target ID from sequence name.
     pub fn tid(&self, name: &str) -> Result<u32, SequenceLookupError> {
         // TODO: naming?
@@ -188,10 +420,148 @@ impl Reader {
     }
 
     /// Fetch region given by numeric sequence number and 0-based begin and end position.
+    ///
+    /// The bundled htslib uses 32-bit positions, so positions beyond `i32::max_value()` cannot
+    /// be queried: a larger `start` yields `FetchError::PositionOutOfRange` and a larger `end`
+    /// is treated as `i32::max_value()`.
     pub fn fetch(&mut self, tid: u32, start: u32, end: u32) -> Result<(), FetchError> {
-        self.tid = tid as i32;
-        self.start = start as i32;
-        self.end = end as i32;
+        self.fetch_itr(tid as i32, start as i64, end as i64)
+    }
+
+    /// Fetch region given by numeric sequence number and 1-based, inclusive begin and end
+    /// position, as in region strings or VCF files.
+    ///
+    /// # Arguments
+    ///
+    /// * `tid` - numeric ID of the sequence
+    /// * `start` - the first position of the region
+    /// * `end` - the last position of the region
+    ///
+    /// # Errors
+    ///
+    /// Returns `FetchError::PositionOutOfRange` if `start` or `end` is the invalid position 0.
+    pub fn fetch_1based(
+        &mut self,
+        tid: u32,
+        start: Position1,
+        end: Position1,
+    ) -> Result<(), FetchError> {
+        let start = try!(Position0::from_1based(start).ok_or(FetchError::PositionOutOfRange));
+        if end.0 == 0 {
+            return Err(FetchError::PositionOutOfRange);
+        }
+        // the last 1-based position equals the 0-based, exclusive end
+        self.fetch(tid, start.0, end.0)
+    }
+
+    /// Fetch all records of the file, in the order of the file (`HTS_IDX_START`).
+    ///
+    /// This allows for a linear pass over the file with `read()` or `records()`, skipping the
+    /// header lines.
+    pub fn fetch_all(&mut self) -> Result<(), FetchError> {
+        self.fetch_itr(htslib::HTS_IDX_START, 0, 0)
+    }
+
+    /// Fetch all records following the record read last, up to the end of the file
+    /// (`HTS_IDX_REST`).
+    ///
+    /// In contrast to the previously fetched region, the records are not restricted to any
+    /// region.
+    pub fn fetch_rest(&mut self) -> Result<(), FetchError> {
+        self.fetch_itr(htslib::HTS_IDX_REST, 0, 0)
+    }
+
+    /// Fetch the records without coordinates, following the records of all contigs
+    /// (`HTS_IDX_NOCOOR`).
+    pub fn fetch_nocoor(&mut self) -> Result<(), FetchError> {
+        self.fetch_itr(htslib::HTS_IDX_NOCOOR, 0, 0)
+    }
+
+    /// Seek to the given virtual offset, as returned by `tell()`.
+    ///
+    /// Afterwards, all records starting from the offset up to the end of the file are read
+    /// by `read()` and `records()`, regardless of any previously fetched region.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - the virtual offset to seek to
+    pub fn seek(&mut self, offset: i64) -> Result<(), SeekError> {
+        let ret = unsafe {
+            htslib::bgzf_seek(htslib::hts_get_bgzfp(self.hts_file), offset, libc::SEEK_SET)
+        };
+        if ret != 0 {
+            return Err(SeekError::Some);
+        }
+        // continue reading from the current position of the file
+        self.fetch_rest().map_err(|_| SeekError::Some)
+    }
+
+    /// Iterate over all lines of the file, including the header, without using the index.
+    ///
+    /// The file is read from its start, which requires seeking, and any fetched region is
+    /// reset, i.e., call `fetch()` again before reading records.
+    pub fn lines(&mut self) -> Result<Lines, SeekError> {
+        if let Some(itr) = self.itr.take() {
+            unsafe {
+                htslib::hts_itr_destroy(itr);
+            }
+        }
+        let ret =
+            unsafe { htslib::bgzf_seek(htslib::hts_get_bgzfp(self.hts_file), 0, libc::SEEK_SET) };
+        if ret != 0 {
+            return Err(SeekError::Some);
+        }
+        Ok(Lines::new(self.hts_file))
+    }
+
+    /// Only yield the records for which `filter` returns `true`.
+    ///
+    /// The filter is called with each record overlapping the fetched region, before it is
+    /// copied, so cheap predicates can skip records without allocating.  It applies to all
+    /// methods reading records, including `count()`, until it is replaced or removed with
+    /// `clear_filter()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use rust_htslib::tbx::{self, Read};
+    ///
+    /// let mut reader = tbx::Reader::from_path(&"genes.bed.gz").unwrap();
+    /// reader.set_filter(|record| tbx::fields(record).nth(3) == Some(&b"BRCA1"[..]));
+    /// reader.fetch_region("chr17").unwrap();
+    /// let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+    /// ```
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: FnMut(&[u8]) -> bool + Send + 'static,
+    {
+        self.filter = Some(RecordFilter(Box::new(filter)));
+    }
+
+    /// Remove the filter set with `set_filter()`.
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+    }
+
+    /// Report the current virtual offset, i.e., the offset of the record following the one
+    /// read last.
+    pub fn tell(&self) -> i64 {
+        // this reimplements the bgzf_tell macro
+        let bgzf = unsafe { htslib::hts_get_bgzfp(self.hts_file).as_ref() }
+            .expect("bug: null pointer to BGZF");
+        (bgzf.block_address << 16) | (bgzf.block_offset as i64 & 0xFFFF)
+    }
+
+    /// Create the iterator for the given tid (or special `HTS_IDX_*` value) and positions.
+    fn fetch_itr(&mut self, tid: i32, start: i64, end: i64) -> Result<(), FetchError> {
+        // htslib uses 32-bit positions, see `fetch()`
+        if start > MAX_POS {
+            return Err(FetchError::PositionOutOfRange);
+        }
+        let end = cmp::min(end, MAX_POS);
+        self.tid = tid;
+        self.start = start;
+        self.end = end;
 
         if let Some(itr) = self.itr {
             unsafe {
@@ -201,7 +571,7 @@ impl Reader {
         let itr = unsafe {
             htslib::hts_itr_query(
                 (*self.tbx).idx,
-                tid as i32,
+                tid,
                 start as i32,
                 end as i32,
                 Some(htslib::tbx_readrec),
@@ -216,6 +586,213 @@ impl Reader {
         }
     }
 
+    /// Count the records overlapping the region given by numeric sequence number and 0-based
+    /// begin and end position.
+    ///
+    /// The records are not copied, so this is faster than counting the results of
+    /// `records()`.  Afterwards, the region is fetched as with `fetch()` but all of its records
+    /// have been consumed.
+    pub fn count(&mut self, tid: u32, start: u32, end: u32) -> Result<u64, ReadError> {
+        if self.fetch(tid, start, end).is_err() {
+            return Err(ReadError::NoIter);
+        }
+        let mut count = 0;
+        loop {
+            match self.next_record() {
+                Ok(()) => count += 1,
+                Err(ReadError::NoMoreRecord) => return Ok(count),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Estimate the number of records of the fetched region from the index, e.g., to
+    /// pre-allocate memory for the results.
+    ///
+    /// The index stores the number of records of each contig but not of smaller regions, so
+    /// for these the number is scaled by the share of the contig's data that is read for the
+    /// region.  This is a rough estimate only, in particular for regions covering few records.
+    /// Returns `None` if no region has been fetched, for `fetch_rest()`, or if the index lacks
+    /// the number of records.
+    pub fn estimated_len(&self) -> Option<u64> {
+        let itr = match self.itr {
+            Some(itr) => itr,
+            None => return None,
+        };
+        let idx = unsafe { (*self.tbx).idx };
+        match self.tid {
+            htslib::HTS_IDX_NOCOOR => Some(unsafe { htslib::hts_idx_get_n_no_coor(idx) }),
+            htslib::HTS_IDX_START => {
+                let mut total = unsafe { htslib::hts_idx_get_n_no_coor(idx) };
+                for tid in 0..self.seqnames().len() {
+                    match contig_len(idx, tid as i32) {
+                        Some(n) => total += n,
+                        None => return None,
+                    }
+                }
+                Some(total)
+            }
+            tid if tid >= 0 => {
+                let n = match contig_len(idx, tid) {
+                    Some(n) => n,
+                    None => return None,
+                };
+                let contig_itr = unsafe {
+                    htslib::hts_itr_query(idx, tid, 0, i32::max_value(), Some(htslib::tbx_readrec))
+                };
+                if contig_itr.is_null() {
+                    return None;
+                }
+                let (region_size, contig_size) =
+                    unsafe { (chunks_size(&*itr), chunks_size(&*contig_itr)) };
+                unsafe {
+                    htslib::hts_itr_destroy(contig_itr);
+                }
+                if contig_size <= 0.0 {
+                    Some(0)
+                } else {
+                    let estimate = (n as f64 * region_size / contig_size).round() as u64;
+                    Some(cmp::min(estimate, n))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Fetch multiple regions, given by numeric sequence number and 0-based begin and end
+    /// position, and iterate over the records of all of them.
+    ///
+    /// The returned iterator yields the index of the region in `regions` together with each
+    /// record overlapping it, region by region in the given order.  Records overlapping
+    /// several regions are yielded once for each region.
+    ///
+    /// # Arguments
+    ///
+    /// * `regions` - the regions to fetch
+    ///
+    /// See `fetch_regions_merged()` for yielding each record only once.
+    pub fn fetch_regions<'a>(&'a mut self, regions: &'a [(u32, u32, u32)]) -> RegionRecords<'a> {
+        RegionRecords {
+            reader: self,
+            regions: regions,
+            next_region: 0,
+        }
+    }
+
+    /// Fetch multiple regions like `fetch_regions()`, but yield each record only once.
+    ///
+    /// The regions are sorted and overlapping or adjacent regions are merged, so the records
+    /// are yielded in the order of the index, without the index of the region.
+    ///
+    /// # Arguments
+    ///
+    /// * `regions` - the regions to fetch
+    pub fn fetch_regions_merged(&mut self, regions: &[(u32, u32, u32)]) -> MergedRecords {
+        MergedRecords {
+            reader: self,
+            regions: merge_regions(regions.to_vec()),
+            next_region: 0,
+        }
+    }
+
+    /// Fetch the regions given in a BED file and iterate over the records overlapping any of
+    /// them.
+    ///
+    /// The BED file may be plain text or compressed.  Regions on contigs that are missing from
+    /// the index are ignored, the others are sorted and overlapping or adjacent regions are
+    /// merged, so each record is yielded once, in the order of the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path of the BED file with the target regions
+    pub fn fetch_from_bed<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<MergedRecords, BedRegionsError> {
+        let path = match path.as_ref().to_str() {
+            Some(p) => ffi::CString::new(p).unwrap(),
+            None => return Err(BedRegionsError::InvalidPath),
+        };
+        let fp =
+            unsafe { htslib::hts_open(path.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+        if fp.is_null() {
+            return Err(BedRegionsError::InvalidPath);
+        }
+        let mut buf = htslib::kstring_t {
+            l: 0,
+            m: 0,
+            s: ptr::null_mut(),
+        };
+        let mut regions = Vec::new();
+        let mut result = Ok(());
+        while unsafe { htslib::hts_getline(fp, KS_SEP_LINE, &mut buf) } >= 0 {
+            let line = unsafe { slice::from_raw_parts(buf.s as *const u8, buf.l as usize) };
+            if line.is_empty() || line.starts_with(b"#") || line.starts_with(b"track")
+                || line.starts_with(b"browser")
+            {
+                continue;
+            }
+            match bed::BedRecord::parse(line) {
+                // the start is at most the end, which is checked by `parse()`
+                Ok(ref record) if record.end > u64::from(u32::max_value()) => {
+                    result = Err(BedRegionsError::PositionOutOfRange);
+                    break;
+                }
+                Ok(record) => if let Ok(tid) = self.tid(&record.chrom) {
+                    regions.push((tid, record.start as u32, record.end as u32));
+                },
+                Err(e) => {
+                    result = Err(BedRegionsError::BedError(e));
+                    break;
+                }
+            }
+        }
+        unsafe {
+            htslib::hts_close(fp);
+            libc::free(buf.s as *mut libc::c_void);
+        }
+        try!(result);
+
+        Ok(MergedRecords {
+            reader: self,
+            regions: merge_regions(regions),
+            next_region: 0,
+        })
+    }
+
+    /// Fetch all records on the contig with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the contig
+    pub fn fetch_contig(&mut self, name: &str) -> Result<(), FetchError> {
+        match self.tid(name) {
+            Ok(tid) => self.fetch(tid, 0, i32::max_value() as u32),
+            Err(_) => Err(FetchError::UnknownContig),
+        }
+    }
+
+    /// Fetch region given as a string, e.g., `chr1:1,000-2,000` (1-based, inclusive) or `chr1`
+    /// for a complete contig, as accepted by `tabix`.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the region to fetch
+    pub fn fetch_region(&mut self, region: &str) -> Result<(), FetchError> {
+        let cregion = ffi::CString::new(region).unwrap();
+        let mut start = 0;
+        let mut end = 0;
+        let name_end = unsafe { htslib::hts_parse_reg(cregion.as_ptr(), &mut start, &mut end) };
+        if name_end.is_null() || start < 0 || end < start {
+            return Err(FetchError::InvalidRegion);
+        }
+        let name = &region[..name_end as usize - cregion.as_ptr() as usize];
+        match self.tid(name) {
+            Ok(tid) => self.fetch(tid, start as u32, end as u32),
+            Err(_) => Err(FetchError::UnknownContig),
+        }
+    }
+
     /// Return the sequence contig names.
     pub fn seqnames(&self) -> Vec<String> {
         let mut result = Vec::new();
@@ -238,6 +815,23 @@ impl Reader {
         result
     }
 
+    /// Return the configuration of the index, i.e., how positions are parsed from the records.
+    pub fn conf(&self) -> TabixConfig {
+        TabixConfig::from_inner(unsafe { (*self.tbx).conf })
+    }
+
+    /// Return tid and 0-based, half-open interval of the record read last, if any.
+    fn current_interval(&self) -> Option<(i32, i64, i64)> {
+        self.itr.map(|itr| unsafe {
+            ((*itr).curr_tid, (*itr).curr_beg as i64, (*itr).curr_end as i64)
+        })
+    }
+
+    /// Set the size of the BGZF block cache in bytes, see `bam::Read::set_cache_size()`.
+    pub fn set_cache_size(&mut self, cache_size: usize) {
+        unsafe { htslib::hts_set_cache_size(self.hts_file, cache_size as i32) }
+    }
+
     /// Activate multi-threaded BGZF read support in htslib. This should permit faster
     /// reading of large BGZF files.
     ///
@@ -254,15 +848,64 @@ impl Reader {
             Ok(())
         }
     }
+
+    /// Use the given thread pool for multi-threaded BGZF decompression.
+    ///
+    /// In contrast to `set_threads()`, the pool can be shared with other readers and writers.
+    /// The reader keeps a handle to the pool, so it stays alive as long as the reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `tpool` - the thread pool to use
+    pub fn set_thread_pool(&mut self, tpool: &ThreadPool) -> Result<(), ThreadingError> {
+        let r = unsafe { htslib::hts_set_thread_pool(self.hts_file, tpool.inner()) };
+        if r != 0 {
+            Err(ThreadingError::Some)
+        } else {
+            self.tpool = Some(tpool.clone());
+            Ok(())
+        }
+    }
+}
+
+/// Encode `data` as base64 `data:` URL, which htslib reads from memory.
+fn data_url(data: &[u8]) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut url = Vec::with_capacity(13 + (data.len() + 2) / 3 * 4);
+    url.extend_from_slice(b"data:;base64,");
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                url.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F]);
+            } else {
+                url.push(b'=');
+            }
+        }
+    }
+    url
+}
+
+/// Predicate for records, see `Reader::set_filter()`.
+struct RecordFilter(Box<FnMut(&[u8]) -> bool + Send>);
+
+impl fmt::Debug for RecordFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("RecordFilter")
+    }
 }
 
 /// Return whether the two given genomic intervals overlap.
-fn overlap(tid1: i32, begin1: i32, end1: i32, tid2: i32, begin2: i32, end2: i32) -> bool {
+fn overlap(tid1: i32, begin1: i64, end1: i64, tid2: i32, begin2: i64, end2: i64) -> bool {
     (tid1 == tid2) && (begin1 < end2) && (begin2 < end1)
 }
 
-impl Read for Reader {
-    fn read(&mut self, record: &mut Vec<u8>) -> Result<(), ReadError> {
+impl Reader {
+    /// Advance to the next record overlapping the fetched region, leaving it in `self.buf`.
+    fn next_record(&mut self) -> Result<(), ReadError> {
         match self.itr {
             Some(itr) => {
                 loop {
@@ -284,13 +927,23 @@ impl Read for Reader {
                         panic!("Return value should not be <0 but was: {}", ret);
                     }
                     // Return first overlapping record (loop will stop when `hts_itr_next(...)`
-                    // returns `< 0`).
-                    let (tid, start, end) =
-                        unsafe { ((*itr).curr_tid, (*itr).curr_beg, (*itr).curr_end) };
-                    if overlap(self.tid, self.start, self.end, tid, start, end) {
-                        *record =
-                            unsafe { Vec::from(ffi::CStr::from_ptr(self.buf.s).to_str().unwrap()) };
-                        return Ok(());
+                    // returns `< 0`).  When iterating over all records, each one is returned.
+                    let (tid, start, end) = unsafe {
+                        ((*itr).curr_tid, (*itr).curr_beg as i64, (*itr).curr_end as i64)
+                    };
+                    if self.tid >= 0 && !overlap(self.tid, self.start, self.end, tid, start, end) {
+                        continue;
+                    }
+                    match self.filter {
+                        Some(ref mut filter) => {
+                            let record = unsafe {
+                                slice::from_raw_parts(self.buf.s as *const u8, self.buf.l as usize)
+                            };
+                            if (filter.0)(record) {
+                                return Ok(());
+                            }
+                        }
+                        None => return Ok(()),
                     }
                 }
             }
@@ -298,6 +951,25 @@ impl Read for Reader {
         }
     }
 
+    /// Return the current record in `self.buf`, without trailing newline.
+    fn buf_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.buf.s as *const u8, self.buf.l as usize) }
+    }
+}
+
+impl Read for Reader {
+    fn read(&mut self, record: &mut Vec<u8>) -> Result<(), ReadError> {
+        try!(self.next_record());
+        record.clear();
+        record.extend_from_slice(self.buf_bytes());
+        Ok(())
+    }
+
+    fn read_ref(&mut self) -> Result<&[u8], ReadError> {
+        try!(self.next_record());
+        Ok(self.buf_bytes())
+    }
+
     fn records(&mut self) -> Records<Self> {
         Records { reader: self }
     }
@@ -319,13 +991,302 @@ impl Drop for Reader {
     }
 }
 
-/// Iterator over the lines of a tabix file.
+/// Split a record into its tab-separated columns, without allocating.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use rust_htslib::tbx::{self, Read};
+///
+/// let mut reader = tbx::Reader::from_path(&"file.bed.gz").unwrap();
+/// reader.fetch_all().unwrap();
+/// while let Ok(record) = reader.read_ref() {
+///     if tbx::fields(record).nth(4) == Some(&b"30"[..]) {
+///         // ...
+///     }
+/// }
+/// ```
+pub fn fields(record: &[u8]) -> Fields {
+    Fields { rest: Some(record) }
+}
+
+/// Iterator over the columns of a record, see `fields()`.
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+    /// The remaining part of the record, `None` after the last column.
+    rest: Option<&'a [u8]>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.rest.map(|rest| match rest.iter().position(|&c| c == b'\t') {
+            Some(pos) => {
+                self.rest = Some(&rest[pos + 1..]);
+                &rest[..pos]
+            }
+            None => {
+                self.rest = None;
+                rest
+            }
+        })
+    }
+}
+
+/// Consuming iterator over the lines of a tabix file, see `Read::into_records()`.
 #[derive(Debug)]
-pub struct Records<'a, R: 'a + Read> {
-    reader: &'a mut R,
+pub struct IntoRecords<R: Read> {
+    reader: R,
 }
 
-impl<'a, R: Read> Iterator for Records<'a, R> {
+impl<R: Read> IntoRecords<R> {
+    /// Return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for IntoRecords<R> {
+    type Item = Result<Vec<u8>, ReadError>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, ReadError>> {
+        let mut record = Vec::new();
+        match self.reader.read(&mut record) {
+            Err(ReadError::NoMoreRecord) => None,
+            Ok(()) => Some(Ok(record)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Iterator over the records of multiple regions, see `Reader::fetch_regions()`.
+#[derive(Debug)]
+pub struct RegionRecords<'a> {
+    reader: &'a mut Reader,
+    regions: &'a [(u32, u32, u32)],
+    /// Index of the next region to fetch, the current region is the one before.
+    next_region: usize,
+}
+
+impl<'a> Iterator for RegionRecords<'a> {
+    type Item = Result<(usize, Vec<u8>), ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Vec::new();
+        loop {
+            if self.next_region > 0 {
+                match self.reader.read(&mut record) {
+                    Ok(()) => return Some(Ok((self.next_region - 1, record))),
+                    Err(ReadError::NoMoreRecord) => (),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            if self.next_region >= self.regions.len() {
+                return None;
+            }
+            let (tid, start, end) = self.regions[self.next_region];
+            self.next_region += 1;
+            if self.reader.fetch(tid, start, end).is_err() {
+                return Some(Err(ReadError::NoIter));
+            }
+        }
+    }
+}
+
+/// Assumed ratio between the uncompressed and compressed size of BGZF blocks, used for
+/// estimating the amount of data in index chunks.
+const COMPRESSION_RATIO: f64 = 4.0;
+
+/// Return the number of records on the contig with the given tid, as stored in the index.
+fn contig_len(idx: *const htslib::hts_idx_t, tid: i32) -> Option<u64> {
+    let mut mapped = 0;
+    let mut unmapped = 0;
+    if unsafe { htslib::hts_idx_get_stat(idx, tid, &mut mapped, &mut unmapped) } < 0 {
+        None
+    } else {
+        Some(mapped)
+    }
+}
+
+/// Approximate the amount of uncompressed data in the index chunks of an iterator.
+fn chunks_size(itr: &htslib::hts_itr_t) -> f64 {
+    if itr.n_off <= 0 {
+        return 0.0;
+    }
+    let chunks = unsafe { slice::from_raw_parts(itr.off, itr.n_off as usize) };
+    chunks
+        .iter()
+        .map(|chunk| {
+            // the upper 48 bits are the offset of the compressed block, the lower 16 bits the
+            // offset within the uncompressed block
+            let blocks = ((chunk.v >> 16) as f64 - (chunk.u >> 16) as f64) * COMPRESSION_RATIO;
+            blocks + (chunk.v & 0xFFFF) as f64 - (chunk.u & 0xFFFF) as f64
+        })
+        .sum()
+}
+
+/// Sort regions and merge overlapping or adjacent ones.
+fn merge_regions(mut regions: Vec<(u32, u32, u32)>) -> Vec<(u32, u32, u32)> {
+    regions.sort();
+    let mut merged: Vec<(u32, u32, u32)> = Vec::with_capacity(regions.len());
+    for (tid, start, end) in regions {
+        if let Some(last) = merged.last_mut() {
+            if last.0 == tid && start <= last.2 {
+                last.2 = cmp::max(last.2, end);
+                continue;
+            }
+        }
+        merged.push((tid, start, end));
+    }
+    merged
+}
+
+/// Iterator over the records overlapping any of a set of merged regions, see
+/// `Reader::fetch_regions_merged()` and `Reader::fetch_from_bed()`.
+#[derive(Debug)]
+pub struct MergedRecords<'a> {
+    reader: &'a mut Reader,
+    /// The sorted, non-overlapping regions.
+    regions: Vec<(u32, u32, u32)>,
+    /// Index of the next region to fetch, the current region is the one before.
+    next_region: usize,
+}
+
+impl<'a> Iterator for MergedRecords<'a> {
+    type Item = Result<Vec<u8>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Vec::new();
+        loop {
+            if self.next_region > 0 {
+                match self.reader.read(&mut record) {
+                    Ok(()) => {
+                        // Records starting before the end of the previous region on the same
+                        // contig overlap it and have been yielded already.
+                        if self.next_region > 1 {
+                            let (prev_tid, _, prev_end) = self.regions[self.next_region - 2];
+                            let (tid, start, _) = self.reader.current_interval().unwrap();
+                            if tid == prev_tid as i32 && start < prev_end as i64 {
+                                continue;
+                            }
+                        }
+                        return Some(Ok(record));
+                    }
+                    Err(ReadError::NoMoreRecord) => (),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            if self.next_region >= self.regions.len() {
+                return None;
+            }
+            let (tid, start, end) = self.regions[self.next_region];
+            self.next_region += 1;
+            if self.reader.fetch(tid, start, end).is_err() {
+                return Some(Err(ReadError::NoIter));
+            }
+        }
+    }
+}
+
+/// A writer for BGZF-compressed text files that builds a tabix index when closed.
+///
+/// The lines must be written sorted by sequence and start position, as required by `tabix`.
+/// Header lines have to start with the meta character of the configuration (e.g., `#` for
+/// BED files).
+#[derive(Debug)]
+pub struct Writer {
+    /// The BGZF file to write to, `None` after closing.
+    inner: Option<*mut htslib::BGZF>,
+    /// Path of the written file.
+    path: PathBuf,
+    /// Configuration of the index to build.
+    conf: TabixConfig,
+}
+
+unsafe impl Send for Writer {}
+
+impl Writer {
+    /// Create a new Writer for the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the path to write to
+    /// * `conf` - the configuration of the index to build, e.g., `TabixConfig::bed()`
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        conf: &TabixConfig,
+    ) -> Result<Self, TabixWriterPathError> {
+        let cpath = match path.as_ref().to_str() {
+            Some(p) => ffi::CString::new(p).unwrap(),
+            None => return Err(TabixWriterPathError::InvalidPath),
+        };
+        let inner =
+            unsafe { htslib::bgzf_open(cpath.as_ptr(), ffi::CString::new("w").unwrap().as_ptr()) };
+        if inner.is_null() {
+            Err(TabixWriterPathError::from(TabixWriterError::Some))
+        } else {
+            Ok(Writer {
+                inner: Some(inner),
+                path: path.as_ref().to_owned(),
+                conf: *conf,
+            })
+        }
+    }
+
+    /// Write a line, the trailing newline is added automatically.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - the line to write, without newline
+    pub fn write(&mut self, line: &[u8]) -> Result<(), WriteError> {
+        let inner = match self.inner {
+            Some(inner) => inner,
+            None => return Err(WriteError::Some),
+        };
+        for data in &[line, b"\n"] {
+            let ret = unsafe {
+                htslib::bgzf_write(inner, data.as_ptr() as *const libc::c_void, data.len())
+            };
+            if ret < 0 || ret as usize != data.len() {
+                return Err(WriteError::Some);
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the file and build the tabix index (suffix `.tbi`).
+    ///
+    /// When the writer is dropped without calling this method, the file is closed and indexed
+    /// as well but errors are ignored.
+    pub fn close(mut self) -> Result<(), WriteError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        if let Some(inner) = self.inner.take() {
+            if unsafe { htslib::bgzf_close(inner) } != 0 {
+                return Err(WriteError::Some);
+            }
+            try!(index::build(&self.path, &self.conf));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Iterator over the lines of a tabix file.
+#[derive(Debug)]
+pub struct Records<'a, R: 'a + Read> {
+    reader: &'a mut R,
+}
+
+impl<'a, R: Read> Iterator for Records<'a, R> {
     type Item = Result<Vec<u8>, ReadError>;
 
     fn next(&mut self) -> Option<Result<Vec<u8>, ReadError>> {
@@ -372,6 +1333,9 @@ quick_error! {
         InvalidIndex {
             description("invalid index")
         }
+        NotReopenable {
+            description("reader from STDIN or file descriptor cannot be reopened")
+        }
         BGZFError(err: BGZFError) {
             from()
         }
@@ -390,6 +1354,39 @@ quick_error! {
     }
 }
 
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum TabixWriterError {
+        Some {
+            description("error opening file for writing")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum TabixWriterPathError {
+        InvalidPath {
+            description("invalid path")
+        }
+        TabixWriterError(err: TabixWriterError) {
+            from()
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum WriteError {
+        Some {
+            description("error writing record")
+        }
+        BuildError(err: index::BuildError) {
+            from()
+        }
+    }
+}
+
 quick_error! {
     #[derive(Debug, Clone)]
     pub enum BGZFError {
@@ -414,6 +1411,39 @@ quick_error! {
         Some {
             description("error fetching a locus")
         }
+        InvalidRegion {
+            description("invalid region string")
+        }
+        UnknownContig {
+            description("unknown contig name")
+        }
+        PositionOutOfRange {
+            description("position out of range")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum BedRegionsError {
+        InvalidPath {
+            description("invalid path or error opening BED file")
+        }
+        BedError(err: bed::BedError) {
+            from()
+        }
+        PositionOutOfRange {
+            description("position out of range")
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum SeekError {
+        Some {
+            description("error seeking to voffset")
+        }
     }
 }
 
@@ -428,7 +1458,11 @@ quick_error! {
 
 #[cfg(test)]
 mod tests {
+    extern crate tempdir;
     use super::*;
+    use std::fs;
+    use std::os::unix::io::IntoRawFd;
+    use std::thread;
 
     #[test]
     fn bed_header() {
@@ -454,6 +1488,68 @@ mod tests {
         assert!(reader.tid("chr3").is_err());
     }
 
+    #[test]
+    fn bed_header_non_utf8() {
+        let mut reader = Reader::from_path("test/test_latin1.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert_eq!(reader.header_bytes(), &vec![b"#caf\xe9".to_vec()]);
+        assert_eq!(reader.header(), &vec![String::from("#caf\u{fffd}")]);
+
+        assert!(reader.fetch_contig("chr1").is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![b"chr1\t10\t20\tna\xefve".to_vec()]);
+    }
+
+    #[test]
+    fn bed_from_path_and_index() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("data.bed.gz");
+        fs::copy("test/test_bed3.bed.gz", &path).unwrap();
+        assert!(Reader::from_path(&path).is_err());
+
+        let mut reader = Reader::from_path_and_index(&path, "test/test_bed3.bed.gz.tbi")
+            .ok()
+            .expect("Error opening file.");
+        assert!(reader.fetch_contig("chr1").is_ok());
+        assert_eq!(reader.records().count(), 2);
+    }
+
+    #[test]
+    fn bed_from_fd() {
+        let fd = fs::File::open("test/test_bed3.bed.gz").unwrap().into_raw_fd();
+        let mut reader = Reader::from_fd(fd, "test/test_bed3.bed.gz.tbi")
+            .ok()
+            .expect("Error opening file.");
+
+        assert_eq!(
+            reader.header(),
+            &vec![String::from("#foo"), String::from("#bar")]
+        );
+        assert!(reader.fetch_contig("chr2").is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr2\t1005\t1006")]);
+    }
+
+    #[test]
+    fn bed_conf() {
+        let reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        let conf = reader.conf();
+        assert_eq!(conf, TabixConfig::bed());
+        assert_eq!(conf.preset(), Some("bed"));
+        assert_eq!(conf.format(), Format::Generic);
+        assert_eq!(conf.seq_col(), 1);
+        assert_eq!(conf.begin_col(), 2);
+        assert_eq!(conf.end_col(), Some(3));
+        assert_eq!(conf.meta_char(), b'#');
+    }
+
     #[test]
     fn bed_fetch_from_chr1_read_api() {
         let mut reader = Reader::from_path("test/test_bed3.bed.gz")
@@ -469,6 +1565,449 @@ mod tests {
         assert!(reader.read(&mut record).is_err());
     }
 
+    #[test]
+    fn bed_fetch_region() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_region("chr1:1,002-1,003").is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr1\t1001\t1002")]);
+
+        assert!(reader.fetch_region("chr2").is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr2\t1005\t1006")]);
+
+        match reader.fetch_region("chr3:1-100") {
+            Err(FetchError::UnknownContig) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn bed_fetch_contig() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_contig("chr1").is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1001\t1002"), Vec::from("chr1\t1004\t1005")]
+        );
+        assert!(reader.fetch_contig("chr3").is_err());
+    }
+
+    #[test]
+    fn bed_fetch_all() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_all().is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(
+            records,
+            vec![
+                Vec::from("chr1\t1001\t1002"),
+                Vec::from("chr1\t1004\t1005"),
+                Vec::from("chr2\t1005\t1006"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bed_fetch_regions() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        let regions = vec![(1, 0, 2000), (0, 0, 10), (0, 1000, 1005)];
+        let records: Vec<(usize, Vec<u8>)> = reader
+            .fetch_regions(&regions)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (0, Vec::from("chr2\t1005\t1006")),
+                (2, Vec::from("chr1\t1001\t1002")),
+                (2, Vec::from("chr1\t1004\t1005")),
+            ]
+        );
+    }
+
+    #[test]
+    fn bed_fetch_rest() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_region("chr1:1-1002").is_ok());
+        let mut record = Vec::new();
+        assert!(reader.read(&mut record).is_ok());
+        assert_eq!(record, Vec::from("chr1\t1001\t1002"));
+        assert!(reader.fetch_rest().is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1004\t1005"), Vec::from("chr2\t1005\t1006")]
+        );
+
+        // there are no records without coordinates
+        assert!(reader.fetch_nocoor().is_ok());
+        assert_eq!(reader.records().count(), 0);
+    }
+
+    #[test]
+    fn bed_fetch_from_bed() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        // the two overlapping regions on chr1 are merged, chr3 is not in the index
+        let records: Vec<Vec<u8>> = reader
+            .fetch_from_bed("test/test_bed3_b.bed")
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1001\t1002"), Vec::from("chr1\t1004\t1005")]
+        );
+        assert!(reader.fetch_from_bed("test/missing.bed").is_err());
+
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("large.bed");
+        fs::write(&path, "chr1\t1000\t5000000000\n").unwrap();
+        match reader.fetch_from_bed(&path) {
+            Err(BedRegionsError::PositionOutOfRange) => (),
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn bed_fetch_regions_merged() {
+        let mut reader = Reader::from_path("test/test_bed3_b.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        // `chr1 1001 1005` overlaps both of the first regions
+        let regions = vec![(0, 1003, 1004), (0, 1001, 1002), (0, 1001, 1002), (1, 0, 10)];
+        let records: Vec<Vec<u8>> = reader
+            .fetch_regions_merged(&regions)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1000\t1002"), Vec::from("chr1\t1001\t1005")]
+        );
+        assert_eq!(reader.fetch_regions(&regions).count(), 5);
+    }
+
+    #[test]
+    fn test_merge_regions() {
+        assert_eq!(
+            merge_regions(vec![(1, 5, 10), (0, 20, 30), (0, 0, 10), (0, 10, 15), (1, 11, 12)]),
+            vec![(0, 0, 15), (0, 20, 30), (1, 5, 10), (1, 11, 12)]
+        );
+    }
+
+    #[test]
+    fn bed_count() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert_eq!(reader.count(0, 0, 2000).unwrap(), 2);
+        assert_eq!(reader.count(0, 1001, 1004).unwrap(), 1);
+        assert_eq!(reader.count(0, 0, 1000).unwrap(), 0);
+        assert_eq!(reader.count(1, 0, 2000).unwrap(), 1);
+    }
+
+    #[test]
+    fn bed_fetch_large_positions() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch(0, 1000, u32::max_value()).is_ok());
+        assert_eq!(reader.records().count(), 2);
+        match reader.fetch(0, 3_000_000_000, u32::max_value()) {
+            Err(FetchError::PositionOutOfRange) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn bed_filter() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        reader.set_filter(|record| record.ends_with(b"1005"));
+        reader.fetch_all().unwrap();
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr1\t1004\t1005")]);
+        assert_eq!(reader.count(0, 0, 2000).unwrap(), 1);
+        assert_eq!(reader.count(1, 0, 2000).unwrap(), 0);
+
+        reader.clear_filter();
+        assert_eq!(reader.count(0, 0, 2000).unwrap(), 2);
+    }
+
+    #[test]
+    fn bed_from_path_and_index_data() {
+        let index = fs::read("test/test_bed3.bed.gz.tbi").unwrap();
+        let mut reader = Reader::from_path_and_index_data("test/test_bed3.bed.gz", &index)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.seqnames(), vec!["chr1", "chr2"]);
+        reader.fetch_contig("chr1").unwrap();
+        assert_eq!(reader.records().count(), 2);
+
+        assert!(Reader::from_path_and_index_data("test/test_bed3.bed.gz", b"foo").is_err());
+    }
+
+    #[test]
+    fn bed_data_url() {
+        assert_eq!(data_url(b""), b"data:;base64,");
+        assert_eq!(data_url(b"f"), b"data:;base64,Zg==");
+        assert_eq!(data_url(b"fo"), b"data:;base64,Zm8=");
+        assert_eq!(data_url(b"foo"), b"data:;base64,Zm9v");
+        assert_eq!(data_url(b"foob\xff"), b"data:;base64,Zm9vYv8=");
+    }
+
+    #[test]
+    fn bed_estimated_len() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert_eq!(reader.estimated_len(), None);
+        reader.fetch_contig("chr1").unwrap();
+        assert_eq!(reader.estimated_len(), Some(2));
+        reader.fetch(0, 1000, 1003).unwrap();
+        assert!(reader.estimated_len().unwrap() <= 2);
+        reader.fetch_all().unwrap();
+        assert_eq!(reader.estimated_len(), Some(3));
+        reader.fetch_rest().unwrap();
+        assert_eq!(reader.estimated_len(), None);
+    }
+
+    #[test]
+    fn bed_estimated_len_large() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+        {
+            let mut writer = Writer::from_path(&path, &TabixConfig::bed())
+                .ok()
+                .expect("Error opening file.");
+            for i in 0..100_000 {
+                writer
+                    .write(format!("chr1\t{}\t{}\tfeature{}", i * 10, i * 10 + 5, i).as_bytes())
+                    .unwrap();
+            }
+            writer.close().unwrap();
+        }
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+
+        reader.fetch(0, 0, 1_000_000).unwrap();
+        assert_eq!(reader.estimated_len(), Some(100_000));
+        reader.fetch(0, 0, 500_000).unwrap();
+        let estimate = reader.estimated_len().unwrap();
+        assert!(estimate > 25_000 && estimate < 75_000);
+    }
+
+    #[test]
+    fn bed_set_cache_size() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        reader.set_cache_size(10 * 1024 * 1024);
+
+        let chr1_id = reader.tid("chr1").unwrap();
+        assert!(reader.fetch(chr1_id, 1000, 1003).is_ok());
+        assert_eq!(reader.records().count(), 1);
+    }
+
+    #[test]
+    fn bed_write() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+        {
+            let mut writer = Writer::from_path(&path, &TabixConfig::from_preset("bed").unwrap())
+                .ok()
+                .expect("Error opening file.");
+            writer.write(b"#header").unwrap();
+            writer.write(b"chr1\t1001\t1002").unwrap();
+            writer.write(b"chr1\t1004\t1005").unwrap();
+            writer.write(b"chr2\t1005\t1006").unwrap();
+            writer.close().unwrap();
+        }
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header(), &vec![String::from("#header")]);
+        assert_eq!(
+            reader.seqnames(),
+            vec![String::from("chr1"), String::from("chr2")]
+        );
+        let chr1_id = reader.tid("chr1").unwrap();
+        assert!(reader.fetch(chr1_id, 1003, 1010).is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr1\t1004\t1005")]);
+    }
+
+    #[test]
+    fn bed_try_clone() {
+        let reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        let handles: Vec<thread::JoinHandle<Vec<Vec<u8>>>> = reader
+            .seqnames()
+            .into_iter()
+            .map(|contig| {
+                let mut reader = reader.try_clone().unwrap();
+                thread::spawn(move || {
+                    assert_eq!(reader.header().len(), 2);
+                    reader.fetch_contig(&contig).unwrap();
+                    reader.records().map(|r| r.unwrap()).collect()
+                })
+            })
+            .collect();
+        let records: Vec<Vec<Vec<u8>>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(records[0].len(), 2);
+        assert_eq!(records[1], vec![Vec::from("chr2\t1005\t1006")]);
+
+        let fd = fs::File::open("test/test_bed3.bed.gz").unwrap().into_raw_fd();
+        let reader = Reader::from_fd(fd, "test/test_bed3.bed.gz.tbi")
+            .ok()
+            .expect("Error opening file.");
+        assert!(reader.try_clone().is_err());
+    }
+
+    #[test]
+    fn bed_thread_pool() {
+        let pool = ThreadPool::new(2).unwrap();
+        let mut reader_a = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        let mut reader_b = Reader::from_path("test/test_bed3_b.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        reader_a.set_thread_pool(&pool).unwrap();
+        reader_b.set_thread_pool(&pool).unwrap();
+        // the readers keep the pool alive
+        drop(pool);
+
+        assert!(reader_a.fetch_contig("chr1").is_ok());
+        assert_eq!(reader_a.records().count(), 2);
+        assert!(reader_b.fetch_contig("chr1").is_ok());
+        assert_eq!(reader_b.records().count(), 2);
+    }
+
+    #[test]
+    fn bed_read_reuses_buffer() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_contig("chr1").is_ok());
+        let mut record = Vec::with_capacity(1024);
+        let ptr = record.as_ptr();
+        assert!(reader.read(&mut record).is_ok());
+        assert!(reader.read(&mut record).is_ok());
+        assert_eq!(record, Vec::from("chr1\t1004\t1005"));
+        assert_eq!(record.as_ptr(), ptr);
+        assert_eq!(record.capacity(), 1024);
+    }
+
+    #[test]
+    fn bed_seek_tell() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_all().is_ok());
+        let mut record = Vec::new();
+        assert!(reader.read(&mut record).is_ok());
+        let offset = reader.tell();
+        assert!(reader.read(&mut record).is_ok());
+        assert_eq!(record, Vec::from("chr1\t1004\t1005"));
+
+        // resume the scan from a fresh reader
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+        assert!(reader.seek(offset).is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1004\t1005"), Vec::from("chr2\t1005\t1006")]
+        );
+    }
+
+    #[test]
+    fn bed_read_ref() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_contig("chr1").is_ok());
+        assert_eq!(reader.read_ref().unwrap(), b"chr1\t1001\t1002");
+        assert_eq!(reader.read_ref().unwrap(), b"chr1\t1004\t1005");
+        assert!(reader.read_ref().unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn bed_fields() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        assert!(reader.fetch_contig("chr2").is_ok());
+        let record = reader.read_ref().unwrap();
+        assert_eq!(
+            fields(record).collect::<Vec<&[u8]>>(),
+            vec![&b"chr2"[..], &b"1005"[..], &b"1006"[..]]
+        );
+        assert_eq!(fields(record).nth(1), Some(&b"1005"[..]));
+        assert_eq!(fields(b"a\t\tb\t").count(), 4);
+        assert_eq!(fields(b"").collect::<Vec<&[u8]>>(), vec![&b""[..]]);
+    }
+
+    #[test]
+    fn bed_into_records() {
+        fn chr1_records(path: &str) -> IntoRecords<Reader> {
+            let mut reader = Reader::from_path(path)
+                .ok()
+                .expect("Error opening file.");
+            reader.fetch_contig("chr1").unwrap();
+            reader.into_records()
+        }
+
+        let records: Vec<Vec<u8>> = chr1_records("test/test_bed3.bed.gz")
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![Vec::from("chr1\t1001\t1002"), Vec::from("chr1\t1004\t1005")]
+        );
+    }
+
     #[test]
     fn bed_fetch_from_chr1_iterator_api() {
         let mut reader = Reader::from_path("test/test_bed3.bed.gz")
@@ -0,0 +1,77 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Positions that encode whether they are 0-based or 1-based.
+//!
+//! `Reader::fetch()` takes 0-based positions (as BED files), while region strings and the
+//! positions in VCF and GFF files are 1-based.  The conversion between both is explicit and
+//! checked, as `Position1(0)` and `Position0(u32::max_value())` have no counterpart:
+//!
+//! ```
+//! use rust_htslib::tbx::{Position0, Position1};
+//!
+//! assert_eq!(Position0::from_1based(Position1(1000)), Some(Position0(999)));
+//! assert_eq!(Position1::from_0based(Position0(999)), Some(Position1(1000)));
+//! assert_eq!(Position0::from_1based(Position1(0)), None);
+//! ```
+
+/// A 0-based position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position0(pub u32);
+
+/// A 1-based position, i.e., the first position is 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position1(pub u32);
+
+impl Position0 {
+    /// Convert a 1-based position, returning `None` for the invalid `Position1(0)`.
+    pub fn from_1based(pos: Position1) -> Option<Self> {
+        pos.0.checked_sub(1).map(Position0)
+    }
+}
+
+impl Position1 {
+    /// Convert a 0-based position, returning `None` if it exceeds the range of `Position1`.
+    pub fn from_0based(pos: Position0) -> Option<Self> {
+        pos.0.checked_add(1).map(Position1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tbx::{FetchError, Read, Reader};
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(Position0::from_1based(Position1(1)), Some(Position0(0)));
+        assert_eq!(Position1::from_0based(Position0(0)), Some(Position1(1)));
+        assert_eq!(
+            Position0::from_1based(Position1(42)).and_then(Position1::from_0based),
+            Some(Position1(42))
+        );
+        assert_eq!(Position0::from_1based(Position1(0)), None);
+        assert_eq!(Position1::from_0based(Position0(u32::max_value())), None);
+    }
+
+    #[test]
+    fn test_fetch_1based() {
+        let mut reader = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.");
+
+        // the record `chr1 1001 1002` covers the 1-based position 1002
+        reader.fetch_1based(0, Position1(1002), Position1(1002)).unwrap();
+        assert_eq!(reader.records().count(), 1);
+        reader.fetch_1based(0, Position1(1001), Position1(1001)).unwrap();
+        assert_eq!(reader.records().count(), 0);
+        reader.fetch_1based(0, Position1(1), Position1(1005)).unwrap();
+        assert_eq!(reader.records().count(), 2);
+        match reader.fetch_1based(0, Position1(0), Position1(1005)) {
+            Err(FetchError::PositionOutOfRange) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
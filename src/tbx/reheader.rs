@@ -0,0 +1,347 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Replacing the header of tabix-indexed files, similar to `tabix --reheader`.
+//!
+//! Only the BGZF block(s) holding the header are decompressed and compressed again, the
+//! remaining blocks are copied as they are.  As the offsets of the records change, the index
+//! of the new file is built afterwards.
+
+use libc;
+use std::ffi;
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use htslib;
+use tbx::index::{self, BuildError, TabixConfig};
+
+/// Maximal amount of uncompressed data in a BGZF block, as used by htslib.
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+/// Maximal size of a compressed BGZF block.
+const BGZF_MAX_BLOCK_SIZE: usize = 0x10000;
+
+/// Replace the header of the tabix-indexed file at `path`, writing the result to `out_path`
+/// and building its index (suffix `.tbi`).
+///
+/// The header consists of the leading lines that are skipped (`line_skip`) or start with the
+/// meta character of the index.  To append lines to the header instead of replacing it, pass
+/// the old header (see `Reader::header_bytes()`) followed by the new lines.
+///
+/// # Arguments
+///
+/// * `path` - the path of the BGZF-compressed, tabix-indexed file
+/// * `out_path` - the path to write the result to, must differ from `path`
+/// * `header` - the new header lines, without trailing newline
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use rust_htslib::tbx::{self, Read};
+///
+/// let reader = tbx::Reader::from_path(&"calls.vcf.gz").unwrap();
+/// let mut header = reader.header_bytes().to_vec();
+/// let columns = header.pop().unwrap();
+/// header.push(b"##FILTER=<ID=q10,Description=\"Quality below 10\">".to_vec());
+/// header.push(columns);
+/// tbx::reheader(&"calls.vcf.gz", &"fixed.vcf.gz", &header).unwrap();
+/// ```
+pub fn reheader<P: AsRef<Path>, Q: AsRef<Path>, L: AsRef<[u8]>>(
+    path: P,
+    out_path: Q,
+    header: &[L],
+) -> Result<(), ReheaderError> {
+    if path.as_ref() == out_path.as_ref() {
+        return Err(ReheaderError::InvalidPath);
+    }
+    let cpath = match path.as_ref().to_str() {
+        Some(p) => ffi::CString::new(p).unwrap(),
+        None => return Err(ReheaderError::InvalidPath),
+    };
+
+    let conf = {
+        let tbx = unsafe { htslib::tbx_index_load(cpath.as_ptr()) };
+        if tbx.is_null() {
+            return Err(ReheaderError::InvalidIndex);
+        }
+        let conf = TabixConfig::from_inner(unsafe { (*tbx).conf });
+        unsafe {
+            htslib::tbx_destroy(tbx);
+        }
+        conf
+    };
+
+    let mut prefix = Vec::new();
+    for line in header {
+        prefix.extend_from_slice(line.as_ref());
+        prefix.push(b'\n');
+    }
+    let data_address = try!(split_header(&cpath, &conf, &mut prefix));
+
+    let mut out = try!(
+        File::create(out_path.as_ref()).map_err(|err| ReheaderError::IoError(err.to_string()))
+    );
+    let mut chunks: Vec<&[u8]> = prefix.chunks(BGZF_BLOCK_SIZE).collect();
+    if data_address.is_none() {
+        // an empty block marks the end of the file
+        chunks.push(&[]);
+    }
+    let mut block = vec![0u8; BGZF_MAX_BLOCK_SIZE];
+    for chunk in chunks {
+        let mut len = block.len();
+        let ret = unsafe {
+            htslib::bgzf_compress(
+                block.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+                chunk.as_ptr() as *const libc::c_void,
+                chunk.len(),
+                -1,
+            )
+        };
+        if ret != 0 {
+            return Err(ReheaderError::WriteError);
+        }
+        try!(out.write_all(&block[..len]).map_err(|_| ReheaderError::WriteError));
+    }
+
+    if let Some(data_address) = data_address {
+        // copy the compressed blocks following the header, including the EOF marker
+        let mut input = try!(
+            File::open(path.as_ref()).map_err(|err| ReheaderError::IoError(err.to_string()))
+        );
+        try!(
+            input
+                .seek(SeekFrom::Start(data_address))
+                .map_err(|_| ReheaderError::ReadError)
+        );
+        try!(io::copy(&mut input, &mut out).map_err(|_| ReheaderError::WriteError));
+    }
+    try!(out.flush().map_err(|_| ReheaderError::WriteError));
+    drop(out);
+
+    try!(index::build(out_path, &conf));
+    Ok(())
+}
+
+/// Skip the header of the file at `path` and append the uncompressed remainder of the block
+/// holding the first record to `prefix`.
+///
+/// Returns the file offset of the following compressed block or `None` if the file has no
+/// records.
+fn split_header(
+    path: &ffi::CStr,
+    conf: &TabixConfig,
+    prefix: &mut Vec<u8>,
+) -> Result<Option<u64>, ReheaderError> {
+    let fp = unsafe { htslib::bgzf_open(path.as_ptr(), ffi::CString::new("r").unwrap().as_ptr()) };
+    if fp.is_null() {
+        return Err(ReheaderError::InvalidPath);
+    }
+    if unsafe { htslib::bgzf_compression(fp) } != htslib::htsCompression_bgzf as i32 {
+        unsafe {
+            htslib::bgzf_close(fp);
+        }
+        return Err(ReheaderError::NotBgzf);
+    }
+
+    let mut buf = htslib::kstring_t {
+        l: 0,
+        m: 0,
+        s: ptr::null_mut(),
+    };
+    let mut result = Ok(None);
+    let mut num_lines = 0;
+    let mut offset;
+    loop {
+        offset = unsafe { ((*fp).block_address << 16) | ((*fp).block_offset as i64 & 0xFFFF) };
+        let ret = unsafe { htslib::bgzf_getline(fp, b'\n' as i32, &mut buf) };
+        if ret < -1 {
+            result = Err(ReheaderError::ReadError);
+            break;
+        } else if ret == -1 {
+            break;
+        }
+        let line = unsafe { slice::from_raw_parts(buf.s as *const u8, buf.l as usize) };
+        if num_lines >= conf.line_skip() && line.first() != Some(&conf.meta_char()) {
+            break;
+        }
+        num_lines += 1;
+    }
+
+    if result.is_ok() {
+        result = unsafe { read_block_rest(fp, offset, prefix) };
+    }
+    unsafe {
+        htslib::bgzf_close(fp);
+        libc::free(buf.s as *mut libc::c_void);
+    }
+    result
+}
+
+/// Seek to `offset` and append the data up to the end of its block to `prefix`, returning the
+/// file offset of the next block or `None` if there is no data left.
+unsafe fn read_block_rest(
+    fp: *mut htslib::BGZF,
+    offset: i64,
+    prefix: &mut Vec<u8>,
+) -> Result<Option<u64>, ReheaderError> {
+    if htslib::bgzf_seek(fp, offset, libc::SEEK_SET) < 0 {
+        return Err(ReheaderError::ReadError);
+    }
+    // reading the first byte loads the block, after reading up to its end, `block_address`
+    // points to the next block
+    let mut byte = 0u8;
+    match htslib::bgzf_read(fp, &mut byte as *mut u8 as *mut libc::c_void, 1) {
+        // no records, only the EOF marker has to be written
+        0 => return Ok(None),
+        1 => prefix.push(byte),
+        _ => return Err(ReheaderError::ReadError),
+    }
+    let rest = ((*fp).block_length - (*fp).block_offset) as usize;
+    let len = prefix.len();
+    prefix.resize(len + rest, 0);
+    let ret = htslib::bgzf_read(fp, prefix[len..].as_mut_ptr() as *mut libc::c_void, rest);
+    if ret < 0 || ret as usize != rest {
+        return Err(ReheaderError::ReadError);
+    }
+    Ok(Some((*fp).block_address as u64))
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ReheaderError {
+        InvalidPath {
+            description("invalid path")
+        }
+        InvalidIndex {
+            description("invalid or missing tabix index")
+        }
+        NotBgzf {
+            description("file is not BGZF-compressed")
+        }
+        ReadError {
+            description("error reading file")
+        }
+        WriteError {
+            description("error writing file")
+        }
+        IoError(msg: String) {
+            description("I/O error")
+            display("I/O error: {}", msg)
+        }
+        BuildError(err: BuildError) {
+            from()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use super::*;
+    use tbx::{Read, Reader, Writer};
+
+    #[test]
+    fn test_reheader() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+
+        reheader("test/test_bed3.bed.gz", &path, &[b"#baz"]).unwrap();
+        assert!(tmp.path().join("test.bed.gz.tbi").exists());
+
+        let mut reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header, vec![String::from("#baz")]);
+        let chr1_id = reader.tid("chr1").unwrap();
+        assert!(reader.fetch(chr1_id, 1000, 1003).is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![Vec::from("chr1\t1001\t1002")]);
+        assert!(reader.fetch_all().is_ok());
+        assert_eq!(reader.records().count(), 3);
+    }
+
+    #[test]
+    fn test_reheader_append() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+        let mut header = Reader::from_path("test/test_bed3.bed.gz")
+            .ok()
+            .expect("Error opening file.")
+            .header_bytes()
+            .to_vec();
+        header.push(b"#baz".to_vec());
+
+        reheader("test/test_bed3.bed.gz", &path, &header).unwrap();
+
+        let reader = Reader::from_path(&path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header, vec!["#foo", "#bar", "#baz"]);
+    }
+
+    #[test]
+    fn test_reheader_multiple_blocks() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("test.bed.gz");
+        let out_path = tmp.path().join("out.bed.gz");
+        {
+            let mut writer = Writer::from_path(&path, &TabixConfig::bed())
+                .ok()
+                .expect("Error opening file.");
+            writer.write(b"#foo").unwrap();
+            for i in 0..50_000 {
+                writer
+                    .write(format!("chr1\t{}\t{}", i * 10, i * 10 + 5).as_bytes())
+                    .unwrap();
+            }
+            writer.close().unwrap();
+        }
+        let long_line = vec![b'x'; 2 * BGZF_BLOCK_SIZE];
+        let header = vec![b"#bar".to_vec(), [&b"#"[..], &long_line[..]].concat()];
+
+        reheader(&path, &out_path, &header).unwrap();
+
+        let mut reader = Reader::from_path(&out_path)
+            .ok()
+            .expect("Error opening file.");
+        assert_eq!(reader.header_bytes(), &header[..]);
+        assert!(reader.fetch_all().is_ok());
+        let records: Vec<Vec<u8>> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 50_000);
+        assert_eq!(records[0], b"chr1\t0\t5");
+        assert_eq!(records[49_999], b"chr1\t499990\t499995");
+        let chr1_id = reader.tid("chr1").unwrap();
+        assert!(reader.fetch(chr1_id, 250_000, 250_010).is_ok());
+        assert_eq!(reader.records().count(), 1);
+    }
+
+    #[test]
+    fn test_reheader_same_path() {
+        match reheader("test/test_bed3.bed.gz", "test/test_bed3.bed.gz", &[b"#baz"]) {
+            Err(ReheaderError::InvalidPath) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+    #[test]
+    fn test_reheader_missing_out_dir() {
+        let tmp = tempdir::TempDir::new("rust-htslib")
+            .ok()
+            .expect("Cannot create temp dir");
+        let path = tmp.path().join("missing").join("test.bed.gz");
+        match reheader("test/test_bed3.bed.gz", &path, &[b"#baz"]) {
+            Err(ReheaderError::IoError(_)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lightweight view of VCF text records fetched from tabix-indexed VCF files.
+//!
+//! The view borrows from the line and only splits it into its columns, the INFO, FORMAT and
+//! sample columns are kept as raw slices.  For full access to the values, use the `bcf`
+//! module instead.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx::{self, vcf, Read};
+//!
+//! let mut reader = tbx::Reader::from_path(&"calls.vcf.gz").unwrap();
+//! reader.fetch_region("chr1:1,000-2,000").unwrap();
+//! let mut line = Vec::new();
+//! while reader.read(&mut line).is_ok() {
+//!     let record = vcf::VcfRecord::parse(&line).unwrap();
+//!     println!("{} {:?}", record.pos, record.info_field(b"DP"));
+//! }
+//! ```
+
+use std::str;
+
+/// View of a VCF record given as text line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcfRecord<'a> {
+    /// Name of the chromosome.
+    pub chrom: &'a [u8],
+    /// 1-based position.
+    pub pos: u64,
+    /// The IDs of the record, empty for `.`.
+    pub id: Vec<&'a [u8]>,
+    /// The reference allele.
+    pub ref_allele: &'a [u8],
+    /// The alternative alleles, empty for `.`.
+    pub alt: Vec<&'a [u8]>,
+    /// The quality, `None` for `.`.
+    pub qual: Option<f32>,
+    /// The filters, empty for `.`.
+    pub filter: Vec<&'a [u8]>,
+    /// The raw INFO column.
+    pub info: &'a [u8],
+    /// The raw FORMAT column, if present.
+    pub format: Option<&'a [u8]>,
+    /// The raw sample columns.
+    pub samples: Vec<&'a [u8]>,
+}
+
+impl<'a> VcfRecord<'a> {
+    /// Parse the columns of a VCF line, without trailing newline.
+    pub fn parse(line: &'a [u8]) -> Result<Self, VcfError> {
+        let mut fields = line.split(|&c| c == b'\t');
+        let mut next = || fields.next().ok_or(VcfError::MissingColumn);
+        let chrom = try!(next());
+        let pos = try!(
+            str::from_utf8(try!(next()))
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .ok_or(VcfError::InvalidPosition)
+        );
+        let id = list(try!(next()), b';');
+        let ref_allele = try!(next());
+        let alt = list(try!(next()), b',');
+        let qual = match try!(next()) {
+            b"." => None,
+            field => Some(try!(
+                str::from_utf8(field)
+                    .ok()
+                    .and_then(|s| s.parse::<f32>().ok())
+                    .ok_or(VcfError::InvalidQuality)
+            )),
+        };
+        let filter = list(try!(next()), b';');
+        let info = try!(next());
+        let format = next().ok();
+        let mut samples = Vec::new();
+        while let Ok(sample) = next() {
+            samples.push(sample);
+        }
+
+        Ok(VcfRecord {
+            chrom: chrom,
+            pos: pos,
+            id: id,
+            ref_allele: ref_allele,
+            alt: alt,
+            qual: qual,
+            filter: filter,
+            info: info,
+            format: format,
+            samples: samples,
+        })
+    }
+
+    /// Whether the record passed all filters, i.e., the FILTER column is `PASS`.
+    pub fn is_pass(&self) -> bool {
+        self.filter.len() == 1 && self.filter[0] == b"PASS"
+    }
+
+    /// Look up a field in the INFO column.
+    ///
+    /// Returns `None` if the field is missing, `Some(None)` for flags and `Some(Some(value))`
+    /// with the raw value otherwise.
+    pub fn info_field(&self, key: &[u8]) -> Option<Option<&'a [u8]>> {
+        if self.info == b"." {
+            return None;
+        }
+        self.info
+            .split(|&c| c == b';')
+            .filter_map(|field| {
+                let mut parts = field.splitn(2, |&c| c == b'=');
+                if parts.next() == Some(key) {
+                    Some(parts.next())
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+}
+
+/// Split a column into its values, yielding an empty list for `.`.
+fn list(field: &[u8], sep: u8) -> Vec<&[u8]> {
+    if field == b"." {
+        Vec::new()
+    } else {
+        field.split(|&c| c == sep).collect()
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum VcfError {
+        MissingColumn {
+            description("VCF record has less than eight columns")
+        }
+        InvalidPosition {
+            description("invalid position in VCF record")
+        }
+        InvalidQuality {
+            description("invalid quality in VCF record")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let line = b"1\t10\trs1;rs2\tA\tC,T\t12.5\tPASS\tDP=10;DB;AF=0.5,0.1\tGT:DP\t0/1:5\t1/1:5";
+        let record = VcfRecord::parse(line).unwrap();
+        assert_eq!(record.chrom, b"1");
+        assert_eq!(record.pos, 10);
+        assert_eq!(record.id, vec![b"rs1", b"rs2"]);
+        assert_eq!(record.ref_allele, b"A");
+        assert_eq!(record.alt, vec![b"C", b"T"]);
+        assert_eq!(record.qual, Some(12.5));
+        assert!(record.is_pass());
+        assert_eq!(record.info_field(b"DP"), Some(Some(&b"10"[..])));
+        assert_eq!(record.info_field(b"DB"), Some(None));
+        assert_eq!(record.info_field(b"AF"), Some(Some(&b"0.5,0.1"[..])));
+        assert_eq!(record.info_field(b"D"), None);
+        assert_eq!(record.format, Some(&b"GT:DP"[..]));
+        assert_eq!(record.samples, vec![&b"0/1:5"[..], &b"1/1:5"[..]]);
+    }
+
+    #[test]
+    fn test_parse_sites_only() {
+        let record = VcfRecord::parse(b"1\t10\t.\tA\t.\t.\tq10\t.").unwrap();
+        assert!(record.id.is_empty());
+        assert!(record.alt.is_empty());
+        assert_eq!(record.qual, None);
+        assert!(!record.is_pass());
+        assert_eq!(record.info_field(b"DP"), None);
+        assert_eq!(record.format, None);
+        assert!(record.samples.is_empty());
+
+        assert!(VcfRecord::parse(b"1\t10\t.\tA\t.\t.\t.").is_err());
+        assert!(VcfRecord::parse(b"1\tfoo\t.\tA\t.\t.\t.\t.").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2018 Manuel Holtgrewe, Berlin Institute of Health.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Module for thread pools that can be shared between multiple readers and writers.
+//!
+//! Instead of starting separate (de)compression threads for each file with `set_threads()`,
+//! a single pool can be used for all files, as `samtools` does with its `--threads` option.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rust_htslib::tbx;
+//! use rust_htslib::tpool::ThreadPool;
+//!
+//! let pool = ThreadPool::new(4).unwrap();
+//! let mut genes = tbx::Reader::from_path(&"genes.bed.gz").unwrap();
+//! let mut repeats = tbx::Reader::from_path(&"repeats.bed.gz").unwrap();
+//! genes.set_thread_pool(&pool).unwrap();
+//! repeats.set_thread_pool(&pool).unwrap();
+//! ```
+
+use std::sync::Arc;
+
+use htslib;
+
+/// A pool of threads for BGZF (de)compression.
+///
+/// Cloning a `ThreadPool` yields a handle to the same pool, the threads are stopped when the
+/// last handle (including the ones held by readers and writers) is dropped.
+#[derive(Debug, Clone)]
+pub struct ThreadPool {
+    inner: Arc<InnerThreadPool>,
+}
+
+impl ThreadPool {
+    /// Create a new thread pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_threads` - number of threads in the pool, must be `> 0`
+    pub fn new(n_threads: u32) -> Result<Self, ThreadPoolError> {
+        assert!(n_threads > 0, "n_threads must be > 0");
+
+        let pool = unsafe { htslib::hts_tpool_init(n_threads as i32) };
+        if pool.is_null() {
+            Err(ThreadPoolError::Some)
+        } else {
+            Ok(ThreadPool {
+                inner: Arc::new(InnerThreadPool {
+                    tpool: htslib::htsThreadPool {
+                        pool: pool,
+                        qsize: 0,
+                    },
+                }),
+            })
+        }
+    }
+
+    /// Return pointer to the `htsThreadPool` to pass to htslib.
+    pub(crate) fn inner(&self) -> *mut htslib::htsThreadPool {
+        &self.inner.tpool as *const htslib::htsThreadPool as *mut htslib::htsThreadPool
+    }
+}
+
+#[derive(Debug)]
+struct InnerThreadPool {
+    tpool: htslib::htsThreadPool,
+}
+
+unsafe impl Send for InnerThreadPool {}
+unsafe impl Sync for InnerThreadPool {}
+
+impl Drop for InnerThreadPool {
+    fn drop(&mut self) {
+        unsafe {
+            htslib::hts_tpool_destroy(self.tpool.pool);
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone)]
+    pub enum ThreadPoolError {
+        Some {
+            description("error creating thread pool")
+        }
+    }
+}
@@ -0,0 +1,13 @@
+#include "wrapper.h"
+
+kbitset_t *wrap_kbs_init(size_t ni) {
+    return kbs_init(ni);
+}
+
+void wrap_kbs_insert(kbitset_t *bs, int i) {
+    kbs_insert(bs, i);
+}
+
+void wrap_kbs_destroy(kbitset_t *bs) {
+    kbs_destroy(bs);
+}
@@ -1,7 +1,15 @@
 #include "htslib/htslib/hts.h"
+#include "htslib/htslib/hfile.h"
 #include "htslib/htslib/vcf.h"
 #include "htslib/htslib/sam.h"
 #include "htslib/htslib/bgzf.h"
 #include "htslib/htslib/vcfutils.h"
 #include "htslib/htslib/tbx.h"
 #include "htslib/htslib/synced_bcf_reader.h"
+#include "htslib/htslib/faidx.h"
+#include "htslib/htslib/kbitset.h"
+
+// Wrappers for the inline functions of kbitset.h, which are compiled from wrapper.c.
+kbitset_t *wrap_kbs_init(size_t ni);
+void wrap_kbs_insert(kbitset_t *bs, int i);
+void wrap_kbs_destroy(kbitset_t *bs);